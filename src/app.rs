@@ -1,114 +1,429 @@
+mod command_palette;
+
 // Screens
 mod bin_screen;
+mod inspector_screen;
 mod main_screen;
 mod notes_screen;
+mod notifications_screen;
 mod projects_screen;
+mod secret_screen;
 mod todos_screen;
 
+pub mod event_log;
+pub mod navigation;
+pub mod notifications;
+pub mod screen;
+pub mod store;
+
+use super::tui::mouse::{Mouse, MouseTracker};
 use super::tui::TUIAction;
-use crate::phoenix::event::PhoenixEvent;
-use bin_screen::BinScreen;
+use crate::config::Profile;
+use crate::keymap::{Action, Context, Keymap};
+use crate::phoenix::connection::{ConnectionState, ConnectionStats};
+use crate::phoenix::event::{PhoenixEvent, SocketEvent};
+use crate::phoenix::presence::Presence;
+use crate::phoenix::topic::TopicCommand;
+use crate::phoenix::upload::Upload;
+use crate::secret::{Secret, SecretError};
+use crate::sqlite::outbox::Outbox;
+use crate::theme::Theme;
+use chrono::Local;
 use cli_log::info;
-use crossterm::event::KeyEvent;
+use command_palette::{Command, CommandPalette, PaletteAction, PaletteItem};
+use crossterm::event::{KeyEvent, MouseEvent};
+use event_log::{EventLog, LoggedSender};
 use main_screen::MainScreen;
-use notes_screen::NotesScreen;
-use projects_screen::ProjectsScreen;
-use ratatui::Frame;
-use todos_screen::TodosScreen;
+use navigation::Navigation;
+use notifications::Notifier;
+use ratatui::{
+    layout::{Constraint, Direction, Flex, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use screen::{Screen, ScreenInfo, SCREENS};
+use secret_screen::SecretScreen;
+use std::rc::Rc;
+use store::Store;
 use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::watch;
 
-pub struct App {
-    pub screen_type: ScreenType,
-    pub screen: Screens,
-    pub socket_receiver: Receiver<PhoenixEvent>,
-    pub screen_sender: Sender<PhoenixEvent>,
-}
+/// Widest a toast gets.
+const TOAST_WIDTH: u16 = 48;
 
-#[derive(Debug, PartialEq)]
-pub enum ScreenType {
-    Main,
-    Todos,
-    Bin,
-    Notes,
-    Projects,
-}
-
-pub enum Screens {
-    Main(MainScreen),
-    Todos(TodosScreen),
-    Bin(BinScreen),
-    Notes(NotesScreen),
-    Projects(ProjectsScreen),
+pub struct App {
+    /// Open screens. `b` goes back to the one below.
+    pub navigation: Navigation<Box<dyn Screen>>,
+    pub socket_receiver: Receiver<SocketEvent>,
+    /// Logs what it sends in `event_log`.
+    pub screen_sender: LoggedSender<PhoenixEvent>,
+    pub topic_sender: Sender<TopicCommand>,
+    pub upload_sender: LoggedSender<Upload>,
+    pub connection_state: watch::Receiver<ConnectionState>,
+    /// Latency, last heartbeat and pending requests, shown in the status bar.
+    pub connection_stats: watch::Receiver<ConnectionStats>,
+    /// Other sessions on the joined topics.
+    pub presence: watch::Receiver<Presence>,
+    /// Updated with every socket event, whichever screen is open.
+    pub store: watch::Sender<Store>,
+    /// Every event sent and received, for the inspector.
+    pub event_log: watch::Sender<EventLog>,
+    /// Toasts over every screen, and their history.
+    pub notifier: Notifier,
+    /// Server the app is connected to.
+    pub profile: Profile,
+    /// Requests queued while offline, shared by the bin screens. `None` when it can't be opened,
+    /// which is notified when the app starts.
+    pub outbox: Option<Rc<Outbox>>,
+    /// Keys of every action, handed to the screens.
+    keymap: Keymap,
+    /// Colors the screens are opened with.
+    theme: Theme,
+    /// Secret `Phoenix` connects with.
+    secret_sender: watch::Sender<Option<Secret>>,
+    /// Shown over every screen while no usable secret is set.
+    secret_screen: Option<SecretScreen>,
+    /// Keys of the current screen, shown over it with F1.
+    show_keybindings: bool,
+    /// Opened over every screen with Ctrl-P.
+    palette: Option<CommandPalette>,
+    mouse: MouseTracker,
+    reconnecting: bool,
+    /// Last message from the socket that could not be decoded.
+    diagnostic: Option<String>,
+    /// Whether the last frame showed something that changes with time, like a toast.
+    changes_with_time: bool,
 }
 
 pub enum AppActions {
-    ChangeScreen(ScreenType),
+    /// Open a screen over the current one.
+    Push(&'static ScreenInfo),
+    /// Go to a screen and show one of its entities, like the command palette does.
+    Open(&'static ScreenInfo, String),
+    /// Close the current screen and return to the one below.
+    Back,
+    /// Connect with a secret entered in the app.
+    UseSecret(Secret),
     Quit,
 }
 
-impl Screens {
-    fn render(&mut self, f: &mut Frame) {
-        match self {
-            Screens::Main(main_screen) => main_screen.render(f),
-            Screens::Todos(todos_screen) => todos_screen.render(f),
-            Screens::Notes(notes_screen) => notes_screen.render(f),
-            Screens::Bin(bin_screen) => bin_screen.render(f),
-            Screens::Projects(projects_screen) => projects_screen.render(f),
-        }
-    }
-
-    fn handle_key(&mut self, e: KeyEvent) -> Option<AppActions> {
-        match self {
-            Screens::Main(main_screen) => main_screen.handle_key(e),
-            Screens::Todos(todos_screen) => todos_screen.handle_key(e),
-            Screens::Notes(notes_screen) => notes_screen.handle_key(e),
-            Screens::Bin(bin_screen) => bin_screen.handle_key(e),
-            Screens::Projects(projects_screen) => projects_screen.handle_key(e),
-        }
-    }
-
-    fn handle_socket_event(&mut self, event: PhoenixEvent) {
-        match self {
-            Screens::Main(main_screen) => main_screen.handle_socket_event(event),
-            Screens::Todos(todos_screen) => todos_screen.handle_socket_event(event),
-            Screens::Notes(notes_screen) => notes_screen.handle_socket_event(event),
-            Screens::Bin(bin_screen) => bin_screen.handle_socket_event(event),
-            Screens::Projects(projects_screen) => projects_screen.handle_socket_event(event),
-        }
-    }
+/// App side of the channels connecting it to the transport.
+pub struct AppChannels {
+    pub socket_receiver: Receiver<SocketEvent>,
+    pub screen_sender: Sender<PhoenixEvent>,
+    pub topic_sender: Sender<TopicCommand>,
+    pub upload_sender: Sender<Upload>,
+    pub connection_state: watch::Receiver<ConnectionState>,
+    pub connection_stats: watch::Receiver<ConnectionStats>,
+    pub presence: watch::Receiver<Presence>,
+    pub secret_sender: watch::Sender<Option<Secret>>,
 }
 
 impl App {
     pub fn new(
-        socket_receiver: Receiver<PhoenixEvent>,
-        screen_sender: Sender<PhoenixEvent>,
+        channels: AppChannels,
+        profile: Profile,
+        keymap: Keymap,
+        theme: Theme,
+        outbox: rusqlite::Result<Outbox>,
     ) -> Self {
+        let AppChannels {
+            socket_receiver,
+            screen_sender,
+            topic_sender,
+            upload_sender,
+            connection_state,
+            connection_stats,
+            presence,
+            secret_sender,
+        } = channels;
+        let event_log = watch::Sender::new(EventLog::new());
+        let screen_sender = LoggedSender::new(screen_sender, event_log.clone(), EventLog::sent);
+        let upload_sender = LoggedSender::new(upload_sender, event_log.clone(), EventLog::upload);
+        let store = watch::Sender::new(Store::default());
+        let main_screen = MainScreen::new(
+            screen_sender.clone(),
+            topic_sender.clone(),
+            store.subscribe(),
+            connection_state.clone(),
+            connection_stats.clone(),
+            theme,
+        );
+        // The root screen is never pushed, nor closed.
+        for feature in main_screen.info().topics() {
+            let _ = topic_sender.try_send(TopicCommand::Join(feature));
+        }
+        let notifier = Notifier::new();
+        let outbox = match outbox {
+            Ok(outbox) => Some(Rc::new(outbox)),
+            Err(e) => {
+                notifier.error(format!("Cannot open the outbox: {e}"));
+                None
+            }
+        };
+
         Self {
-            screen: Screens::Main(MainScreen::new()),
-            screen_type: ScreenType::Main,
+            navigation: Navigation::new(Box::new(main_screen)),
             socket_receiver,
             screen_sender,
+            topic_sender,
+            upload_sender,
+            connection_state,
+            connection_stats,
+            presence,
+            store,
+            event_log,
+            notifier,
+            profile,
+            outbox,
+            keymap,
+            theme,
+            secret_sender,
+            secret_screen: None,
+            show_keybindings: false,
+            palette: None,
+            mouse: MouseTracker::default(),
+            reconnecting: false,
+            diagnostic: None,
+            changes_with_time: false,
         }
     }
 
     pub fn render(&mut self, f: &mut Frame) {
-        self.screen.render(f);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(f.area());
+
+        match &mut self.secret_screen {
+            Some(secret_screen) => secret_screen.render(f, chunks[0], &self.keymap),
+            None => self
+                .navigation
+                .current_mut()
+                .render(f, chunks[0], &self.keymap),
+        }
+        if self.show_keybindings {
+            self.keybindings(chunks[0], f);
+        }
+        self.toasts(chunks[0], f);
+        if let Some(palette) = &mut self.palette {
+            palette.render(f, chunks[0]);
+        }
+        self.status_bar(chunks[1], f);
+
+        let screen = match &self.secret_screen {
+            Some(_) => false,
+            None => self.navigation.current().changes_with_time(),
+        };
+        self.changes_with_time = screen || self.notifier.borrow().toasts().next().is_some();
+    }
+
+    /// Whether the screen must be redrawn as time passes, even if nothing else happens. Stays
+    /// true for the frame after a toast expires, which removes it.
+    pub fn changes_with_time(&self) -> bool {
+        self.changes_with_time
+    }
+
+    /// Keys of the current screen, over it.
+    fn keybindings(&self, area: Rect, f: &mut Frame) {
+        let screen = self.navigation.current();
+        let lines: Vec<Line> = screen
+            .keybindings()
+            .iter()
+            .map(|keybinding| {
+                Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "{:>14}  ",
+                            self.keymap.label(keybinding.context, keybinding.action)
+                        ),
+                        self.theme.accent,
+                    ),
+                    Span::raw(keybinding.description),
+                ])
+            })
+            .collect();
+
+        let [area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Max(66)])
+            .flex(Flex::Center)
+            .areas(area);
+        let block = Block::new()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .border_style(self.theme.focused_border)
+            .title_style(self.theme.title)
+            .title(format!("{} keys", screen.title()));
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    /// Recent notifications, newest on top, in the top right corner.
+    fn toasts(&self, area: Rect, f: &mut Frame) {
+        let notifications = self.notifier.borrow();
+        let width = area.width.min(TOAST_WIDTH);
+        let mut y = area.y;
+        for notification in notifications.toasts() {
+            let message = format!("{} {}", notification.level, notification.message);
+            // Wrapped to the width inside the borders.
+            let lines = (message.chars().count() as u16).div_ceil(width.saturating_sub(2).max(1));
+            let height = lines.max(1) + 2;
+            if y + height > area.bottom() {
+                break;
+            }
+            let toast = Rect::new(area.right() - width, y, width, height);
+            let style = notification.level.style(&self.theme);
+            let block = Block::new()
+                .border_type(BorderType::Rounded)
+                .borders(Borders::ALL)
+                .border_style(style);
+            f.render_widget(Clear, toast);
+            f.render_widget(
+                Paragraph::new(Span::styled(message, style))
+                    .wrap(Wrap { trim: true })
+                    .block(block),
+                toast,
+            );
+            y += height;
+        }
+    }
+
+    /// Connection state and health, other sessions and the last diagnostic, on every screen.
+    fn status_bar(&self, area: Rect, f: &mut Frame) {
+        let state = self.connection_state.borrow();
+        let theme = &self.theme;
+        let style = connection_style(&state, theme);
+
+        let mut spans = vec![
+            Span::styled(" ● ", style),
+            Span::styled(state.to_string(), theme.muted),
+        ];
+        let stats = self.connection_stats.borrow();
+        if let Some(latency) = stats.latency {
+            spans.push(Span::styled(
+                format!("  ⏱ {}ms", latency.as_millis()),
+                theme.muted,
+            ));
+        }
+        // The time rather than the age, which would need a redraw every second.
+        if let Some(last_heartbeat) = stats.last_heartbeat {
+            let at = Local::now() - last_heartbeat.elapsed();
+            spans.push(Span::styled(
+                format!("  ♥ {}", at.format("%H:%M:%S")),
+                theme.muted,
+            ));
+        }
+        if stats.pending > 0 {
+            spans.push(Span::styled(
+                format!("  ⇅ {} pending", stats.pending),
+                theme.warning,
+            ));
+        }
+        let sessions = self.presence.borrow().sessions();
+        if !sessions.is_empty() {
+            let names: Vec<&str> = sessions
+                .iter()
+                .map(|session| session.name.as_str())
+                .collect();
+            spans.push(Span::styled(
+                format!("  ⧉ {}", names.join(", ")),
+                theme.accent,
+            ));
+        }
+        if let Some(diagnostic) = &self.diagnostic {
+            spans.push(Span::styled(format!("  ⚠ {diagnostic}"), theme.warning));
+        }
+        let line = Line::from(spans);
+        f.render_widget(Paragraph::new(line), area);
     }
 
     pub fn handle_key(&mut self, key: KeyEvent) -> Option<TUIAction> {
-        let key_response = self.screen.handle_key(key);
-        match key_response {
-            Some(AppActions::ChangeScreen(screen_type)) => {
-                let screen = match screen_type {
-                    ScreenType::Main => Screens::Main(MainScreen::new()),
-                    ScreenType::Notes => {
-                        Screens::Notes(NotesScreen::new(self.screen_sender.clone()))
-                    }
-                    ScreenType::Bin => Screens::Bin(BinScreen::new(self.screen_sender.clone())),
-                    ScreenType::Projects => Screens::Projects(ProjectsScreen::new()),
-                    ScreenType::Todos => Screens::Todos(TodosScreen::new()),
-                };
-                self.change_screen(screen_type, screen);
+        // Like in a shell, whatever has the keyboard.
+        match self.keymap.action(&[Context::Global], &key) {
+            Some(Action::Interrupt) => return Some(TUIAction::Quit),
+            Some(Action::Suspend) => return Some(TUIAction::Suspend),
+            _ => {}
+        }
+        if self.show_keybindings {
+            self.show_keybindings = false;
+            return None;
+        }
+        if let Some(palette) = &mut self.palette {
+            return match palette.handle_key(&self.keymap, key) {
+                Some(PaletteAction::Close) => {
+                    self.palette = None;
+                    None
+                }
+                Some(PaletteAction::Run(command)) => {
+                    self.palette = None;
+                    self.run_command(command)
+                }
+                None => None,
+            };
+        }
+        if let Some(secret_screen) = &mut self.secret_screen {
+            let action = secret_screen.handle_key(&self.keymap, key);
+            return self.handle_action(action);
+        }
+        let key_response = match self.keymap.action(&[Context::Global], &key) {
+            Some(Action::Help) => {
+                self.show_keybindings = true;
+                None
+            }
+            Some(Action::Inspector) => {
+                self.toggle_screen(&inspector_screen::INFO);
+                None
+            }
+            Some(Action::Notifications) => {
+                self.toggle_screen(&notifications_screen::INFO);
+                None
+            }
+            Some(Action::Palette) => {
+                self.palette = Some(CommandPalette::new(self.palette_items(), self.theme));
+                None
+            }
+            _ => self.navigation.current_mut().handle_key(&self.keymap, key),
+        };
+        self.handle_action(key_response)
+    }
+
+    pub fn handle_mouse(&mut self, e: MouseEvent) -> Option<TUIAction> {
+        let mouse = self.mouse.mouse(e)?;
+        if self.show_keybindings {
+            if let Mouse::Click(_) = mouse {
+                self.show_keybindings = false;
+            }
+            return None;
+        }
+        // Overlays are used with the keyboard.
+        if self.palette.is_some() || self.secret_screen.is_some() {
+            return None;
+        }
+        let action = self.navigation.current_mut().handle_mouse(mouse);
+        self.handle_action(action)
+    }
+
+    fn handle_action(&mut self, action: Option<AppActions>) -> Option<TUIAction> {
+        match action {
+            Some(AppActions::Push(info)) => {
+                self.push_screen((info.open)(self));
+                None
+            }
+            Some(AppActions::Open(info, id)) => {
+                self.open_entity(info, &id);
+                None
+            }
+            Some(AppActions::Back) => {
+                self.pop_screen();
+                None
+            }
+            Some(AppActions::UseSecret(secret)) => {
+                self.secret_screen = None;
+                self.secret_sender.send_replace(Some(secret));
                 None
             }
             Some(AppActions::Quit) => Some(TUIAction::Quit),
@@ -116,25 +431,320 @@ impl App {
         }
     }
 
-    pub fn change_screen(&mut self, screen_type: ScreenType, screen: Screens) {
-        self.screen_type = screen_type;
-        self.screen = screen;
+    /// Commands of the current screen, every screen and what they list, and app-wide commands.
+    fn palette_items(&self) -> Vec<PaletteItem> {
+        let screen = self.navigation.current();
+        let mut items: Vec<PaletteItem> = screen
+            .keybindings()
+            .iter()
+            .filter_map(|keybinding| {
+                let key = self
+                    .keymap
+                    .keys(keybinding.context, keybinding.action)
+                    .first()?;
+                Some(PaletteItem {
+                    label: capitalize(keybinding.description),
+                    detail: format!("{} · {key}", screen.title()),
+                    command: Command::Key(key.event()),
+                })
+            })
+            .collect();
+        items.extend(SCREENS.iter().map(|info| PaletteItem {
+            label: format!("Go to {}", info.title),
+            detail: String::new(),
+            command: Command::Open(info),
+        }));
+        items.push(PaletteItem {
+            label: "Reconnect".to_string(),
+            detail: self.connection_state.borrow().to_string(),
+            command: Command::Reconnect,
+        });
+        items.push(PaletteItem {
+            label: "Quit".to_string(),
+            detail: String::new(),
+            command: Command::Quit,
+        });
+        for info in SCREENS {
+            items.extend((info.entities)(self).into_iter().map(|entity| PaletteItem {
+                label: entity.title,
+                detail: info.title.to_string(),
+                command: Command::OpenEntity(info, entity.id),
+            }));
+        }
+        items
     }
 
-    pub fn receive_socket_events(&mut self) {
-        if let Ok(event) = self.socket_receiver.try_recv() {
-            match (event.for_screen(), &self.screen_type) {
-                (payload_screen_type, screen_type) if payload_screen_type == *screen_type => {
-                    self.screen.handle_socket_event(event)
-                }
+    fn run_command(&mut self, command: Command) -> Option<TUIAction> {
+        match command {
+            Command::Key(key) => {
+                let action = self.navigation.current_mut().handle_key(&self.keymap, key);
+                self.handle_action(action)
+            }
+            Command::Open(info) => {
+                self.go_to(info);
+                None
+            }
+            Command::OpenEntity(info, id) => {
+                self.open_entity(info, &id);
+                None
+            }
+            Command::Reconnect => {
+                let _ = self.topic_sender.try_send(TopicCommand::Reconnect);
+                None
+            }
+            Command::Quit => Some(TUIAction::Quit),
+        }
+    }
+
+    /// Go back to the screen if it is open, otherwise open it over the current one.
+    fn go_to(&mut self, info: &'static ScreenInfo) {
+        let is_open = self
+            .navigation
+            .iter()
+            .any(|screen| screen.info().id == info.id);
+        if is_open {
+            while self.navigation.current().info().id != info.id {
+                self.pop_screen();
+            }
+        } else {
+            self.push_screen((info.open)(self));
+        }
+    }
+
+    fn open_entity(&mut self, info: &'static ScreenInfo, id: &str) {
+        self.go_to(info);
+        self.navigation.current_mut().open_entity(id);
+    }
+
+    /// Ask for the secret instead of showing the current screen.
+    pub fn request_secret(&mut self, error: SecretError) {
+        self.secret_screen = Some(SecretScreen::new(
+            self.profile.secret.clone(),
+            error,
+            self.theme,
+        ));
+    }
+
+    /// Open a screen over the current one, or close it if it is the current one.
+    fn toggle_screen(&mut self, info: &'static ScreenInfo) {
+        if self.navigation.current().info().id == info.id {
+            self.pop_screen();
+        } else {
+            self.push_screen((info.open)(self));
+        }
+    }
+
+    /// Open a screen over the current one, joining its topics. Screens below keep their topics.
+    pub fn push_screen(&mut self, screen: Box<dyn Screen>) {
+        for feature in screen.info().topics() {
+            let _ = self.topic_sender.try_send(TopicCommand::Join(feature));
+        }
+        self.navigation.push(screen);
+    }
+
+    /// Return to the screen below, releasing the closed screen's topics unless another open
+    /// screen needs them.
+    pub fn pop_screen(&mut self) {
+        let Some(closed) = self.navigation.pop() else {
+            return;
+        };
+        self.navigation.current_mut().on_resume();
+        for feature in closed.info().topics() {
+            let still_open = self
+                .navigation
+                .iter()
+                .any(|screen| screen.info().topics().any(|topic| topic == feature));
+            if !still_open {
+                let _ = self.topic_sender.try_send(TopicCommand::Release(feature));
+            }
+        }
+    }
+
+    /// Wait until the transport sends an event or the connection changes, then handle
+    /// everything pending. Closed channels are no longer waited on.
+    pub async fn receive(&mut self) {
+        tokio::select! {
+            Some(event) = self.socket_receiver.recv() => self.handle_socket_event(event),
+            Ok(()) = self.connection_state.changed() => self.handle_connection_state(),
+            // Shown in the status bar.
+            Ok(()) = self.connection_stats.changed() => {}
+            Ok(()) = self.presence.changed() => {}
+            else => std::future::pending().await,
+        }
+        self.receive_socket_events();
+    }
+
+    /// Handle every event the transport has sent so far.
+    fn receive_socket_events(&mut self) {
+        if self.connection_state.has_changed().unwrap_or(false) {
+            self.handle_connection_state();
+        }
+        while let Ok(event) = self.socket_receiver.try_recv() {
+            self.handle_socket_event(event);
+        }
+    }
 
-                (payload_screen_type, screen_type) => {
-                    info!(
-                        "Screen: {:?} Received payload: {:?}",
-                        payload_screen_type, screen_type
-                    )
+    fn handle_socket_event(&mut self, event: SocketEvent) {
+        self.event_log
+            .send_modify(|event_log| event_log.received(&event));
+
+        let Some(feature) = event.feature() else {
+            if let SocketEvent::Diagnostic(e) = event {
+                info!("Diagnostic: {e}");
+                self.diagnostic = Some(e.to_string());
+            }
+            return;
+        };
+
+        if let SocketEvent::Response { response, .. } = &event {
+            self.store.send_if_modified(|store| store.ingest(response));
+        }
+
+        // Screens that aren't open catch up from the store when they are. The topmost open
+        // screen of the feature gets the event, even under another screen.
+        let screen = self
+            .navigation
+            .iter_mut()
+            .rev()
+            .find(|screen| screen.feature() == Some(feature));
+        if let Some(screen) = screen {
+            screen.handle_socket_event(event);
+        }
+    }
+
+    fn handle_connection_state(&mut self) {
+        let state = self.connection_state.borrow_and_update().clone();
+        match state {
+            ConnectionState::Reconnecting { .. } | ConnectionState::Failed(_) => {
+                if !self.reconnecting {
+                    self.notifier.warning("Connection lost");
+                }
+                self.reconnecting = true
+            }
+            ConnectionState::Connected if self.reconnecting => {
+                self.reconnecting = false;
+                self.notifier.success("Reconnected");
+                for screen in self.navigation.iter_mut() {
+                    screen.on_reconnect();
                 }
             }
+            ConnectionState::Rejected => self.request_secret(SecretError::Rejected),
+            _ => {}
         }
     }
 }
+
+/// Color of the dot showing the connection state.
+fn connection_style(state: &ConnectionState, theme: &Theme) -> Style {
+    match state {
+        ConnectionState::Connected => theme.success,
+        ConnectionState::Connecting | ConnectionState::Reconnecting { .. } => theme.warning,
+        ConnectionState::Failed(_) | ConnectionState::Rejected => theme.error,
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::keymap::Key;
+    use crate::phoenix::presence::LocalSession;
+    use crate::sqlite::Sqlite;
+    use crate::theme::ThemeConfig;
+    use crate::transport::TransportChannels;
+    use ratatui::{backend::TestBackend, Terminal};
+    use tokio::sync::mpsc;
+
+    /// `App` with an in-memory outbox, and the transport side of its channels.
+    pub(super) fn app() -> (App, TransportChannels) {
+        let (socket_tx, socket_receiver) = mpsc::channel(100);
+        let (screen_sender, screen_rx) = mpsc::channel(100);
+        let (topic_sender, topic_rx) = mpsc::channel(10);
+        let (upload_sender, upload_rx) = mpsc::channel(10);
+        let state_tx = watch::Sender::new(ConnectionState::Connecting);
+        let stats_tx = watch::Sender::new(ConnectionStats::default());
+        let presence_tx = watch::Sender::new(Presence::new(LocalSession::new()));
+        let channels = AppChannels {
+            socket_receiver,
+            screen_sender,
+            topic_sender,
+            upload_sender,
+            connection_state: state_tx.subscribe(),
+            connection_stats: stats_tx.subscribe(),
+            presence: presence_tx.subscribe(),
+            secret_sender: watch::Sender::new(None),
+        };
+        let transport = TransportChannels {
+            socket_tx,
+            screen_rx,
+            topic_rx,
+            upload_rx,
+            state_tx,
+            stats_tx,
+            presence_tx,
+        };
+
+        let (_, profile) = Config::default().profile(Some("dev")).unwrap();
+        let app = App::new(
+            channels,
+            profile,
+            Keymap::default(),
+            Theme::new(&ThemeConfig::default()),
+            Outbox::open(Sqlite::in_memory()),
+        );
+        (app, transport)
+    }
+
+    fn press(app: &mut App, key: &str) -> Option<TUIAction> {
+        app.handle_key(key.parse::<Key>().unwrap().event())
+    }
+
+    fn render(app: &mut App) {
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+    }
+
+    #[test]
+    fn interrupt_and_suspend_work_over_everything() {
+        let (mut app, _transport) = app();
+        assert!(matches!(press(&mut app, "ctrl-c"), Some(TUIAction::Quit)));
+
+        press(&mut app, "ctrl-p");
+        assert!(app.palette.is_some());
+        assert!(matches!(
+            press(&mut app, "ctrl-z"),
+            Some(TUIAction::Suspend)
+        ));
+        assert!(matches!(press(&mut app, "ctrl-c"), Some(TUIAction::Quit)));
+
+        app.request_secret(SecretError::Rejected);
+        assert!(matches!(press(&mut app, "ctrl-c"), Some(TUIAction::Quit)));
+    }
+
+    #[test]
+    fn only_what_changes_with_time_is_redrawn_on_ticks() {
+        let (mut app, _transport) = app();
+        // Heartbeat ages and expiring bins.
+        render(&mut app);
+        assert!(app.changes_with_time());
+
+        app.handle_action(Some(AppActions::Push(&notifications_screen::INFO)));
+        render(&mut app);
+        assert!(!app.changes_with_time());
+
+        app.notifier.warning("Connection lost");
+        render(&mut app);
+        assert!(app.changes_with_time());
+        app.notifier.dismiss();
+        render(&mut app);
+        assert!(!app.changes_with_time());
+    }
+}
@@ -1,50 +1,93 @@
+mod attachments;
 mod edit;
 mod main;
 mod new;
 mod show;
 
-use super::{AppActions, ScreenType};
-use crate::phoenix::event::{PhoenixEvent, StatusEvent};
-use chrono::{DateTime, Local};
+use super::event_log::LoggedSender;
+use super::navigation::Navigation;
+use super::notifications::Notifier;
+use super::screen::{Entity, Keybinding, Screen, ScreenInfo};
+use super::store::Store;
+use super::{App, AppActions};
+use crate::config::Profile;
+use crate::keymap::Keymap;
+use crate::phoenix::connection::ConnectionState;
+use crate::phoenix::event::{PhoenixEvent, SocketEvent};
+use crate::phoenix::presence::Presence;
+use crate::phoenix::protocol::bin::{
+    Bin, BinId, BinRequest, BinResponse, EditBin, EditFile, Expire, ExpireUnit, NewBin, Viewing,
+};
+use crate::phoenix::protocol::{Feature, Request, Response};
+use crate::phoenix::request::RequestId;
+use crate::phoenix::topic::TopicCommand;
+use crate::phoenix::upload::Upload;
+use crate::sqlite::outbox::{Outbox, OutboxStatus};
+use crate::theme::Theme;
+use crate::tui::expire_at_input_widget::{ExpireAt, TimeUnit};
+use crate::tui::mouse::Mouse;
+use attachments::{Attachment, AttachmentState, Attachments};
 use cli_log::info;
 use crossterm::event::KeyEvent;
 use edit::EditScreen;
 use main::MainScreen;
 use new::NewScreen;
-use ratatui::Frame;
-use serde_json::{json, Value};
+use ratatui::{layout::Rect, Frame};
 use show::ShowScreen;
+use std::collections::HashMap;
+use std::rc::Rc;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::watch;
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub struct Bin {
-    id: u64,
-    title: String,
-    content: String,
-    expire_at: DateTime<Local>,
-    files: Vec<File>,
+impl From<ExpireAt> for Expire {
+    fn from(value: ExpireAt) -> Self {
+        let unit = match value.unit {
+            TimeUnit::Day => ExpireUnit::Day,
+            TimeUnit::Minute => ExpireUnit::Minute,
+            TimeUnit::Hour => ExpireUnit::Hour,
+        };
+        Self {
+            time: value.time,
+            unit,
+        }
+    }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-struct File {
-    id: String,
-    name: String,
-    access_path: String,
-    #[serde(rename = "type")]
-    type_name: String,
-}
+pub static INFO: ScreenInfo = ScreenInfo {
+    id: "bin",
+    title: "Bin",
+    feature: Some(Feature::Bin),
+    extra_topics: &[],
+    in_menu: true,
+    open: |app| Box::new(BinScreen::new(app)),
+    entities: |app| {
+        let store = app.store.borrow();
+        store
+            .bins
+            .iter()
+            .map(|bin| Entity {
+                id: bin.id.to_string(),
+                title: bin.title.clone(),
+            })
+            .collect()
+    },
+};
 
 pub enum BinActions {
-    SendEvent(Option<Value>),
+    SendEvent(BinRequest),
     App(AppActions),
-    ChangeScreen(CurrentScreen, Option<Bin>),
-}
-
-pub enum CurrentScreen {
-    Main,
-    New,
-    Show,
-    Edit,
+    /// Open the form for a new bin over the current screen.
+    PushNew,
+    /// Show the bin over the current screen.
+    PushShow(Bin),
+    /// Edit the bin over the current screen.
+    PushEdit(Bin),
+    /// Return to the screen below.
+    Back,
+    /// Upload the attached files, then save the bin.
+    UploadAttachments,
+    DiscardOutboxItem(i64),
+    RetryOutboxItem(i64),
 }
 
 enum Screens {
@@ -55,173 +98,770 @@ enum Screens {
 }
 
 impl Screens {
-    fn render(&mut self, f: &mut Frame) {
+    fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        match self {
+            Screens::Main(main_screen) => main_screen.render(f, area, keymap),
+            Screens::New(new_screen) => new_screen.render(f, area, keymap),
+            Screens::Show(show_screen) => show_screen.render(f, area, keymap),
+            Screens::Edit(edit_screen) => edit_screen.render(f, area, keymap),
+        }
+    }
+
+    fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<BinActions> {
+        match self {
+            Screens::Main(main_screen) => main_screen.handle_key(keymap, e),
+            Screens::New(new_screen) => new_screen.handle_key(keymap, e),
+            Screens::Show(show_screen) => show_screen.handle_key(keymap, e),
+            Screens::Edit(edit_screen) => edit_screen.handle_key(keymap, e),
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: Mouse) -> Option<BinActions> {
+        match self {
+            Screens::Main(main_screen) => main_screen.handle_mouse(mouse),
+            Screens::New(new_screen) => new_screen.handle_mouse(mouse),
+            Screens::Show(show_screen) => show_screen.handle_mouse(mouse),
+            Screens::Edit(edit_screen) => edit_screen.handle_mouse(mouse),
+        }
+    }
+
+    fn attachments(&mut self) -> Option<&mut Attachments> {
+        match self {
+            Screens::New(new_screen) => Some(&mut new_screen.attachments),
+            Screens::Edit(edit_screen) => Some(&mut edit_screen.attachments),
+            Screens::Main(_) | Screens::Show(_) => None,
+        }
+    }
+
+    fn keybindings(&self) -> &'static [Keybinding] {
         match self {
-            Screens::Main(main_screen) => main_screen.render(f),
-            Screens::New(new_screen) => new_screen.render(f),
-            Screens::Show(show_screen) => show_screen.render(f),
-            Screens::Edit(edit_screen) => edit_screen.render(f),
+            Screens::Main(_) => main::KEYBINDINGS,
+            Screens::New(_) => new::KEYBINDINGS,
+            Screens::Show(_) => show::KEYBINDINGS,
+            Screens::Edit(_) => edit::KEYBINDINGS,
         }
     }
 
-    fn handle_key(&mut self, e: KeyEvent) -> Option<BinActions> {
+    fn shows(&self, id: u64) -> bool {
+        matches!(self, Screens::Show(show_screen) if show_screen.bin_id() == id)
+    }
+
+    /// Bin shown or edited, announced to other sessions.
+    fn viewing(&self) -> Option<u64> {
+        match self {
+            Screens::Show(show_screen) => Some(show_screen.bin_id()),
+            Screens::Edit(edit_screen) => Some(edit_screen.bin_id()),
+            Screens::Main(_) | Screens::New(_) => None,
+        }
+    }
+
+    /// Request saving the bin being created or edited.
+    fn save_request(&self) -> Option<BinRequest> {
         match self {
-            Screens::Main(main_screen) => main_screen.handle_key(e),
-            Screens::New(new_screen) => new_screen.handle_key(e),
-            Screens::Show(show_screen) => show_screen.handle_key(e),
-            Screens::Edit(edit_screen) => edit_screen.handle_key(e),
+            Screens::New(new_screen) => Some(new_screen.request()),
+            Screens::Edit(edit_screen) => Some(edit_screen.request()),
+            Screens::Main(_) | Screens::Show(_) => None,
         }
     }
 }
 
-/// ## Events
+/// Screens for managing bins. The events exchanged with the socket are described on
+/// `BinRequest` and `BinResponse`. Bins are read from the `Store`, which `App` keeps up to date.
 ///
-/// ### Get all bins
-/// screen -> socket: `{"name": "bin", "payload": {"action": "get-all"}}`
+/// ## Offline
 ///
-/// screen <- socket: `{"name": "bin", "payload": {"action": "get-all", "data": [...]}}`
+/// `new`, `edit` and `delete` events made while disconnected are stored in the outbox and replayed
+/// by `Phoenix` after it reconnects. Events that can't be sent while connected, or that got no
+/// reply because the connection dropped, and items retried from the list, are replayed right
+/// away.
 ///
-/// ### Edit bin
-/// screen -> socket: `{"name": "bin", "payload": {"action": "edit", "data": {"id": 1, ...}}}`
+/// ## Uploads
 ///
-/// screen <- socket: `{"name": "bin", "payload": {"action": "edit", "data": {"id": 1, ...}}}`
+/// Files attached in the new and edit screens are uploaded when saving. The bin is sent once every
+/// upload finished; a failed upload is shown next to its file and retried on the next save. A bin
+/// with files to upload can't be saved offline.
 ///
-/// ### Delete bin
-/// screen -> socket: `{"name": "bin", "payload": {"action": "delete", "id": 1}}`
+/// ## Presence
 ///
+/// The bin being shown or edited is announced with `viewing` events, so other sessions can warn
+/// before editing the same bin.
 pub struct BinScreen {
-    pub screen_sender: Sender<PhoenixEvent>,
-    current_screen: CurrentScreen,
-    screen: Screens,
+    pub screen_sender: LoggedSender<PhoenixEvent>,
+    upload_sender: LoggedSender<Upload>,
+    topic_sender: Sender<TopicCommand>,
+    connection_state: watch::Receiver<ConnectionState>,
+    store: watch::Receiver<Store>,
+    presence: watch::Receiver<Presence>,
+    profile: Profile,
+    /// Bin last announced as open.
+    viewing: Option<u64>,
+    /// Mutating requests sent and not answered yet, by request ID.
+    sent: HashMap<RequestId, Request>,
+    outbox: Option<Rc<Outbox>>,
+    /// The bin list, with the screens opened from it on top.
+    navigation: Navigation<Screens>,
+    notifier: Notifier,
+    theme: Theme,
 }
 
 impl BinScreen {
-    pub fn new(screen_sender: Sender<PhoenixEvent>) -> Self {
-        let bin_screen = Self {
-            screen_sender,
-            current_screen: CurrentScreen::Main,
-            screen: Screens::Main(MainScreen::new(Vec::new())),
+    /// Opened with the app's channels, store, notifier and theme.
+    pub fn new(app: &App) -> Self {
+        let store = app.store.subscribe();
+        let mut bin_screen = Self {
+            screen_sender: app.screen_sender.clone(),
+            upload_sender: app.upload_sender.clone(),
+            topic_sender: app.topic_sender.clone(),
+            connection_state: app.connection_state.clone(),
+            navigation: Navigation::new(Screens::Main(MainScreen::new(store.clone(), app.theme))),
+            store,
+            presence: app.presence.clone(),
+            profile: app.profile.clone(),
+            viewing: None,
+            sent: HashMap::new(),
+            outbox: app.outbox.clone(),
+            notifier: app.notifier.clone(),
+            theme: app.theme,
         };
 
-        bin_screen.push_event(Some(json!({"action": "get-all"})));
+        bin_screen.push_event(BinRequest::GetAll);
+        bin_screen.refresh_outbox();
         bin_screen
     }
 
-    pub fn render(&mut self, f: &mut Frame) {
-        self.screen.render(f);
-    }
-
-    // Keyboard event handler
-
-    pub fn handle_key(&mut self, e: KeyEvent) -> Option<AppActions> {
-        match self.screen.handle_key(e) {
-            Some(action) => match action {
-                BinActions::ChangeScreen(screen_type, data) => match screen_type {
-                    CurrentScreen::Main => {
-                        self.change_to_main_screen();
-                        None
+    fn handle_response(&mut self, response: BinResponse) {
+        match response {
+            // Already in the store.
+            BinResponse::GetAll { .. } => {}
+            BinResponse::New { data } => {
+                if data.is_ok() {
+                    self.notifier.success("Bin created");
+                    if !matches!(self.navigation.current(), Screens::Main(_)) {
+                        self.back_to_main_screen();
+                    }
+                } else {
+                    self.notifier
+                        .error(format!("Cannot create the bin: {}", data.error_message()));
+                }
+            }
+            BinResponse::Delete { data } => {
+                if data.is_ok() {
+                    self.notifier.success("Bin deleted");
+                    if !matches!(self.navigation.current(), Screens::Main(_)) {
+                        self.back_to_main_screen();
                     }
-                    CurrentScreen::New => {
-                        self.current_screen = CurrentScreen::New;
-                        self.screen = Screens::New(NewScreen::new());
-                        None
+                } else {
+                    self.notifier
+                        .error(format!("Cannot delete the bin: {}", data.error_message()));
+                }
+            }
+            // Back to the bin, which picks the changes up from the store.
+            BinResponse::Edit { data } => {
+                self.notifier.success(format!("Saved {}", data.title));
+                let shown_below = self
+                    .navigation
+                    .iter()
+                    .rev()
+                    .nth(1)
+                    .is_some_and(|screen| screen.shows(data.id));
+                match self.navigation.current() {
+                    Screens::Edit(_) if shown_below => {
+                        self.navigation.pop();
                     }
-                    CurrentScreen::Show => {
-                        self.current_screen = CurrentScreen::Show;
-                        self.screen = Screens::Show(ShowScreen::new(data.unwrap()));
-                        None
+                    Screens::Edit(_) => {
+                        let show = self.show_screen(data);
+                        self.navigation.replace(show);
                     }
-                    CurrentScreen::Edit => {
-                        self.current_screen = CurrentScreen::Edit;
-                        self.screen = Screens::Edit(EditScreen::new(data.unwrap()));
-                        None
+                    screen if screen.shows(data.id) => {}
+                    _ => {
+                        let show = self.show_screen(data);
+                        self.navigation.push(show);
                     }
+                }
+                self.on_screen_change();
+            }
+            // Replies to upload steps are handled by the upload task.
+            BinResponse::UploadStart { .. }
+            | BinResponse::UploadChunk { .. }
+            | BinResponse::UploadFinish { .. } => {}
+            BinResponse::Viewing { data } => {
+                if !data.is_ok() {
+                    self.notifier
+                        .warning(format!("Cannot announce the bin: {}", data.error_message()));
+                }
+            }
+        }
+    }
+
+    /// Send every attached file that isn't uploaded yet to `Phoenix`. Files can't be queued in
+    /// the outbox, so saving them offline is refused and the form stays open.
+    fn start_uploads(&mut self) {
+        let connected = self.is_connected();
+        let Some(attachments) = self.navigation.current_mut().attachments() else {
+            return;
+        };
+        if !connected && attachments.pending_uploads().next().is_some() {
+            self.notifier
+                .error("Cannot upload the attached files while offline, save again once connected");
+            return;
+        }
+
+        for attachment in attachments.pending_uploads() {
+            let upload = Upload::new(attachment.path.clone());
+            let request_id = upload.request_id;
+            attachment.state = match self.upload_sender.try_send(upload) {
+                Ok(()) => AttachmentState::Uploading {
+                    request_id,
+                    sent: 0,
                 },
-                BinActions::SendEvent(value) => {
-                    self.push_event(value);
-                    None
+                Err(e) => AttachmentState::Failed(e.to_string()),
+            };
+        }
+        self.save_after_uploads();
+    }
+
+    fn upload(&mut self, request_id: RequestId) -> Option<&mut Attachment> {
+        self.navigation
+            .current_mut()
+            .attachments()?
+            .find(request_id)
+    }
+
+    fn save_after_uploads(&mut self) {
+        let screen = self.navigation.current_mut();
+        let Some(attachments) = screen.attachments() else {
+            return;
+        };
+        if attachments.all_uploaded() {
+            if let Some(request) = screen.save_request() {
+                self.push_event(request);
+            }
+        }
+    }
+
+    /// Announce the open bin when it changes.
+    fn set_viewing(&mut self, id: Option<u64>) {
+        if self.viewing == id {
+            return;
+        }
+        self.viewing = id;
+        self.push_event(BinRequest::Viewing {
+            data: Viewing { id },
+        });
+    }
+
+    /// Send an event to the socket. Mutating events are queued in the outbox instead when the
+    /// socket is down or the send queue is full.
+    fn push_event(&mut self, request: BinRequest) {
+        let event = PhoenixEvent::new(Request::Bin(request));
+        let connected = self.is_connected();
+
+        let (event, reason) = if connected {
+            // Kept until the reply, in case the call fails.
+            let sent = Outbox::accepts(&event.request).then(|| event.clone());
+            match self.screen_sender.try_send(event) {
+                Ok(()) => {
+                    info!("sent message");
+                    if let Some(sent) = sent {
+                        self.sent.insert(sent.request_id, sent.request);
+                    }
+                    return;
+                }
+                Err(e) => {
+                    info!("{e}");
+                    let reason = format!("Cannot send the request: {e}");
+                    (e.into_inner(), reason)
+                }
+            }
+        } else {
+            (event, "Not connected".to_string())
+        };
+
+        if Outbox::accepts(&event.request) {
+            self.queue(&event.request, &reason);
+        } else if connected {
+            self.notifier.warning(reason);
+        } else {
+            let _ = self.screen_sender.try_send(event);
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        matches!(*self.connection_state.borrow(), ConnectionState::Connected)
+    }
+
+    /// Keep a request that could not be sent, or got no reply, in the outbox and return to the
+    /// bin list, which shows it.
+    fn queue(&mut self, request: &Request, reason: &str) {
+        let queued = match &self.outbox {
+            Some(outbox) => outbox.enqueue(request).map_err(|e| e.to_string()),
+            None => Err("the outbox isn't open".to_string()),
+        };
+        match queued {
+            Ok(_) => {
+                self.notifier
+                    .warning(format!("{reason}, the request is queued in the outbox"));
+                if self.is_connected() {
+                    self.replay_outbox();
                 }
-                BinActions::App(action) => Some(action),
-            },
-            None => None,
+            }
+            Err(e) => self
+                .notifier
+                .error(format!("Cannot queue the request: {e}")),
+        }
+        self.refresh_outbox();
+        if !matches!(self.navigation.current(), Screens::Main(_)) {
+            self.back_to_main_screen();
+        }
+    }
+
+    /// Ask `Phoenix` to send the queued items now. It ignores this while disconnected, and
+    /// replays the outbox once it reconnects.
+    fn replay_outbox(&self) {
+        let _ = self.topic_sender.try_send(TopicCommand::ReplayOutbox);
+    }
+
+    fn refresh_outbox(&mut self) {
+        let Some(outbox) = &self.outbox else {
+            return;
+        };
+        if let Screens::Main(main_screen) = self.navigation.current_mut() {
+            match outbox.items() {
+                Ok(items) => main_screen.set_outbox(items),
+                Err(e) => self.notifier.error(format!("Cannot read the outbox: {e}")),
+            }
         }
     }
 
+    fn handle_bin_action(&mut self, action: BinActions) -> Option<AppActions> {
+        match action {
+            BinActions::PushNew => {
+                self.push_screen(Screens::New(NewScreen::new(self.theme)));
+                None
+            }
+            BinActions::PushShow(bin) => {
+                let screen = self.show_screen(bin);
+                self.push_screen(screen);
+                None
+            }
+            BinActions::PushEdit(bin) => {
+                let screen = Screens::Edit(EditScreen::new(bin, self.presence.clone(), self.theme));
+                self.push_screen(screen);
+                None
+            }
+            BinActions::Back => {
+                self.navigation.pop();
+                self.on_screen_change();
+                None
+            }
+            BinActions::SendEvent(request) => {
+                self.push_event(request);
+                None
+            }
+            BinActions::UploadAttachments => {
+                self.start_uploads();
+                None
+            }
+            BinActions::DiscardOutboxItem(id) => {
+                if let Some(Err(e)) = self.outbox.as_ref().map(|outbox| outbox.remove(id)) {
+                    self.notifier
+                        .error(format!("Cannot discard the request: {e}"));
+                }
+                self.refresh_outbox();
+                None
+            }
+            BinActions::RetryOutboxItem(id) => {
+                let retried = self
+                    .outbox
+                    .as_ref()
+                    .map(|outbox| outbox.set_status(id, OutboxStatus::Queued, None));
+                match retried {
+                    Some(Ok(())) => self.replay_outbox(),
+                    Some(Err(e)) => self
+                        .notifier
+                        .error(format!("Cannot retry the request: {e}")),
+                    None => {}
+                }
+                self.refresh_outbox();
+                None
+            }
+            BinActions::App(action) => Some(action),
+        }
+    }
+
+    fn show_screen(&self, bin: Bin) -> Screens {
+        Screens::Show(ShowScreen::new(
+            bin,
+            self.store.clone(),
+            self.profile.clone(),
+            self.notifier.clone(),
+            self.theme,
+        ))
+    }
+
+    fn push_screen(&mut self, screen: Screens) {
+        self.navigation.push(screen);
+        self.on_screen_change();
+    }
+
+    /// Announce the bin now open and show the outbox again on the bin list.
+    fn on_screen_change(&mut self) {
+        let viewing = self.navigation.current().viewing();
+        self.set_viewing(viewing);
+        self.refresh_outbox();
+    }
+
+    /// Close every screen over the bin list and refetch it after a bin was created or deleted.
+    fn back_to_main_screen(&mut self) {
+        self.navigation.pop_to_root();
+        self.on_screen_change();
+        self.push_event(BinRequest::GetAll);
+    }
+}
+
+impl Screen for BinScreen {
+    fn info(&self) -> &'static ScreenInfo {
+        &INFO
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        self.navigation.current_mut().render(f, area, keymap);
+    }
+
+    // Keyboard event handler
+
+    fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions> {
+        let action = self.navigation.current_mut().handle_key(keymap, e)?;
+        self.handle_bin_action(action)
+    }
+
+    fn handle_mouse(&mut self, mouse: Mouse) -> Option<AppActions> {
+        let action = self.navigation.current_mut().handle_mouse(mouse)?;
+        self.handle_bin_action(action)
+    }
+
     // Event handlers
 
-    pub fn handle_socket_event(&mut self, event: PhoenixEvent) {
-        if let Some(payload) = event.payload {
-            let action = payload["action"].as_str().unwrap();
-            let data = payload["data"].clone();
-            info!("{data:#?}");
-            match action {
-                "get-all" => {
-                    let data: Result<Vec<Bin>, serde_json::Error> = serde_json::from_value(data);
-                    if data.is_ok() {
-                        if let Screens::Main(main_screen) = &mut self.screen {
-                            main_screen.bins = data.unwrap();
-                            main_screen.select_first();
-                        }
-                    }
+    fn handle_socket_event(&mut self, event: SocketEvent) {
+        match event {
+            SocketEvent::Response {
+                request_id: Some(request_id),
+                response: Response::Bin(response),
+            } => {
+                info!("bin response to #{request_id}: {response:#?}");
+                self.sent.remove(&request_id);
+                self.handle_response(response)
+            }
+            // Broadcasts only update the store. Replies to replayed outbox items come as
+            // broadcasts too.
+            SocketEvent::Response {
+                request_id: None, ..
+            } => self.refresh_outbox(),
+            SocketEvent::UploadProgress {
+                request_id,
+                sent,
+                total,
+            } => {
+                if let Some(attachment) = self.upload(request_id) {
+                    attachment.size = total;
+                    attachment.state = AttachmentState::Uploading { request_id, sent };
                 }
-                "new" => {
-                    let data: Result<StatusEvent, serde_json::Error> = serde_json::from_value(data);
-                    if data.is_ok() {
-                        let data = data.unwrap();
-                        if data.status.as_str() == "OK" {
-                            // on successful creation of new bin
-                            // move to main screen
-                            if !matches!(self.current_screen, CurrentScreen::Main) {
-                                self.change_to_main_screen();
-                            }
-                        } else {
-                            info!("{}", data.message.unwrap());
-                        }
-                    }
+            }
+            SocketEvent::Uploaded {
+                request_id,
+                upload_id,
+            } => {
+                if let Some(attachment) = self.upload(request_id) {
+                    attachment.state = AttachmentState::Uploaded(upload_id);
                 }
-                "delete" => {
-                    let data: Result<StatusEvent, serde_json::Error> = serde_json::from_value(data);
-                    if data.is_ok() {
-                        let data = data.unwrap();
-                        if data.status.as_str() == "OK" {
-                            // on successful deletion
-                            // move to main screen
-                            if !matches!(self.current_screen, CurrentScreen::Main) {
-                                self.change_to_main_screen();
-                            }
-                        } else {
-                            info!("{}", data.message.unwrap());
-                        }
-                    }
+                self.save_after_uploads();
+            }
+            SocketEvent::Failed {
+                request_id, error, ..
+            } => {
+                if let Some(attachment) = self.upload(request_id) {
+                    let message = format!("Cannot upload {}: {error}", attachment.name);
+                    attachment.state = AttachmentState::Failed(error.to_string());
+                    self.notifier.error(message);
+                    return;
                 }
-                "edit" => {
-                    let data: Result<Bin, serde_json::Error> = serde_json::from_value(data);
-                    if data.is_ok() {
-                        let bin = data.unwrap();
-                        self.current_screen = CurrentScreen::Show;
-                        self.screen = Screens::Show(ShowScreen::new(bin));
+                // The connection dropped during the call: send it again later.
+                match self.sent.remove(&request_id) {
+                    Some(request) if error.is_transient() => {
+                        self.queue(&request, &format!("Request failed: {error}"))
                     }
+                    _ => self.notifier.error(format!("Request failed: {error}")),
                 }
-                e => info!("Unhandled action: {e}"),
             }
+            event => info!("Unhandled event: {event:?}"),
         }
     }
 
-    fn push_event(&self, payload: Option<Value>) {
-        match self.screen_sender.try_send(PhoenixEvent {
-            name: "bin".to_string(),
-            payload,
-        }) {
-            Ok(()) => info!("sent message"),
-            Err(e) => info!("{e}"),
+    fn open_entity(&mut self, id: &str) {
+        let bin = id
+            .parse()
+            .ok()
+            .and_then(|id| self.store.borrow().bin(id).cloned());
+        let Some(bin) = bin else {
+            return;
+        };
+        self.navigation.pop_to_root();
+        let show = self.show_screen(bin);
+        self.push_screen(show);
+    }
+
+    fn on_reconnect(&mut self) {
+        self.push_event(BinRequest::GetAll);
+        self.refresh_outbox();
+        // Presence starts over on the rejoined topic.
+        if let Some(id) = self.viewing {
+            self.push_event(BinRequest::Viewing {
+                data: Viewing { id: Some(id) },
+            });
         }
     }
 
-    // Used frequently. Moved into a helper function.
-    /// Change to the main screen.
-    fn change_to_main_screen(&mut self) {
-        self.current_screen = CurrentScreen::Main;
-        self.screen = Screens::Main(MainScreen::new(Vec::new()));
-        self.push_event(Some(json!({"action": "get-all"})));
+    fn keybindings(&self) -> &'static [Keybinding] {
+        self.navigation.current().keybindings()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app;
+    use crate::keymap::Key;
+    use crate::phoenix::request::{RequestError, DEFAULT_TIMEOUT};
+    use crate::sqlite::outbox::OutboxItem;
+    use crate::transport::memory::MemoryTransport;
+    use crate::transport::Transport;
+    use ratatui::{backend::TestBackend, Terminal};
+    use std::time::Duration;
+
+    /// `App` on `MemoryTransport`, once connected.
+    async fn connected_app() -> App {
+        let (mut app, transport) = app();
+        let mut transport = MemoryTransport::new(transport);
+        tokio::spawn(async move { transport.run().await });
+        wait_until(&mut app, |app| {
+            *app.connection_state.borrow() == ConnectionState::Connected
+        })
+        .await;
+        app
+    }
+
+    fn open_bin_screen(app: &mut App) {
+        app.handle_action(Some(AppActions::Push(&INFO)));
+    }
+
+    /// Handle what the transport sends until `done`.
+    async fn wait_until(app: &mut App, done: impl Fn(&App) -> bool) {
+        let wait = async {
+            while !done(app) {
+                app.receive().await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .expect("timed out");
+    }
+
+    /// Press keys written like in the config file, separated by spaces.
+    fn press(app: &mut App, keys: &str) {
+        for key in keys.split(' ') {
+            let key: Key = key.parse().unwrap();
+            app.handle_key(key.event());
+        }
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            let key = Key {
+                code: crossterm::event::KeyCode::Char(c),
+                modifiers: crossterm::event::KeyModifiers::NONE,
+            };
+            app.handle_key(key.event());
+        }
+    }
+
+    /// What the app shows, row after row.
+    fn screen(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn shows_bin_list(app: &mut App) -> bool {
+        screen(app).contains("to add a new bin")
+    }
+
+    fn outbox(app: &App) -> Vec<OutboxItem> {
+        app.outbox.as_ref().unwrap().items().unwrap()
+    }
+
+    fn titles(app: &App) -> Vec<String> {
+        let store = app.store.borrow();
+        store.bins.iter().map(|bin| bin.title.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn create_edit_and_delete_a_bin() {
+        let mut app = connected_app().await;
+        open_bin_screen(&mut app);
+        assert!(shows_bin_list(&mut app));
+
+        press(&mut app, "n");
+        assert!(screen(&mut app).contains("Expire in"));
+        type_text(&mut app, "Draft");
+        press(&mut app, "tab");
+        type_text(&mut app, "build log");
+        press(&mut app, "ctrl-s");
+        wait_until(&mut app, |app| titles(app) == ["Draft"]).await;
+        assert!(shows_bin_list(&mut app));
+        assert_eq!(app.store.borrow().bins[0].content, "build log");
+
+        press(&mut app, "g enter");
+        assert!(screen(&mut app).contains("Draft - Bin"));
+        press(&mut app, "e");
+        assert!(screen(&mut app).contains("Extend Expire time by"));
+        type_text(&mut app, " v2");
+        press(&mut app, "ctrl-s");
+        wait_until(&mut app, |app| titles(app) == ["Draft v2"]).await;
+        // Back to the bin, which shows the changes.
+        assert!(screen(&mut app).contains("Draft v2 - Bin"));
+
+        press(&mut app, "d");
+        wait_until(&mut app, |app| app.store.borrow().bins.is_empty()).await;
+        assert!(shows_bin_list(&mut app));
+        assert!(outbox(&app).is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_bin_saved_offline_is_queued_in_the_outbox() {
+        let (mut app, _transport) = app();
+        open_bin_screen(&mut app);
+
+        press(&mut app, "n");
+        type_text(&mut app, "Draft on a train");
+        press(&mut app, "ctrl-s");
+
+        assert!(shows_bin_list(&mut app));
+        assert!(app.store.borrow().bins.is_empty());
+        let items = outbox(&app);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].status, OutboxStatus::Queued);
+        assert_eq!(items[0].title(), "Draft on a train");
+        assert!(screen(&mut app).contains("new Draft on a train"));
+
+        // Discarded from the list.
+        press(&mut app, "tab x");
+        assert!(outbox(&app).is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_bin_with_files_is_not_saved_offline() {
+        let (mut app, _transport) = app();
+        open_bin_screen(&mut app);
+        let path = std::env::temp_dir().join(format!("meowui-offline-{}.txt", std::process::id()));
+        std::fs::write(&path, "log").unwrap();
+
+        press(&mut app, "n");
+        type_text(&mut app, "Draft");
+        press(&mut app, "tab tab tab");
+        type_text(&mut app, &path.display().to_string());
+        press(&mut app, "enter ctrl-s");
+        std::fs::remove_file(&path).unwrap();
+
+        let shown = screen(&mut app);
+        assert!(shown.contains("Expire in"));
+        assert!(!shown.contains("failed"));
+        assert!(outbox(&app).is_empty());
+        let notifications = app.notifier.borrow();
+        let error = notifications.toasts().next().unwrap();
+        assert!(error
+            .message
+            .starts_with("Cannot upload the attached files while offline"));
+    }
+
+    #[tokio::test]
+    async fn a_request_the_full_channel_refuses_is_queued() {
+        let (mut app, transport) = app();
+        transport.state_tx.send_replace(ConnectionState::Connected);
+        open_bin_screen(&mut app);
+        let get_all = || PhoenixEvent::new(Request::Bin(BinRequest::GetAll));
+        while app.screen_sender.try_send(get_all()).is_ok() {}
+
+        press(&mut app, "n");
+        type_text(&mut app, "Draft");
+        press(&mut app, "ctrl-s");
+
+        let items = outbox(&app);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title(), "Draft");
+        // Only what the channel took is logged as sent.
+        let event_log = app.event_log.borrow();
+        assert!(event_log.entries().all(|entry| entry.name != "bin new"));
+    }
+
+    #[tokio::test]
+    async fn a_request_lost_with_the_connection_is_queued() {
+        let (mut app, mut transport) = app();
+        transport.state_tx.send_replace(ConnectionState::Connected);
+        wait_until(&mut app, |app| {
+            *app.connection_state.borrow() == ConnectionState::Connected
+        })
+        .await;
+        open_bin_screen(&mut app);
+
+        press(&mut app, "n");
+        type_text(&mut app, "Draft");
+        press(&mut app, "ctrl-s");
+        let new = loop {
+            let event = transport.screen_rx.recv().await.unwrap();
+            if event.request.action() == "new" {
+                break event;
+            }
+        };
+        // Still waiting for the reply.
+        assert!(outbox(&app).is_empty());
+
+        let failed = SocketEvent::failed(&new, RequestError::Timeout(DEFAULT_TIMEOUT));
+        transport.socket_tx.send(failed).await.unwrap();
+        wait_until(&mut app, |app| !outbox(app).is_empty()).await;
+        let items = outbox(&app);
+        assert_eq!(items[0].request.as_ref(), Some(&new.request));
+        assert!(shows_bin_list(&mut app));
+        // Sent again right away, as the app is still connected.
+        let mut replayed = false;
+        while let Ok(command) = transport.topic_rx.try_recv() {
+            replayed |= command == TopicCommand::ReplayOutbox;
+        }
+        assert!(replayed);
+
+        // A request the server refused is not sent again.
+        press(&mut app, "n");
+        type_text(&mut app, "Refused");
+        press(&mut app, "ctrl-s");
+        let new = loop {
+            let event = transport.screen_rx.recv().await.unwrap();
+            if event.request.action() == "new" {
+                break event;
+            }
+        };
+        let failed = SocketEvent::failed(&new, RequestError::Rejected("too large".to_string()));
+        transport.socket_tx.send(failed).await.unwrap();
+        wait_until(&mut app, |app| {
+            let notifications = app.notifier.borrow();
+            let mut toasts = notifications.toasts();
+            toasts.any(|toast| toast.message == "Request failed: rejected: too large")
+        })
+        .await;
+        assert_eq!(outbox(&app).len(), 1);
     }
 }
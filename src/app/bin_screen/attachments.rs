@@ -0,0 +1,165 @@
+use crate::keymap::{Action, Context, Keymap};
+use crate::phoenix::request::RequestId;
+use crate::phoenix::upload;
+use crate::theme::Theme;
+use crate::tui::input_widget::InputWidget;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph},
+    Frame,
+};
+use std::path::PathBuf;
+
+/// Local files attached to a new or edited bin. They are uploaded when the bin is saved and sent
+/// as the bin's `uploads` once every upload has finished.
+pub struct Attachments {
+    path_input: InputWidget<'static>,
+    items: Vec<Attachment>,
+    error: Option<String>,
+    theme: Theme,
+}
+
+pub struct Attachment {
+    pub path: PathBuf,
+    pub name: String,
+    pub size: u64,
+    pub state: AttachmentState,
+}
+
+pub enum AttachmentState {
+    Pending,
+    Uploading { request_id: RequestId, sent: u64 },
+    Uploaded(String),
+    Failed(String),
+}
+
+impl Attachments {
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            path_input: InputWidget::new(String::new(), &theme),
+            items: Vec::new(),
+            error: None,
+            theme,
+        }
+    }
+
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) {
+        match keymap.action_in_text(&[Context::Form], &e) {
+            Some(Action::Confirm) => self.add(),
+            Some(Action::RemoveLastFile) => {
+                if !self.is_uploading() {
+                    self.items.pop();
+                }
+            }
+            _ => self.path_input.handle_key(keymap, e),
+        }
+    }
+
+    fn add(&mut self) {
+        let path = PathBuf::from(self.path_input.content().trim());
+        match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => {
+                self.items.push(Attachment {
+                    name: upload::file_name(&path),
+                    path,
+                    size: metadata.len(),
+                    state: AttachmentState::Pending,
+                });
+                self.path_input = InputWidget::new(String::new(), &self.theme);
+                self.error = None;
+            }
+            Ok(_) => self.error = Some(format!("{} is not a file", path.display())),
+            Err(e) => self.error = Some(format!("{}: {e}", path.display())),
+        }
+    }
+
+    pub fn is_uploading(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item.state, AttachmentState::Uploading { .. }))
+    }
+
+    pub fn all_uploaded(&self) -> bool {
+        self.items
+            .iter()
+            .all(|item| matches!(item.state, AttachmentState::Uploaded(_)))
+    }
+
+    /// Attachments that still need uploading, including ones that failed before.
+    pub fn pending_uploads(&mut self) -> impl Iterator<Item = &mut Attachment> {
+        self.items.iter_mut().filter(|item| {
+            matches!(
+                item.state,
+                AttachmentState::Pending | AttachmentState::Failed(_)
+            )
+        })
+    }
+
+    pub fn find(&mut self, request_id: RequestId) -> Option<&mut Attachment> {
+        self.items.iter_mut().find(|item| {
+            matches!(item.state, AttachmentState::Uploading { request_id: id, .. } if id == request_id)
+        })
+    }
+
+    pub fn upload_ids(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .filter_map(|item| match &item.state {
+                AttachmentState::Uploaded(upload_id) => Some(upload_id.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Rows needed to render the input and every attachment.
+    pub fn height(&self) -> u16 {
+        3 + self.items.len() as u16 + u16::from(self.error.is_some())
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, focused: bool) {
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+
+        let input_block = Block::new()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .border_style(self.theme.border_style(focused))
+            .title_style(self.theme.title)
+            .style(self.theme.text)
+            .title("Attach file (path)");
+        let input_widget = self.path_input.clone().block(input_block);
+        f.render_widget(&input_widget, chunks[0]);
+
+        let mut lines: Vec<Line> = self
+            .items
+            .iter()
+            .map(|item| {
+                let (state, style) = match &item.state {
+                    AttachmentState::Pending => ("pending".to_string(), self.theme.muted),
+                    AttachmentState::Uploading { sent, .. } => {
+                        let percent = (sent * 100).checked_div(item.size).unwrap_or(100);
+                        (format!("{percent}%"), self.theme.accent)
+                    }
+                    AttachmentState::Uploaded(_) => ("uploaded".to_string(), self.theme.success),
+                    AttachmentState::Failed(error) => {
+                        (format!("failed: {error}"), self.theme.error)
+                    }
+                };
+                Line::from(vec![
+                    Span::styled(format!(" {} ", item.name), self.theme.accent),
+                    Span::styled(format!("[{state}]"), style),
+                ])
+            })
+            .collect();
+        if let Some(error) = &self.error {
+            lines.push(Line::from(Span::styled(
+                format!(" {error}"),
+                self.theme.error,
+            )));
+        }
+        f.render_widget(Paragraph::new(lines), chunks[1]);
+    }
+}
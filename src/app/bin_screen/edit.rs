@@ -1,32 +1,46 @@
-use super::{Bin, BinActions, CurrentScreen, File};
+use super::attachments::Attachments;
+use super::{Bin, BinActions, BinRequest, EditBin, EditFile};
+use crate::app::screen::Keybinding;
+use crate::keymap::{Action, Context, Keymap};
+use crate::phoenix::presence::Presence;
+use crate::theme::Theme;
 use crate::tui::{
-    expire_at_input_widget::ExpireAtWidget, input_widget::InputWidget,
+    expire_at_input_widget::ExpireAtWidget, input_widget::InputWidget, mouse::Mouse,
     multiline_input_widget::MultilineInput,
 };
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::KeyEvent;
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Stylize},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::Stylize,
     text::{Line, Span, Text},
     widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph},
     Frame,
 };
-use serde_json::json;
+use tokio::sync::watch;
+
+pub const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::form(Action::Save, "upload the files and save"),
+    Keybinding::form(Action::NextField, "next field"),
+    Keybinding::form(Action::ToggleRemove, "toggle removing the file"),
+    Keybinding::form(Action::Confirm, "attach the file"),
+    Keybinding::form(Action::RemoveLastFile, "remove the last file"),
+    Keybinding::form(Action::Cancel, "cancel"),
+];
 
 pub struct EditScreen {
     bin: Bin,
+    presence: watch::Receiver<Presence>,
     title_input: InputWidget<'static>,
     content_input: MultilineInput<'static>,
     expire_at: ExpireAtWidget<'static>,
-    files: Vec<EditFiles>,
+    files: Vec<EditFile>,
     selected_file: ListState,
+    pub attachments: Attachments,
     focused_element: EditElements,
-}
-
-#[derive(serde::Serialize)]
-struct EditFiles {
-    file: File,
-    removed: bool,
+    /// Where the title, content, files, expiry and attachments were rendered, to focus them on
+    /// click. The files' area is empty when the bin has none.
+    field_areas: [Rect; 5],
+    theme: Theme,
 }
 
 enum EditElements {
@@ -34,14 +48,15 @@ enum EditElements {
     Content,
     Files,
     Expire,
+    Attach,
 }
 
 impl EditScreen {
-    pub fn new(bin: Bin) -> Self {
-        let files: Vec<EditFiles> = bin
+    pub fn new(bin: Bin, presence: watch::Receiver<Presence>, theme: Theme) -> Self {
+        let files: Vec<EditFile> = bin
             .files
             .iter()
-            .map(|file| EditFiles {
+            .map(|file| EditFile {
                 file: file.clone(),
                 removed: false,
             })
@@ -49,30 +64,57 @@ impl EditScreen {
 
         Self {
             bin: bin.clone(),
-            title_input: InputWidget::new(
-                bin.title,
-                Style::default(),
-                Style::default().fg(Color::Black).bg(Color::White),
-            ),
-            content_input: MultilineInput::new(bin.content),
+            presence,
+            title_input: InputWidget::new(bin.title, &theme),
+            content_input: MultilineInput::new(bin.content, &theme),
             files,
             selected_file: ListState::default().with_selected(None),
+            attachments: Attachments::new(theme),
             focused_element: EditElements::Title,
-            expire_at: ExpireAtWidget::new(
-                Style::default(),
-                Style::default().fg(Color::Black).bg(Color::White),
-            ),
+            field_areas: [Rect::default(); 5],
+            expire_at: ExpireAtWidget::new(&theme),
+            theme,
         }
     }
 
-    pub fn render(&mut self, f: &mut Frame) {
+    /// The edited bin, with the finished uploads attached.
+    pub fn request(&self) -> BinRequest {
+        let data = EditBin {
+            id: self.bin.id,
+            title: self.title_input.content().to_string(),
+            content: self.content_input.content(),
+            files: self.files.clone(),
+            expire: self.expire_at.time().into(),
+            uploads: self.attachments.upload_ids(),
+        };
+        BinRequest::Edit { data }
+    }
+
+    pub fn bin_id(&self) -> u64 {
+        self.bin.id
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        let others = self.presence.borrow().viewing(self.bin.id);
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(1), Constraint::Length(3)])
-            .split(f.area());
+            .constraints([
+                Constraint::Length(u16::from(!others.is_empty())),
+                Constraint::Min(1),
+                Constraint::Length(3),
+            ])
+            .split(area);
 
-        self.edit_widget(chunks[0], f);
-        self.help_widget(chunks[1], f);
+        if !others.is_empty() {
+            let names: Vec<&str> = others.iter().map(|session| session.name.as_str()).collect();
+            let warning = Span::styled(
+                format!(" ⚠ Also open in {}", names.join(", ")),
+                self.theme.warning,
+            );
+            f.render_widget(Paragraph::new(Line::from(warning)), chunks[0]);
+        }
+        self.edit_widget(chunks[1], f);
+        self.help_widget(chunks[2], f, keymap);
     }
 
     fn edit_widget(&mut self, area: Rect, f: &mut Frame) {
@@ -82,6 +124,7 @@ impl EditScreen {
                     Constraint::Length(3),
                     Constraint::Min(1),
                     Constraint::Length(3),
+                    Constraint::Length(self.attachments.height()),
                 ])
                 .split(area)
         } else {
@@ -91,50 +134,48 @@ impl EditScreen {
                     Constraint::Min(1),
                     Constraint::Min(1),
                     Constraint::Length(3),
+                    Constraint::Length(self.attachments.height()),
                 ])
                 .split(area)
         };
 
-        let title_style = if matches!(self.focused_element, EditElements::Title) {
-            Style::default().fg(Color::Blue)
-        } else {
-            Style::default().fg(Color::Gray)
-        };
+        let title_style = self
+            .theme
+            .border_style(matches!(self.focused_element, EditElements::Title));
 
         let title_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
             .border_style(title_style)
-            .style(Style::new().green())
+            .style(self.theme.text)
+            .title_style(self.theme.title)
             .title("Title");
         let title_widget = self.title_input.clone().block(title_block);
         f.render_widget(&title_widget, chunks[0]);
 
-        let content_style = if matches!(self.focused_element, EditElements::Content) {
-            Style::default().fg(Color::Blue)
-        } else {
-            Style::default().fg(Color::Gray)
-        };
+        let content_style = self
+            .theme
+            .border_style(matches!(self.focused_element, EditElements::Content));
         let content_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
             .border_style(content_style)
-            .style(Style::new().green())
+            .style(self.theme.text)
+            .title_style(self.theme.title)
             .title("Content");
         let content_widget = self.content_input.clone().block(content_block);
         f.render_widget(&content_widget, chunks[1]);
 
         if !self.files.is_empty() {
-            let files_style = if matches!(self.focused_element, EditElements::Files) {
-                Style::default().fg(Color::Blue)
-            } else {
-                Style::default().fg(Color::Gray)
-            };
+            let files_style = self
+                .theme
+                .border_style(matches!(self.focused_element, EditElements::Files));
             let files_block = Block::new()
                 .border_type(BorderType::Rounded)
                 .borders(Borders::ALL)
                 .border_style(files_style)
                 .style(files_style)
+                .title_style(self.theme.title)
                 .title("Files");
 
             let files = self.files.iter().map(|file| {
@@ -144,9 +185,7 @@ impl EditScreen {
                 ];
 
                 if file.removed {
-                    file_lines.push(
-                        Line::from("Removed").style(Style::default().italic().fg(Color::Red)),
-                    );
+                    file_lines.push(Line::from("Removed").style(self.theme.error.italic()));
                     file_lines.push(Line::from("\n"));
                 } else {
                     file_lines.push(Line::from("\n"));
@@ -154,49 +193,62 @@ impl EditScreen {
 
                 let formatted = Text::from(file_lines);
                 let style = if file.removed {
-                    Style::default().fg(Color::Gray)
+                    self.theme.muted
                 } else {
-                    Style::default().fg(Color::Cyan)
+                    self.theme.accent
                 };
                 ListItem::new(formatted).style(style)
             });
             let list = List::new(files)
-                .style(Style::new().green())
+                .style(self.theme.text)
                 .highlight_symbol("-> ")
                 .repeat_highlight_symbol(false)
                 .block(files_block);
             f.render_stateful_widget(list, chunks[2], &mut self.selected_file);
         }
 
-        let chunk = if !self.files.is_empty() {
-            chunks[3]
+        let (files_chunk, chunk, attachments_chunk) = if !self.files.is_empty() {
+            (chunks[2], chunks[3], chunks[4])
         } else {
-            chunks[2]
+            (Rect::default(), chunks[2], chunks[3])
         };
+        self.field_areas = [chunks[0], chunks[1], files_chunk, chunk, attachments_chunk];
 
-        let expire_at_style = if matches!(self.focused_element, EditElements::Expire) {
-            Style::default().fg(Color::Blue)
-        } else {
-            Style::default().fg(Color::Gray)
-        };
+        let expire_at_style = self
+            .theme
+            .border_style(matches!(self.focused_element, EditElements::Expire));
         let expire_at_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
             .border_style(expire_at_style)
-            .style(Style::new().green())
+            .style(self.theme.text)
+            .title_style(self.theme.title)
             .title("Extend Expire time by");
         let expire_at_widget = self.expire_at.clone().block(expire_at_block);
         f.render_widget(&expire_at_widget, chunk);
+
+        let attach_focused = matches!(self.focused_element, EditElements::Attach);
+        self.attachments
+            .render(f, attachments_chunk, attach_focused);
     }
 
-    fn help_widget(&self, area: Rect, f: &mut Frame) {
-        let help_message = if matches!(self.focused_element, EditElements::Files) {
-            "(Esc) to cancel edit / (Ctrl-S) to save / (r) to toggle file remove"
-        } else {
-            "(Esc) to cancel edit / (Ctrl-S) to save"
+    fn help_widget(&self, area: Rect, f: &mut Frame, keymap: &Keymap) {
+        let cancel = keymap.hint(Context::Form, Action::Cancel);
+        let save = keymap.hint(Context::Form, Action::Save);
+        let help_message = match self.focused_element {
+            EditElements::Files => format!(
+                "({cancel}) to cancel edit / ({save}) to save / ({}) to toggle file remove",
+                keymap.hint(Context::Form, Action::ToggleRemove),
+            ),
+            EditElements::Attach => format!(
+                "({cancel}) to cancel edit / ({save}) to upload and save / ({}) to attach file / ({}) to remove last file",
+                keymap.hint(Context::Form, Action::Confirm),
+                keymap.hint(Context::Form, Action::RemoveLastFile),
+            ),
+            _ => format!("({cancel}) to cancel edit / ({save}) to save"),
         };
 
-        let help_widget = Span::styled(help_message, Style::default().fg(Color::Blue));
+        let help_widget = Span::styled(help_message, self.theme.muted);
         let help_widget = Paragraph::new(Line::from(help_widget)).block(
             Block::default()
                 .borders(Borders::ALL)
@@ -205,43 +257,33 @@ impl EditScreen {
         f.render_widget(help_widget, area);
     }
 
-    pub fn handle_key(&mut self, e: KeyEvent) -> Option<BinActions> {
-        match e.code {
-            KeyCode::Esc => Some(BinActions::ChangeScreen(
-                CurrentScreen::Show,
-                Some(self.bin.clone()),
-            )),
-            KeyCode::Char('s') if e.modifiers == KeyModifiers::CONTROL => {
-                let edited_title = self.title_input.content();
-                let edited_content = self.content_input.content();
-                let expire_at = self.expire_at.time();
-
-                Some(BinActions::SendEvent(Some(json!({
-                   "action": "edit",
-                   "data": {
-                        "id": self.bin.id,
-                        "title": edited_title,
-                        "content": edited_content,
-                        "files": self.files,
-                        "expire": {"time": expire_at.time, "unit": expire_at.unit.to_string()}
-                   }
-                }))))
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<BinActions> {
+        match self.action(keymap, &e) {
+            Some(Action::Cancel) => Some(BinActions::Back),
+            Some(Action::Save) => {
+                if self.attachments.is_uploading() {
+                    None
+                } else if !self.attachments.all_uploaded() {
+                    Some(BinActions::UploadAttachments)
+                } else {
+                    Some(BinActions::SendEvent(self.request()))
+                }
             }
-            KeyCode::Char('j') if matches!(self.focused_element, EditElements::Files) => {
+            Some(Action::Down) if matches!(self.focused_element, EditElements::Files) => {
                 self.select_edit_next_file();
                 None
             }
-            KeyCode::Char('k') if matches!(self.focused_element, EditElements::Files) => {
+            Some(Action::Up) if matches!(self.focused_element, EditElements::Files) => {
                 self.select_edit_previous_file();
                 None
             }
-            KeyCode::Char('r') if matches!(self.focused_element, EditElements::Files) => {
+            Some(Action::ToggleRemove) if matches!(self.focused_element, EditElements::Files) => {
                 if let Some(selected_index) = self.selected_file.selected() {
                     self.files[selected_index].removed = !self.files[selected_index].removed;
                 }
                 None
             }
-            KeyCode::Tab => {
+            Some(Action::NextField) => {
                 match self.focused_element {
                     EditElements::Title => self.focused_element = EditElements::Content,
                     EditElements::Content => {
@@ -252,22 +294,80 @@ impl EditScreen {
                         }
                     }
                     EditElements::Files => self.focused_element = EditElements::Expire,
-                    EditElements::Expire => self.focused_element = EditElements::Title,
+                    EditElements::Expire => self.focused_element = EditElements::Attach,
+                    EditElements::Attach => self.focused_element = EditElements::Title,
                 }
                 None
             }
             _ => {
                 match self.focused_element {
-                    EditElements::Title => self.title_input.handle_key(e),
-                    EditElements::Content => self.content_input.handle_key(e),
-                    EditElements::Expire => self.expire_at.handle_key(e),
-                    _ => {}
+                    EditElements::Title => self.title_input.handle_key(keymap, e),
+                    EditElements::Content => self.content_input.handle_key(keymap, e),
+                    EditElements::Expire => self.expire_at.handle_key(keymap, e),
+                    EditElements::Attach => self.attachments.handle_key(keymap, e),
+                    EditElements::Files => {}
                 }
                 None
             }
         }
     }
 
+    pub fn handle_mouse(&mut self, mouse: Mouse) -> Option<BinActions> {
+        let index = self
+            .field_areas
+            .iter()
+            .position(|&area| mouse.is_in(area))?;
+        match (index, mouse) {
+            (2, Mouse::Click(position) | Mouse::DoubleClick(position)) => {
+                self.focused_element = EditElements::Files;
+                let inner = self.field_areas[2].inner(Margin::new(1, 1));
+                if let Some(index) = self.file_at(inner, position.y) {
+                    self.selected_file.select(Some(index));
+                }
+            }
+            (2, Mouse::ScrollUp(_)) => self.select_edit_previous_file(),
+            (2, Mouse::ScrollDown(_)) => self.select_edit_next_file(),
+            (_, Mouse::Click(_)) => {
+                self.focused_element = match index {
+                    0 => EditElements::Title,
+                    1 => EditElements::Content,
+                    3 => EditElements::Expire,
+                    _ => EditElements::Attach,
+                };
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Index of the file drawn on line `y` of the list in `area`. Removed files take a line more.
+    fn file_at(&self, area: Rect, y: u16) -> Option<usize> {
+        if y < area.y {
+            return None;
+        }
+        let mut bottom = area.y;
+        for (index, file) in self
+            .files
+            .iter()
+            .enumerate()
+            .skip(self.selected_file.offset())
+        {
+            bottom += if file.removed { 4 } else { 3 };
+            if y < bottom {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    /// The file list takes list keys; the other fields take text, where characters are typed.
+    fn action(&self, keymap: &Keymap, e: &KeyEvent) -> Option<Action> {
+        match self.focused_element {
+            EditElements::Files => keymap.action(&[Context::Form, Context::List], e),
+            _ => keymap.action_in_text(&[Context::Form], e),
+        }
+    }
+
     fn select_edit_previous_file(&mut self) {
         self.selected_file.select_previous();
     }
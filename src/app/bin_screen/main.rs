@@ -1,72 +1,201 @@
-use super::{AppActions, Bin, BinActions, CurrentScreen, ScreenType};
-use crossterm::event::{KeyCode, KeyEvent};
+use super::{AppActions, BinActions};
+use crate::app::screen::Keybinding;
+use crate::app::store::Store;
+use crate::keymap::{Action, Context, Keymap};
+use crate::sqlite::outbox::{OutboxItem, OutboxStatus};
+use crate::theme::Theme;
+use crate::tui::mouse::{item_at, Mouse};
+use crossterm::event::KeyEvent;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Stylize},
     text::{Line, Span, Text},
     widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph},
     Frame,
 };
+use tokio::sync::watch;
+
+pub const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::list(Action::Down, "next bin"),
+    Keybinding::list(Action::Up, "previous bin"),
+    Keybinding::list(Action::Open, "see the bin"),
+    Keybinding::list(Action::New, "add a new bin"),
+    Keybinding::list(Action::SwitchPane, "switch between bins and outbox"),
+    Keybinding::list(Action::Discard, "discard the queued outbox item"),
+    Keybinding::list(Action::Retry, "retry the failed outbox item"),
+    Keybinding::global(Action::Back, "back"),
+    Keybinding::global(Action::Quit, "quit"),
+];
 
 pub struct MainScreen {
-    pub bins: Vec<Bin>,
+    store: watch::Receiver<Store>,
     pub list_state: ListState,
+    pub outbox: Vec<OutboxItem>,
+    outbox_state: ListState,
+    focused_element: MainElements,
+    /// Where the bins and the outbox were rendered, inside their borders.
+    bins_area: Rect,
+    outbox_area: Rect,
+    theme: Theme,
+}
+
+enum MainElements {
+    Bins,
+    Outbox,
 }
 
 impl MainScreen {
-    pub fn new(bins: Vec<Bin>) -> Self {
-        let bin_screen = Self {
-            bins,
+    pub fn new(store: watch::Receiver<Store>, theme: Theme) -> Self {
+        Self {
+            store,
             list_state: ListState::default(),
-        };
-        bin_screen
+            outbox: Vec::new(),
+            outbox_state: ListState::default(),
+            focused_element: MainElements::Bins,
+            bins_area: Rect::default(),
+            outbox_area: Rect::default(),
+            theme,
+        }
+    }
+
+    pub fn set_outbox(&mut self, outbox: Vec<OutboxItem>) {
+        self.outbox = outbox;
+        if self.outbox.is_empty() {
+            self.focused_element = MainElements::Bins;
+            self.outbox_state.select(None);
+        } else if self.outbox_state.selected().is_none() {
+            self.outbox_state.select_first();
+        }
     }
 
-    pub fn render(&mut self, f: &mut Frame) {
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(1), Constraint::Length(3)])
-            .split(f.area());
+    pub fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        if self.outbox.is_empty() {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(1), Constraint::Length(3)])
+                .split(area);
+
+            self.menu_items_widget(chunks[0], f);
+            self.help_widget(chunks[1], f, keymap);
+            self.outbox_area = Rect::default();
+        } else {
+            let outbox_height = (self.outbox.len() as u16 + 2).min(8);
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Min(1),
+                    Constraint::Length(outbox_height),
+                    Constraint::Length(3),
+                ])
+                .split(area);
 
-        self.menu_items_widget(chunks[0], f);
-        self.help_widget(chunks[1], f);
+            self.menu_items_widget(chunks[0], f);
+            self.outbox_widget(chunks[1], f);
+            self.help_widget(chunks[2], f, keymap);
+        }
     }
 
     // Keyboard event handler
 
-    pub fn handle_key(&mut self, e: KeyEvent) -> Option<BinActions> {
-        match e.code {
-            KeyCode::Char('q') | KeyCode::Esc => Some(BinActions::App(AppActions::Quit)),
-            KeyCode::Char('b') => Some(BinActions::App(AppActions::ChangeScreen(ScreenType::Main))),
-            KeyCode::Char('j') | KeyCode::Down => {
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<BinActions> {
+        let outbox_focused = matches!(self.focused_element, MainElements::Outbox);
+        match keymap.action(&[Context::List, Context::Global], &e)? {
+            Action::Quit => Some(BinActions::App(AppActions::Quit)),
+            Action::Back => Some(BinActions::App(AppActions::Back)),
+            Action::SwitchPane if !self.outbox.is_empty() => {
+                self.focused_element = match self.focused_element {
+                    MainElements::Bins => MainElements::Outbox,
+                    MainElements::Outbox => MainElements::Bins,
+                };
+                None
+            }
+            Action::Down if outbox_focused => {
+                self.outbox_state.select_next();
+                None
+            }
+            Action::Up if outbox_focused => {
+                self.outbox_state.select_previous();
+                None
+            }
+            Action::Discard if outbox_focused => {
+                let item = self.selected_outbox_item()?;
+                if item.status == OutboxStatus::Sending {
+                    return None;
+                }
+                Some(BinActions::DiscardOutboxItem(item.id))
+            }
+            Action::Retry if outbox_focused => {
+                let item = self.selected_outbox_item()?;
+                // Unreadable items would fail again.
+                if item.status != OutboxStatus::Failed || item.request.is_none() {
+                    return None;
+                }
+                Some(BinActions::RetryOutboxItem(item.id))
+            }
+            Action::Down => {
                 self.select_next();
                 None
             }
-            KeyCode::Char('k') | KeyCode::Up => {
+            Action::Up => {
                 self.select_previous();
                 None
             }
-            KeyCode::Char('g') | KeyCode::Home => {
+            Action::First => {
                 self.select_first();
                 None
             }
-            KeyCode::Char('G') | KeyCode::End => {
+            Action::Last => {
                 self.select_last();
                 None
             }
-            KeyCode::Char('l') | KeyCode::Enter => {
-                if let Some(index) = self.list_state.selected() {
-                    let selected = self.bins[index].clone();
-                    return Some(BinActions::ChangeScreen(
-                        CurrentScreen::Show,
-                        Some(selected),
-                    ));
+            Action::Open => self.open_selected(),
+            Action::New => Some(BinActions::PushNew),
+            _ => None,
+        }
+    }
+
+    pub fn handle_mouse(&mut self, mouse: Mouse) -> Option<BinActions> {
+        if mouse.is_in(self.outbox_area) {
+            self.focused_element = MainElements::Outbox;
+            match mouse {
+                Mouse::Click(position) | Mouse::DoubleClick(position) => {
+                    let index = item_at(self.outbox_area, self.outbox_state.offset(), 1, position)
+                        .filter(|&index| index < self.outbox.len())?;
+                    self.outbox_state.select(Some(index));
                 }
-                None
+                Mouse::ScrollUp(_) => self.outbox_state.select_previous(),
+                Mouse::ScrollDown(_) => self.outbox_state.select_next(),
             }
-            KeyCode::Char('n') => Some(BinActions::ChangeScreen(CurrentScreen::New, None)),
-            _ => None,
+            return None;
+        }
+        if !mouse.is_in(self.bins_area) {
+            return None;
         }
+        self.focused_element = MainElements::Bins;
+        match mouse {
+            Mouse::Click(position) | Mouse::DoubleClick(position) => {
+                let len = self.store.borrow().bins.len();
+                // Each bin takes three lines: title, expiry and a blank line.
+                let index = item_at(self.bins_area, self.list_state.offset(), 3, position)
+                    .filter(|&index| index < len)?;
+                self.list_state.select(Some(index));
+                if let Mouse::DoubleClick(_) = mouse {
+                    return self.open_selected();
+                }
+            }
+            Mouse::ScrollUp(_) => self.select_previous(),
+            Mouse::ScrollDown(_) => self.select_next(),
+        }
+        None
+    }
+
+    fn open_selected(&self) -> Option<BinActions> {
+        let index = self.list_state.selected()?;
+        let selected = self.store.borrow().bins.get(index).cloned()?;
+        Some(BinActions::PushShow(selected))
+    }
+
+    fn selected_outbox_item(&self) -> Option<&OutboxItem> {
+        self.outbox.get(self.outbox_state.selected()?)
     }
 
     fn select_next(&mut self) {
@@ -77,7 +206,7 @@ impl MainScreen {
         self.list_state.select_previous();
     }
 
-    pub fn select_first(&mut self) {
+    fn select_first(&mut self) {
         self.list_state.select_first();
     }
 
@@ -86,12 +215,20 @@ impl MainScreen {
     }
 
     fn menu_items_widget(&mut self, area: Rect, f: &mut Frame) {
-        let items = self.bins.iter().map(|item| {
+        let store = self.store.borrow();
+        if self.list_state.selected().is_none() && !store.bins.is_empty() {
+            self.list_state.select_first();
+        }
+
+        let items = store.bins.iter().map(|item| {
             let mut text = Text::default();
             let formatted_date_time = item.expire_at.format("%d/%m/%Y %I:%M %p").to_string();
             text.extend([
                 Span::raw(&item.title),
-                Span::raw(format!("Expire at: {formatted_date_time}")).blue(),
+                Span::styled(
+                    format!("Expire at: {formatted_date_time}"),
+                    self.theme.accent,
+                ),
                 Span::raw(""),
             ]);
             ListItem::new(text)
@@ -100,25 +237,84 @@ impl MainScreen {
         let block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Green))
-            .style(Style::new().green())
+            .border_style(self.theme.border)
+            .style(self.theme.text)
+            .title_style(self.theme.title)
             .title("Bin");
+        self.bins_area = block.inner(area);
 
         let list = List::new(items)
             .block(block)
-            .highlight_style(Style::new().reversed())
-            .style(Style::new().green())
+            .highlight_style(self.theme.highlight)
+            .style(self.theme.text)
             .highlight_symbol("-> ")
             .repeat_highlight_symbol(false);
 
         f.render_stateful_widget(list, area, &mut self.list_state);
     }
 
-    fn help_widget(&self, area: Rect, f: &mut Frame) {
-        let help_message =
-            "(q) to quit / (b) back to main menu / (n) to add a new bin / (l) to see a bin";
+    fn outbox_widget(&mut self, area: Rect, f: &mut Frame) {
+        let items = self.outbox.iter().map(|item| {
+            let status_style = match item.status {
+                OutboxStatus::Queued => self.theme.warning,
+                OutboxStatus::Sending => self.theme.accent,
+                OutboxStatus::Failed => self.theme.error,
+            };
+            let mut spans = vec![
+                Span::styled(format!("[{}] ", item.status), status_style),
+                Span::raw(item.summary()),
+            ];
+            if let Some(error) = &item.error {
+                spans.push(Span::styled(format!(" - {error}"), self.theme.error));
+            }
+            ListItem::new(Line::from(spans))
+        });
+
+        let border_style = self
+            .theme
+            .border_style(matches!(self.focused_element, MainElements::Outbox));
+        let block = Block::new()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .border_style(border_style)
+            .style(self.theme.text)
+            .title_style(self.theme.title)
+            .title("Outbox (waiting for connection)");
+        self.outbox_area = block.inner(area);
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(self.theme.highlight)
+            .highlight_symbol("-> ")
+            .repeat_highlight_symbol(false);
+
+        f.render_stateful_widget(list, area, &mut self.outbox_state);
+    }
+
+    fn help_widget(&self, area: Rect, f: &mut Frame, keymap: &Keymap) {
+        let switch_pane = keymap.hint(Context::List, Action::SwitchPane);
+        let help_message = match self.focused_element {
+            MainElements::Outbox => format!(
+                "({switch_pane}) back to bins / ({}) to discard queued item / ({}) to retry failed item",
+                keymap.hint(Context::List, Action::Discard),
+                keymap.hint(Context::List, Action::Retry),
+            ),
+            MainElements::Bins => {
+                let mut help_message = format!(
+                    "({}) to quit / ({}) to go back / ({}) to add a new bin / ({}) to see a bin",
+                    keymap.hint(Context::Global, Action::Quit),
+                    keymap.hint(Context::Global, Action::Back),
+                    keymap.hint(Context::List, Action::New),
+                    keymap.hint(Context::List, Action::Open),
+                );
+                if !self.outbox.is_empty() {
+                    help_message.push_str(&format!(" / ({switch_pane}) to outbox"));
+                }
+                help_message
+            }
+        };
 
-        let help_widget = Span::styled(help_message, Style::default().fg(Color::Blue));
+        let help_widget = Span::styled(help_message, self.theme.muted);
         let help_widget = Paragraph::new(Line::from(help_widget)).block(
             Block::default()
                 .borders(Borders::ALL)
@@ -1,79 +1,95 @@
-use super::{BinActions, CurrentScreen};
+use super::attachments::Attachments;
+use super::{BinActions, BinRequest, NewBin};
+use crate::app::screen::Keybinding;
+use crate::keymap::{Action, Context, Keymap};
+use crate::theme::Theme;
 use crate::tui::expire_at_input_widget::ExpireAtWidget;
 use crate::tui::input_widget::InputWidget;
+use crate::tui::mouse::Mouse;
 use crate::tui::multiline_input_widget::MultilineInput;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::KeyEvent;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Stylize},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Paragraph},
     Frame,
 };
-use serde_json::json;
+
+pub const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::form(Action::Save, "upload the files and save"),
+    Keybinding::form(Action::NextField, "next field"),
+    Keybinding::form(Action::Confirm, "attach the file"),
+    Keybinding::form(Action::RemoveLastFile, "remove the last file"),
+    Keybinding::form(Action::Cancel, "cancel"),
+];
 
 pub struct NewScreen {
     title_input: InputWidget<'static>,
     content_input: MultilineInput<'static>,
     expire_at: ExpireAtWidget<'static>,
+    pub attachments: Attachments,
     focused_element: NewElements,
+    /// Where the title, content, expiry and attachments were rendered, to focus them on click.
+    field_areas: [Rect; 4],
+    theme: Theme,
 }
 
 enum NewElements {
     Title,
     Content,
     Expire,
+    Attach,
 }
 
 impl NewScreen {
-    pub fn new() -> Self {
+    pub fn new(theme: Theme) -> Self {
         Self {
-            title_input: InputWidget::new(
-                String::new(),
-                Style::default(),
-                Style::default().fg(Color::Black).bg(Color::White),
-            ),
-            content_input: MultilineInput::new(String::new()),
-            expire_at: ExpireAtWidget::new(
-                Style::default(),
-                Style::default().fg(Color::Black).bg(Color::White),
-            ),
+            title_input: InputWidget::new(String::new(), &theme),
+            content_input: MultilineInput::new(String::new(), &theme),
+            expire_at: ExpireAtWidget::new(&theme),
+            attachments: Attachments::new(theme),
             focused_element: NewElements::Title,
+            field_areas: [Rect::default(); 4],
+            theme,
         }
     }
 
-    pub fn render(&mut self, f: &mut Frame) {
+    /// The bin as filled in, with the finished uploads attached.
+    pub fn request(&self) -> BinRequest {
+        let data = NewBin {
+            title: self.title_input.content().to_string(),
+            content: self.content_input.content(),
+            expire: self.expire_at.time().into(),
+            uploads: self.attachments.upload_ids(),
+        };
+        BinRequest::New { data }
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Min(1), Constraint::Length(3)])
-            .split(f.area());
+            .split(area);
 
         self.new_bin_widget(chunks[0], f);
-        self.help_widget(chunks[1], f);
+        self.help_widget(chunks[1], f, keymap);
     }
 
     // Keyboard event handler
 
-    pub fn handle_key(&mut self, e: KeyEvent) -> Option<BinActions> {
-        match e.code {
-            KeyCode::Esc => Some(BinActions::ChangeScreen(CurrentScreen::Main, None)),
-            KeyCode::Char('s') if e.modifiers == KeyModifiers::CONTROL => {
-                let title = self.title_input.content();
-                let content = self.content_input.content();
-                let expire_at = self.expire_at.time();
-
-                let event = Some(json!({
-                    "action": "new",
-                    "data": {
-                        "title": title,
-                        "content": content,
-                        "expire": {"time": expire_at.time, "unit": expire_at.unit.to_string()}
-                    }
-                }));
-
-                Some(BinActions::SendEvent(event))
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<BinActions> {
+        match self.action(keymap, &e) {
+            Some(Action::Cancel) => Some(BinActions::Back),
+            Some(Action::Save) => {
+                if self.attachments.is_uploading() {
+                    None
+                } else if !self.attachments.all_uploaded() {
+                    Some(BinActions::UploadAttachments)
+                } else {
+                    Some(BinActions::SendEvent(self.request()))
+                }
             }
-            KeyCode::Tab => {
+            Some(Action::NextField) => {
                 match self.focused_element {
                     NewElements::Title => {
                         self.focused_element = NewElements::Content;
@@ -82,6 +98,9 @@ impl NewScreen {
                         self.focused_element = NewElements::Expire;
                     }
                     NewElements::Expire => {
+                        self.focused_element = NewElements::Attach;
+                    }
+                    NewElements::Attach => {
                         self.focused_element = NewElements::Title;
                     }
                 }
@@ -89,72 +108,107 @@ impl NewScreen {
             }
             _ => {
                 match self.focused_element {
-                    NewElements::Title => self.title_input.handle_key(e),
-                    NewElements::Content => self.content_input.handle_key(e),
-                    NewElements::Expire => self.expire_at.handle_key(e),
+                    NewElements::Title => self.title_input.handle_key(keymap, e),
+                    NewElements::Content => self.content_input.handle_key(keymap, e),
+                    NewElements::Expire => self.expire_at.handle_key(keymap, e),
+                    NewElements::Attach => self.attachments.handle_key(keymap, e),
                 }
                 None
             }
         }
     }
 
+    pub fn handle_mouse(&mut self, mouse: Mouse) -> Option<BinActions> {
+        let Mouse::Click(_) = mouse else {
+            return None;
+        };
+        let index = self
+            .field_areas
+            .iter()
+            .position(|&area| mouse.is_in(area))?;
+        self.focused_element = match index {
+            0 => NewElements::Title,
+            1 => NewElements::Content,
+            2 => NewElements::Expire,
+            _ => NewElements::Attach,
+        };
+        None
+    }
+
+    /// Every field takes text, so characters are always typed.
+    fn action(&self, keymap: &Keymap, e: &KeyEvent) -> Option<Action> {
+        keymap.action_in_text(&[Context::Form], e)
+    }
+
     fn new_bin_widget(&mut self, area: Rect, f: &mut Frame) {
         let chunks = Layout::default()
             .constraints([
                 Constraint::Length(4),
                 Constraint::Min(1),
                 Constraint::Length(3),
+                Constraint::Length(self.attachments.height()),
             ])
             .split(area);
+        self.field_areas = [chunks[0], chunks[1], chunks[2], chunks[3]];
 
-        let title_style = if matches!(self.focused_element, NewElements::Title) {
-            Style::default().fg(Color::Blue)
-        } else {
-            Style::default().fg(Color::Gray)
-        };
+        let title_style = self
+            .theme
+            .border_style(matches!(self.focused_element, NewElements::Title));
 
         let title_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
             .border_style(title_style)
-            .style(Style::new().green())
+            .style(self.theme.text)
+            .title_style(self.theme.title)
             .title("Title");
         let title_widget = self.title_input.clone().block(title_block);
         f.render_widget(&title_widget, chunks[0]);
 
-        let content_style = if matches!(self.focused_element, NewElements::Content) {
-            Style::default().fg(Color::Blue)
-        } else {
-            Style::default().fg(Color::Gray)
-        };
+        let content_style = self
+            .theme
+            .border_style(matches!(self.focused_element, NewElements::Content));
         let content_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
             .border_style(content_style)
-            .style(Style::new().green())
+            .style(self.theme.text)
+            .title_style(self.theme.title)
             .title("Content");
         let content_widget = self.content_input.clone().block(content_block);
         f.render_widget(&content_widget, chunks[1]);
 
-        let expire_at_style = if matches!(self.focused_element, NewElements::Expire) {
-            Style::default().fg(Color::Blue)
-        } else {
-            Style::default().fg(Color::Gray)
-        };
+        let expire_at_style = self
+            .theme
+            .border_style(matches!(self.focused_element, NewElements::Expire));
         let expire_at_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
             .border_style(expire_at_style)
-            .style(Style::new().green())
+            .style(self.theme.text)
+            .title_style(self.theme.title)
             .title("Expire in");
         let expire_at_widget = self.expire_at.clone().block(expire_at_block);
         f.render_widget(&expire_at_widget, chunks[2]);
+
+        let attach_focused = matches!(self.focused_element, NewElements::Attach);
+        self.attachments.render(f, chunks[3], attach_focused);
     }
 
-    fn help_widget(&self, area: Rect, f: &mut Frame) {
-        let help_message = "(Esc) to cancel / (Ctrl-S) to save";
+    fn help_widget(&self, area: Rect, f: &mut Frame, keymap: &Keymap) {
+        let cancel = keymap.hint(Context::Form, Action::Cancel);
+        let save = keymap.hint(Context::Form, Action::Save);
+        let help_message = if matches!(self.focused_element, NewElements::Attach) {
+            format!(
+                "({cancel}) to cancel / ({save}) to upload and save / ({}) to attach file / ({}) to remove last file",
+                keymap.hint(Context::Form, Action::Confirm),
+                keymap.hint(Context::Form, Action::RemoveLastFile),
+            )
+        } else {
+            format!("({cancel}) to cancel / ({save}) to save")
+        };
 
-        let help_widget = Span::styled(help_message, Style::default().fg(Color::Blue));
+        let help_widget = Span::styled(help_message, self.theme.muted);
         let help_widget = Paragraph::new(Line::from(help_widget)).block(
             Block::default()
                 .borders(Borders::ALL)
@@ -1,86 +1,168 @@
-use super::{AppActions, Bin, BinActions, CurrentScreen};
-use cli_log::info;
-use crossterm::event::{KeyCode, KeyEvent};
+use super::{AppActions, Bin, BinActions, BinId, BinRequest};
+use crate::app::notifications::Notifier;
+use crate::app::screen::Keybinding;
+use crate::app::store::Store;
+use crate::config::Profile;
+use crate::keymap::{Action, Context, Keymap};
+use crate::theme::Theme;
+use crate::tui::mouse::{item_at, Mouse, SCROLL_LINES};
+use crossterm::event::KeyEvent;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Stylize},
     text::{Line, Span, Text},
     widgets::{Block, BorderType, Borders, List, ListItem, ListState, Padding, Paragraph},
     Frame,
 };
-use serde_json::json;
+use tokio::sync::watch;
 
-const ENDPOINT: &str = if cfg!(debug_assertions) {
-    "http://localhost:4000"
-} else {
-    "https://phoenix.aayushsahu.com"
-};
+pub const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::list(Action::Down, "next file"),
+    Keybinding::list(Action::Up, "previous file"),
+    Keybinding::list(Action::OpenFile, "open the file"),
+    Keybinding::list(Action::Edit, "edit the bin"),
+    Keybinding::list(Action::Delete, "delete the bin"),
+    Keybinding::global(Action::Back, "back"),
+    Keybinding::global(Action::Quit, "quit"),
+];
 
 pub struct ShowScreen {
     bin: Bin,
+    store: watch::Receiver<Store>,
+    /// Server file links point to.
+    profile: Profile,
     list_state: ListState,
+    /// Lines of the content scrolled past with the wheel.
+    content_scroll: u16,
+    /// Where the content and the files were rendered, inside their borders.
+    content_area: Rect,
+    files_area: Rect,
+    notifier: Notifier,
+    theme: Theme,
 }
 
 impl ShowScreen {
-    pub fn new(bin: Bin) -> Self {
+    pub fn new(
+        bin: Bin,
+        store: watch::Receiver<Store>,
+        profile: Profile,
+        notifier: Notifier,
+        theme: Theme,
+    ) -> Self {
         Self {
             bin,
+            store,
+            profile,
             list_state: ListState::default().with_selected(None),
+            content_scroll: 0,
+            content_area: Rect::default(),
+            files_area: Rect::default(),
+            notifier,
+            theme,
         }
     }
 
-    pub fn render(&mut self, f: &mut Frame) {
+    pub fn bin_id(&self) -> u64 {
+        self.bin.id
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        self.sync_bin();
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Min(1), Constraint::Length(3)])
-            .split(f.area());
+            .split(area);
 
         self.bin_widget(chunks[0], f);
-        self.help_widget(chunks[1], f);
+        self.help_widget(chunks[1], f, keymap);
     }
 
     // Keyboard event handler
 
-    pub fn handle_key(&mut self, e: KeyEvent) -> Option<BinActions> {
-        match e.code {
-            KeyCode::Char('q') | KeyCode::Esc => Some(BinActions::App(AppActions::Quit)),
-            KeyCode::Char('b') => Some(BinActions::ChangeScreen(CurrentScreen::Main, None)),
-            KeyCode::Char('j') | KeyCode::Down => {
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<BinActions> {
+        match keymap.action(&[Context::List, Context::Global], &e)? {
+            Action::Quit => Some(BinActions::App(AppActions::Quit)),
+            Action::Back => Some(BinActions::Back),
+            Action::Down => {
                 if !self.bin.files.is_empty() {
                     self.select_next_file();
                 }
                 None
             }
-            KeyCode::Char('k') | KeyCode::Up => {
+            Action::Up => {
                 if !self.bin.files.is_empty() {
                     self.select_previous_file();
                 }
                 None
             }
-            KeyCode::Char('d') => {
-                let event = Some(json!({"action": "delete", "data": {"id": self.bin.id}}));
-                Some(BinActions::SendEvent(event))
+            Action::Delete => {
+                let data = BinId { id: self.bin.id };
+                Some(BinActions::SendEvent(BinRequest::Delete { data }))
+            }
+            Action::OpenFile => {
+                self.open_selected_file();
+                None
+            }
+            Action::Edit => Some(BinActions::PushEdit(self.bin.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn handle_mouse(&mut self, mouse: Mouse) -> Option<BinActions> {
+        if mouse.is_in(self.content_area) {
+            match mouse {
+                Mouse::ScrollUp(_) => {
+                    self.content_scroll = self.content_scroll.saturating_sub(SCROLL_LINES)
+                }
+                Mouse::ScrollDown(_) => {
+                    self.content_scroll = self.content_scroll.saturating_add(SCROLL_LINES)
+                }
+                Mouse::Click(_) | Mouse::DoubleClick(_) => {}
             }
-            KeyCode::Char('o') => {
-                if !self.list_state.selected().is_none() {
-                    let selected_index = self.list_state.selected().unwrap();
-                    let selected_file = &self.bin.files[selected_index];
-
-                    match open::that(format!("{}{}", ENDPOINT, selected_file.access_path)) {
-                        Ok(()) => {}
-                        Err(e) => info!("Failed to open file: {e}"),
+        } else if mouse.is_in(self.files_area) {
+            match mouse {
+                Mouse::Click(position) | Mouse::DoubleClick(position) => {
+                    // Each file takes three lines: name, type and a blank line.
+                    let index = item_at(self.files_area, self.list_state.offset(), 3, position)
+                        .filter(|&index| index < self.bin.files.len())?;
+                    self.list_state.select(Some(index));
+                    if let Mouse::DoubleClick(_) = mouse {
+                        self.open_selected_file();
                     }
                 }
-                None
+                Mouse::ScrollUp(_) => self.select_previous_file(),
+                Mouse::ScrollDown(_) => self.select_next_file(),
             }
-            KeyCode::Char('e') => {
-                let selected = self.bin.clone();
-                Some(BinActions::ChangeScreen(
-                    CurrentScreen::Edit,
-                    Some(selected),
-                ))
+        }
+        None
+    }
+
+    fn open_selected_file(&self) {
+        let Some(selected_index) = self.list_state.selected() else {
+            return;
+        };
+        let selected_file = &self.bin.files[selected_index];
+
+        match open::that(self.profile.file_url(&selected_file.access_path)) {
+            Ok(()) => {}
+            Err(e) => self
+                .notifier
+                .error(format!("Cannot open {}: {e}", selected_file.name)),
+        }
+    }
+
+    /// Pick up changes to the bin pushed while it is shown. Keeps the last copy if it was removed.
+    fn sync_bin(&mut self) {
+        if !self.store.has_changed().unwrap_or(false) {
+            return;
+        }
+        if let Some(bin) = self.store.borrow_and_update().bin(self.bin.id) {
+            self.bin = bin.clone();
+        }
+        if let Some(selected) = self.list_state.selected() {
+            if selected >= self.bin.files.len() {
+                self.list_state.select(None);
             }
-            _ => None,
         }
     }
 
@@ -104,14 +186,15 @@ impl ShowScreen {
         let top_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
-            .border_style(Style::default().fg(Color::Green))
-            .style(Style::new().green())
+            .border_style(self.theme.border)
+            .style(self.theme.text)
+            .title_style(self.theme.title)
             .title(format!("{} - Bin", self.bin.title));
 
         let formatted_date_time = self.bin.expire_at.format("%d/%m/%Y %I:%M %p").to_string();
         let expire_text = Text::style(
             format!("Expire at: {}\n\n", formatted_date_time).into(),
-            Style::default().fg(Color::Blue),
+            self.theme.accent,
         );
         let expire_paragraph = Paragraph::new(expire_text).block(top_block);
         f.render_widget(expire_paragraph, layout_chunks[0]);
@@ -119,13 +202,11 @@ impl ShowScreen {
         let content_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM)
-            .border_style(Style::default().fg(Color::Green))
-            .style(Style::new().green());
-        let content_text = Text::styled(
-            format!("{}", self.bin.content),
-            Style::default().fg(Color::Magenta),
-        );
-        let content_paragraph = Paragraph::new(content_text).block(content_block);
+            .border_style(self.theme.border)
+            .style(self.theme.text);
+        self.content_area = content_block.inner(layout_chunks[1]);
+        self.files_area = Rect::default();
+        let content_paragraph = self.content_paragraph().block(content_block);
         f.render_widget(content_paragraph, layout_chunks[1]);
     }
 
@@ -141,61 +222,79 @@ impl ShowScreen {
         let top_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
-            .border_style(Style::default().fg(Color::Green))
-            .style(Style::new().green())
+            .border_style(self.theme.border)
+            .style(self.theme.text)
+            .title_style(self.theme.title)
             .title(format!("{} - Bin", self.bin.title));
 
         let formatted_date_time = self.bin.expire_at.format("%d/%m/%Y %I:%M %p").to_string();
         let expire_text = Text::style(
             format!("Expire at: {}\n\n", formatted_date_time).into(),
-            Style::default().fg(Color::Blue),
+            self.theme.accent,
         );
         let expire_paragraph = Paragraph::new(expire_text).block(top_block);
         f.render_widget(expire_paragraph, layout_chunks[0]);
 
         let content_block = Block::new()
             .borders(Borders::LEFT | Borders::RIGHT)
-            .border_style(Style::default().fg(Color::Green))
-            .style(Style::new().green());
-        let content_text = Text::styled(
-            format!("{}", self.bin.content),
-            Style::default().fg(Color::Magenta),
-        );
-        let content_paragraph = Paragraph::new(content_text).block(content_block);
+            .border_style(self.theme.border)
+            .style(self.theme.text);
+        self.content_area = content_block.inner(layout_chunks[1]);
+        let content_paragraph = self.content_paragraph().block(content_block);
         f.render_widget(content_paragraph, layout_chunks[1]);
 
         let file_block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM)
-            .border_style(Style::default().fg(Color::Green))
-            .style(Style::new().green())
+            .border_style(self.theme.border)
+            .style(self.theme.text)
             .padding(Padding::top(1))
+            .title_style(self.theme.title)
             .title("Files:");
+        self.files_area = file_block.inner(layout_chunks[2]);
         let files = self.bin.files.iter().map(|file| {
             let formatted = Text::from(vec![
                 Line::from(file.name.as_str()),
-                Line::from(file.type_name.as_str()).style(Style::default().fg(Color::Blue)),
+                Line::from(file.type_name.as_str()).style(self.theme.muted),
                 Line::from("\n"),
             ]);
-            ListItem::new(formatted).style(Style::default().fg(Color::Cyan))
+            ListItem::new(formatted).style(self.theme.accent)
         });
         let list = List::new(files)
-            .highlight_style(Style::new().bg(Color::Green))
-            .style(Style::new().green())
+            .highlight_style(self.theme.highlight)
+            .style(self.theme.text)
             .highlight_symbol("-> ")
             .repeat_highlight_symbol(false)
             .block(file_block);
         f.render_stateful_widget(list, layout_chunks[2], &mut self.list_state);
     }
 
-    fn help_widget(&self, area: Rect, f: &mut Frame) {
-        let help_message = if self.list_state.selected().is_none() {
-            "(q) to quit / (b) back to bin menu / (d) to delete this bin / (e) to edit this bin"
+    /// The bin's content, scrolled with the wheel but never past its last line.
+    fn content_paragraph(&mut self) -> Paragraph<'static> {
+        let lines = self.bin.content.lines().count() as u16;
+        self.content_scroll = self.content_scroll.min(lines.saturating_sub(1));
+        let content_text = Text::styled(self.bin.content.to_string(), self.theme.text);
+        Paragraph::new(content_text).scroll((self.content_scroll, 0))
+    }
+
+    fn help_widget(&self, area: Rect, f: &mut Frame, keymap: &Keymap) {
+        let open_file = if self.list_state.selected().is_none() {
+            String::new()
         } else {
-            "(q) to quit / (b) back to bin menu / (o) open file / (d) to delete this bin / (e) to edit this bin"
+            format!(
+                " / ({}) open file",
+                keymap.hint(Context::List, Action::OpenFile)
+            )
         };
+        let help_message = format!(
+            "({}) to quit / ({}) to go back{open_file} / ({}) to delete this bin / ({}) to edit this bin",
+            keymap.hint(Context::Global, Action::Quit),
+            keymap.hint(Context::Global, Action::Back),
+            keymap.hint(Context::List, Action::Delete),
+            keymap.hint(Context::List, Action::Edit),
+        );
 
-        let help_widget = Span::styled(help_message, Style::default().fg(Color::Blue));
+        let help_widget = Span::styled(help_message, self.theme.muted);
         let help_widget = Paragraph::new(Line::from(help_widget)).block(
             Block::default()
                 .borders(Borders::ALL)
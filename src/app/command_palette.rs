@@ -0,0 +1,211 @@
+use super::screen::ScreenInfo;
+use crate::keymap::{Action, Context, Keymap};
+use crate::theme::Theme;
+use crate::tui::input_widget::InputWidget;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Constraint, Direction, Flex, Layout, Margin, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+/// Most matches listed at once.
+const MAX_MATCHES: usize = 12;
+
+/// Ctrl-P overlay fuzzy-searching the commands of the current screen, the screens and the
+/// entities they list, like bins and projects.
+pub struct CommandPalette {
+    input: InputWidget<'static>,
+    items: Vec<PaletteItem>,
+    /// Indexes into `items` matching the input, best first.
+    matches: Vec<usize>,
+    list_state: ListState,
+    theme: Theme,
+}
+
+pub struct PaletteItem {
+    pub label: String,
+    /// Where the item comes from, e.g. the key running the command or the screen of an entity.
+    pub detail: String,
+    pub command: Command,
+}
+
+pub enum Command {
+    /// Press a key on the current screen.
+    Key(KeyEvent),
+    /// Go to a screen, back to it if it is already open.
+    Open(&'static ScreenInfo),
+    /// Open an entity listed by a screen.
+    OpenEntity(&'static ScreenInfo, String),
+    Reconnect,
+    Quit,
+}
+
+pub enum PaletteAction {
+    Close,
+    Run(Command),
+}
+
+impl CommandPalette {
+    pub fn new(items: Vec<PaletteItem>, theme: Theme) -> Self {
+        let mut palette = Self {
+            input: InputWidget::new(String::new(), &theme),
+            items,
+            matches: Vec::new(),
+            list_state: ListState::default(),
+            theme,
+        };
+        palette.update_matches();
+        palette
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let height = self.matches.len().min(MAX_MATCHES) as u16 + 5;
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Start)
+            .areas(area.inner(Margin::new(0, 2)));
+        let [area] = Layout::horizontal([Constraint::Max(70)])
+            .flex(Flex::Center)
+            .areas(area);
+        f.render_widget(Clear, area);
+
+        let block = Block::new()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .border_style(self.theme.focused_border)
+            .title_style(self.theme.title)
+            .title("Commands");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(inner);
+        let input = self.input.clone().block(
+            Block::new()
+                .border_type(BorderType::Rounded)
+                .borders(Borders::ALL)
+                .border_style(self.theme.border),
+        );
+        f.render_widget(&input, chunks[0]);
+
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|&i| {
+                let item = &self.items[i];
+                ListItem::new(Line::from(vec![
+                    Span::raw(item.label.clone()),
+                    Span::styled(format!("  {}", item.detail), self.theme.muted),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(self.theme.highlight)
+            .style(self.theme.text);
+        f.render_stateful_widget(list, chunks[1], &mut self.list_state);
+    }
+
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<PaletteAction> {
+        let contexts = [Context::Form, Context::Global, Context::List];
+        match keymap.action_in_text(&contexts, &e) {
+            Some(Action::Cancel | Action::Palette) => Some(PaletteAction::Close),
+            Some(Action::Confirm) => {
+                let index = *self.matches.get(self.list_state.selected()?)?;
+                let item = self.items.swap_remove(index);
+                Some(PaletteAction::Run(item.command))
+            }
+            Some(Action::Down) => {
+                self.list_state.select_next();
+                None
+            }
+            Some(Action::Up) => {
+                self.list_state.select_previous();
+                None
+            }
+            _ => {
+                self.input.handle_key(keymap, e);
+                self.update_matches();
+                None
+            }
+        }
+    }
+
+    fn update_matches(&mut self) {
+        let query = self.input.content();
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| fuzzy_score(query, &item.label).map(|score| (score, i)))
+            .collect();
+        // Stable, so equally good matches keep the order they were listed in.
+        scored.sort_by_key(|(score, _)| -score);
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.list_state.select(if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+}
+
+/// How well `query` matches `text`, None if its characters don't all appear in order. Matches
+/// at the start of words and runs of consecutive characters score higher, gaps lower.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|&t| t == c)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        match previous {
+            Some(previous) if previous + 1 == found => score += 5,
+            Some(previous) => score -= (found - previous - 1).min(5) as i64,
+            None => score -= found.min(5) as i64,
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_must_appear_in_order() {
+        assert!(fuzzy_score("bin", "Bins").is_some());
+        assert!(fuzzy_score("nib", "Bins").is_none());
+        assert!(fuzzy_score("binx", "Bins").is_none());
+        assert_eq!(fuzzy_score("", "Bins"), Some(0));
+    }
+
+    #[test]
+    fn case_and_spaces_in_the_query_are_ignored() {
+        assert_eq!(fuzzy_score("BINS", "bins"), fuzzy_score("bins", "Bins"));
+        assert_eq!(
+            fuzzy_score("new bin", "New bin"),
+            fuzzy_score("newbin", "New bin")
+        );
+    }
+
+    #[test]
+    fn word_starts_and_runs_score_higher() {
+        let score = |query, text| fuzzy_score(query, text).unwrap();
+        // Start of the text, start of a word, inside a word.
+        assert!(score("b", "bins") > score("b", "new bin"));
+        assert!(score("b", "new bin") > score("b", "cabin"));
+        // One run against the same characters spread out.
+        assert!(score("bin", "bins") > score("bin", "brain"));
+        assert!(score("nb", "new bin") > score("nb", "nothing about bins"));
+    }
+}
@@ -0,0 +1,327 @@
+use crate::phoenix::event::{PhoenixEvent, SocketEvent};
+use crate::phoenix::protocol::Request;
+use crate::phoenix::request::RequestId;
+use crate::phoenix::upload::Upload;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, watch};
+
+/// Oldest events are dropped past this many.
+const CAPACITY: usize = 1000;
+
+/// Events exchanged with the transport this session, shown in the inspector.
+pub struct EventLog {
+    started: Instant,
+    entries: VecDeque<LoggedEvent>,
+}
+
+pub struct LoggedEvent {
+    /// Time since the app started.
+    pub at: Duration,
+    pub direction: EventDirection,
+    pub request_id: Option<RequestId>,
+    /// Feature and action, e.g. `bin new`.
+    pub name: String,
+    pub status: EventStatus,
+    /// The whole event, as recorded by `transport::record`.
+    pub json: Value,
+    /// Request of a sent event, which can be resent.
+    pub request: Option<Request>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EventDirection {
+    Sent,
+    Received,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum EventStatus {
+    /// Sent and not replied to yet.
+    Pending,
+    /// Replied to after the duration.
+    Ok(Duration),
+    Failed(String),
+    /// Upload still in progress.
+    Progress {
+        sent: u64,
+        total: u64,
+    },
+    /// Pushed by the server without a request.
+    Broadcast,
+}
+
+impl Display for EventDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventDirection::Sent => write!(f, "→"),
+            EventDirection::Received => write!(f, "←"),
+        }
+    }
+}
+
+impl Display for EventStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventStatus::Pending => write!(f, "pending"),
+            EventStatus::Ok(latency) => write!(f, "ok {}ms", latency.as_millis()),
+            EventStatus::Failed(e) => write!(f, "failed: {e}"),
+            EventStatus::Progress { sent, total } => write!(f, "{sent}/{total} bytes"),
+            EventStatus::Broadcast => write!(f, "broadcast"),
+        }
+    }
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &LoggedEvent> {
+        self.entries.iter()
+    }
+
+    pub fn sent(&mut self, event: &PhoenixEvent) {
+        let name = format!("{} {}", event.name(), event.request.action());
+        self.push(
+            EventDirection::Sent,
+            Some(event.request_id),
+            name,
+            EventStatus::Pending,
+            to_json(event),
+            Some(event.request.clone()),
+        );
+    }
+
+    pub fn upload(&mut self, upload: &Upload) {
+        let name = format!("bin upload {}", upload.path.display());
+        self.push(
+            EventDirection::Sent,
+            Some(upload.request_id),
+            name,
+            EventStatus::Pending,
+            to_json(upload),
+            None,
+        );
+    }
+
+    /// Log a received event, completing the request it replies to.
+    pub fn received(&mut self, event: &SocketEvent) {
+        let (request_id, name, status) = match event {
+            SocketEvent::Response {
+                request_id,
+                response,
+            } => {
+                let action = response
+                    .payload()
+                    .ok()
+                    .and_then(|payload| payload["action"].as_str().map(String::from))
+                    .unwrap_or_default();
+                let name = format!("{} {action}", response.feature().name());
+                let status = match (request_id, response.status()) {
+                    (None, _) => EventStatus::Broadcast,
+                    (Some(_), Some(status)) if !status.is_ok() => {
+                        EventStatus::Failed(status.error_message())
+                    }
+                    (Some(request_id), _) => EventStatus::Ok(self.latency(*request_id)),
+                };
+                (*request_id, name, status)
+            }
+            SocketEvent::Failed {
+                request_id,
+                feature,
+                error,
+            } => (
+                Some(*request_id),
+                format!("{} failed", feature.name()),
+                EventStatus::Failed(error.to_string()),
+            ),
+            SocketEvent::UploadProgress {
+                request_id,
+                sent,
+                total,
+            } => (
+                Some(*request_id),
+                "bin upload progress".to_string(),
+                EventStatus::Progress {
+                    sent: *sent,
+                    total: *total,
+                },
+            ),
+            SocketEvent::Uploaded { request_id, .. } => (
+                Some(*request_id),
+                "bin uploaded".to_string(),
+                EventStatus::Ok(self.latency(*request_id)),
+            ),
+            SocketEvent::Diagnostic(e) => (
+                None,
+                "diagnostic".to_string(),
+                EventStatus::Failed(e.to_string()),
+            ),
+        };
+
+        if let Some(request_id) = request_id {
+            if let Some(sent) = self.sent_entry(request_id) {
+                sent.status = status.clone();
+            }
+        }
+        self.push(
+            EventDirection::Received,
+            request_id,
+            name,
+            status,
+            to_json(event),
+            None,
+        );
+    }
+
+    fn push(
+        &mut self,
+        direction: EventDirection,
+        request_id: Option<RequestId>,
+        name: String,
+        status: EventStatus,
+        json: Value,
+        request: Option<Request>,
+    ) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LoggedEvent {
+            at: self.started.elapsed(),
+            direction,
+            request_id,
+            name,
+            status,
+            json,
+            request,
+        });
+    }
+
+    fn sent_entry(&mut self, request_id: RequestId) -> Option<&mut LoggedEvent> {
+        self.entries.iter_mut().rev().find(|entry| {
+            entry.direction == EventDirection::Sent && entry.request_id == Some(request_id)
+        })
+    }
+
+    /// Time since the request was sent, zero if it is no longer logged.
+    fn latency(&mut self, request_id: RequestId) -> Duration {
+        let now = self.started.elapsed();
+        self.sent_entry(request_id)
+            .map(|sent| now.saturating_sub(sent.at))
+            .unwrap_or_default()
+    }
+}
+
+fn to_json<T: serde::Serialize>(event: &T) -> Value {
+    serde_json::to_value(event).unwrap_or_else(|e| Value::String(e.to_string()))
+}
+
+/// Sender logging everything the channel takes in the event log, as it is sent. A full channel
+/// logs nothing and hands the item back, like `mpsc::Sender`.
+pub struct LoggedSender<T> {
+    sender: mpsc::Sender<T>,
+    event_log: watch::Sender<EventLog>,
+    log: fn(&mut EventLog, &T),
+}
+
+impl<T> LoggedSender<T> {
+    pub fn new(
+        sender: mpsc::Sender<T>,
+        event_log: watch::Sender<EventLog>,
+        log: fn(&mut EventLog, &T),
+    ) -> Self {
+        Self {
+            sender,
+            event_log,
+            log,
+        }
+    }
+
+    pub fn try_send(&self, item: T) -> Result<(), TrySendError<T>> {
+        match self.sender.try_reserve() {
+            Ok(permit) => {
+                self.event_log
+                    .send_modify(|event_log| (self.log)(event_log, &item));
+                permit.send(item);
+                Ok(())
+            }
+            Err(TrySendError::Full(())) => Err(TrySendError::Full(item)),
+            Err(TrySendError::Closed(())) => Err(TrySendError::Closed(item)),
+        }
+    }
+}
+
+impl<T> Clone for LoggedSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            event_log: self.event_log.clone(),
+            log: self.log,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phoenix::protocol::bin::BinRequest;
+    use crate::phoenix::protocol::Request;
+
+    fn get_all() -> PhoenixEvent {
+        PhoenixEvent::new(Request::Bin(BinRequest::GetAll))
+    }
+
+    fn names(event_log: &watch::Sender<EventLog>) -> Vec<String> {
+        event_log
+            .borrow()
+            .entries()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn sent_events_are_logged_right_away() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let event_log = watch::Sender::new(EventLog::new());
+        let sender = LoggedSender::new(tx, event_log.clone(), EventLog::sent);
+
+        let event = get_all();
+        let request_id = event.request_id;
+        sender.try_send(event).unwrap();
+        assert_eq!(names(&event_log), ["bin get-all"]);
+        let entry = event_log.borrow().entries().next().unwrap().request_id;
+        assert_eq!(entry, Some(request_id));
+        assert_eq!(rx.try_recv().unwrap().request_id, request_id);
+    }
+
+    #[test]
+    fn events_a_full_channel_refuses_are_handed_back_unlogged() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let event_log = watch::Sender::new(EventLog::new());
+        let sender = LoggedSender::new(tx, event_log.clone(), EventLog::sent);
+        sender.try_send(get_all()).unwrap();
+
+        let event = get_all();
+        let request_id = event.request_id;
+        match sender.clone().try_send(event) {
+            Err(TrySendError::Full(event)) => assert_eq!(event.request_id, request_id),
+            other => panic!("expected a full channel, got {other:?}"),
+        }
+        assert_eq!(names(&event_log).len(), 1);
+
+        rx.close();
+        while rx.try_recv().is_ok() {}
+        assert!(matches!(
+            sender.try_send(get_all()),
+            Err(TrySendError::Closed(_))
+        ));
+        assert_eq!(names(&event_log).len(), 1);
+    }
+}
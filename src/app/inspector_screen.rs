@@ -0,0 +1,400 @@
+use super::event_log::LoggedSender;
+use super::event_log::{EventDirection, EventLog, EventStatus, LoggedEvent};
+use super::screen::{no_entities, Keybinding, Screen, ScreenInfo};
+use super::AppActions;
+use crate::keymap::{Action, Context, Keymap};
+use crate::phoenix::event::PhoenixEvent;
+use crate::phoenix::protocol::Request;
+use crate::theme::Theme;
+use crate::tui::input_widget::InputWidget;
+use crate::tui::mouse::{item_at, Mouse, SCROLL_LINES};
+use crate::tui::multiline_input_widget::MultilineInput;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use tokio::sync::watch;
+
+pub static INFO: ScreenInfo = ScreenInfo {
+    id: "inspector",
+    title: "Inspector",
+    feature: None,
+    extra_topics: &[],
+    in_menu: false,
+    open: |app| {
+        Box::new(InspectorScreen::new(
+            app.screen_sender.clone(),
+            app.event_log.subscribe(),
+            app.theme,
+        ))
+    },
+    entities: no_entities,
+};
+
+const BROWSE_KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::list(Action::Filter, "filter by name or action"),
+    Keybinding::list(Action::Edit, "edit and send the request"),
+    Keybinding::list(Action::Retry, "resend the request"),
+    Keybinding::list(Action::PageDown, "scroll the detail down"),
+    Keybinding::list(Action::PageUp, "scroll the detail up"),
+    Keybinding::global(Action::Back, "back"),
+    Keybinding::global(Action::Quit, "quit"),
+];
+
+const FILTER_KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::form(Action::Confirm, "done"),
+    Keybinding::form(Action::Cancel, "clear the filter"),
+];
+
+const EDIT_KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::form(Action::Save, "send"),
+    Keybinding::form(Action::Cancel, "cancel"),
+];
+
+/// Every event sent to and received from the socket this session, with a JSON detail pane.
+/// Sent requests can be edited and sent again.
+pub struct InspectorScreen {
+    screen_sender: LoggedSender<PhoenixEvent>,
+    event_log: watch::Receiver<EventLog>,
+    list_state: ListState,
+    filter_input: InputWidget<'static>,
+    mode: Mode,
+    detail_scroll: u16,
+    /// Where the events and the detail were rendered, inside their borders.
+    list_area: Rect,
+    detail_area: Rect,
+    /// Result of the last resend.
+    message: Option<String>,
+    theme: Theme,
+}
+
+enum Mode {
+    Browse,
+    Filter,
+    /// Editing the JSON of a request before sending it.
+    Edit(Box<MultilineInput<'static>>),
+}
+
+impl InspectorScreen {
+    pub fn new(
+        screen_sender: LoggedSender<PhoenixEvent>,
+        event_log: watch::Receiver<EventLog>,
+        theme: Theme,
+    ) -> Self {
+        Self {
+            screen_sender,
+            event_log,
+            list_state: ListState::default(),
+            filter_input: InputWidget::new(String::new(), &theme),
+            mode: Mode::Browse,
+            detail_scroll: 0,
+            list_area: Rect::default(),
+            detail_area: Rect::default(),
+            message: None,
+            theme,
+        }
+    }
+
+    /// Logged events matching the filter, oldest first.
+    fn visible<'a>(&self, event_log: &'a EventLog) -> Vec<&'a LoggedEvent> {
+        let filter = self.filter_input.content().to_lowercase();
+        event_log
+            .entries()
+            .filter(|entry| filter.is_empty() || entry.name.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    fn selected_request(&self) -> Option<Request> {
+        let event_log = self.event_log.borrow();
+        let entries = self.visible(&event_log);
+        let entry = entries.get(self.list_state.selected()?)?;
+        entry.request.clone()
+    }
+
+    fn handle_browse_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions> {
+        self.message = None;
+        match keymap.action(&[Context::List, Context::Global], &e)? {
+            Action::Quit => Some(AppActions::Quit),
+            Action::Back => Some(AppActions::Back),
+            Action::Filter => {
+                self.mode = Mode::Filter;
+                None
+            }
+            Action::Down => {
+                self.list_state.select_next();
+                self.detail_scroll = 0;
+                None
+            }
+            Action::Up => {
+                self.list_state.select_previous();
+                self.detail_scroll = 0;
+                None
+            }
+            Action::First => {
+                self.list_state.select_first();
+                self.detail_scroll = 0;
+                None
+            }
+            Action::Last => {
+                self.list_state.select_last();
+                self.detail_scroll = 0;
+                None
+            }
+            Action::PageDown => {
+                self.detail_scroll = self.detail_scroll.saturating_add(10);
+                None
+            }
+            Action::PageUp => {
+                self.detail_scroll = self.detail_scroll.saturating_sub(10);
+                None
+            }
+            Action::Edit => {
+                match self.selected_request() {
+                    Some(request) => {
+                        let json = serde_json::to_string_pretty(&request).unwrap_or_default();
+                        let editor = MultilineInput::new(json, &self.theme);
+                        self.mode = Mode::Edit(Box::new(editor));
+                    }
+                    None => self.message = Some("Only sent requests can be edited.".to_string()),
+                }
+                None
+            }
+            Action::Retry => {
+                match self.selected_request() {
+                    Some(request) => self.send(request),
+                    None => self.message = Some("Only sent requests can be resent.".to_string()),
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn send(&mut self, request: Request) {
+        let event = PhoenixEvent::new(request);
+        self.message = Some(match self.screen_sender.try_send(event.clone()) {
+            Ok(()) => format!("Sent {event}"),
+            Err(e) => format!("Cannot send {event}: {e}"),
+        });
+        // Follow the new event.
+        self.list_state.select(None);
+    }
+}
+
+impl Screen for InspectorScreen {
+    fn info(&self) -> &'static ScreenInfo {
+        &INFO
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        let theme = self.theme;
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(area);
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+            .split(chunks[1]);
+
+        let filter = self.filter_input.clone().block(
+            Block::new()
+                .border_type(BorderType::Rounded)
+                .borders(Borders::ALL)
+                .border_style(theme.border_style(matches!(self.mode, Mode::Filter)))
+                .title_style(theme.title)
+                .title("Filter"),
+        );
+        f.render_widget(&filter, chunks[0]);
+
+        let event_log = self.event_log.borrow();
+        let entries = self.visible(&event_log);
+        if self.list_state.selected().is_none() && !entries.is_empty() {
+            self.list_state.select(Some(entries.len() - 1));
+        }
+        let items: Vec<ListItem> = entries
+            .iter()
+            .map(|entry| list_item(entry, &theme))
+            .collect();
+        let block = Block::new()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .border_style(theme.border_style(matches!(self.mode, Mode::Browse)))
+            .title_style(theme.title)
+            .title(format!("Events ({})", entries.len()));
+        self.list_area = block.inner(panes[0]);
+        self.detail_area = block.inner(panes[1]);
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(theme.highlight)
+            .style(theme.text);
+        f.render_stateful_widget(list, panes[0], &mut self.list_state);
+
+        let selected = self.list_state.selected().and_then(|i| entries.get(i));
+        match &self.mode {
+            Mode::Edit(editor) => {
+                let editor = (**editor).clone().block(
+                    Block::new()
+                        .border_type(BorderType::Rounded)
+                        .borders(Borders::ALL)
+                        .border_style(theme.focused_border)
+                        .title_style(theme.title)
+                        .title("Edit request"),
+                );
+                f.render_widget(&editor, panes[1]);
+            }
+            Mode::Browse | Mode::Filter => {
+                let detail = selected
+                    .map(|entry| serde_json::to_string_pretty(&entry.json).unwrap_or_default())
+                    .unwrap_or_default();
+                let detail = Paragraph::new(detail)
+                    .block(
+                        Block::new()
+                            .border_type(BorderType::Rounded)
+                            .borders(Borders::ALL)
+                            .border_style(theme.border)
+                            .title_style(theme.title)
+                            .title("Detail"),
+                    )
+                    .style(theme.text)
+                    .wrap(Wrap { trim: false })
+                    .scroll((self.detail_scroll, 0));
+                f.render_widget(detail, panes[1]);
+            }
+        }
+
+        let help = match (&self.message, &self.mode) {
+            (Some(message), _) => Line::from(message.as_str()).style(theme.warning),
+            (None, Mode::Browse) => Line::from(format!(
+                "{}: filter  {}: edit and send  {}: resend  {}/{}: scroll detail  {}/{}: back",
+                keymap.hint(Context::List, Action::Filter),
+                keymap.hint(Context::List, Action::Edit),
+                keymap.hint(Context::List, Action::Retry),
+                keymap.hint(Context::List, Action::PageUp),
+                keymap.hint(Context::List, Action::PageDown),
+                keymap.hint(Context::Global, Action::Back),
+                keymap.hint(Context::Global, Action::Inspector),
+            ))
+            .style(theme.muted),
+            (None, Mode::Filter) => Line::from(format!(
+                "{}: done  {}: clear",
+                keymap.hint(Context::Form, Action::Confirm),
+                keymap.hint(Context::Form, Action::Cancel),
+            ))
+            .style(theme.muted),
+            (None, Mode::Edit(_)) => Line::from(format!(
+                "{}: send  {}: cancel",
+                keymap.hint(Context::Form, Action::Save),
+                keymap.hint(Context::Form, Action::Cancel),
+            ))
+            .style(theme.muted),
+        };
+        f.render_widget(Paragraph::new(help), chunks[2]);
+    }
+
+    // Keyboard event handler
+
+    fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions> {
+        match &mut self.mode {
+            Mode::Browse => self.handle_browse_key(keymap, e),
+            Mode::Filter => {
+                match keymap.action_in_text(&[Context::Form], &e) {
+                    Some(Action::Confirm) => self.mode = Mode::Browse,
+                    Some(Action::Cancel) => {
+                        self.filter_input = InputWidget::new(String::new(), &self.theme);
+                        self.mode = Mode::Browse;
+                    }
+                    _ => self.filter_input.handle_key(keymap, e),
+                }
+                self.list_state.select(None);
+                None
+            }
+            Mode::Edit(editor) => {
+                match keymap.action_in_text(&[Context::Form], &e) {
+                    Some(Action::Cancel) => self.mode = Mode::Browse,
+                    Some(Action::Save) => {
+                        match serde_json::from_str::<Request>(&editor.content()) {
+                            Ok(request) => {
+                                self.send(request);
+                                self.mode = Mode::Browse;
+                            }
+                            Err(e) => self.message = Some(format!("Invalid request: {e}")),
+                        }
+                    }
+                    _ => editor.handle_key(keymap, e),
+                }
+                None
+            }
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: Mouse) -> Option<AppActions> {
+        // The filter and the editor keep the keyboard until they are closed.
+        if !matches!(self.mode, Mode::Browse) {
+            return None;
+        }
+        if mouse.is_in(self.detail_area) {
+            match mouse {
+                Mouse::ScrollUp(_) => {
+                    self.detail_scroll = self.detail_scroll.saturating_sub(SCROLL_LINES)
+                }
+                Mouse::ScrollDown(_) => {
+                    self.detail_scroll = self.detail_scroll.saturating_add(SCROLL_LINES)
+                }
+                Mouse::Click(_) | Mouse::DoubleClick(_) => {}
+            }
+            return None;
+        }
+        match mouse {
+            Mouse::Click(position) | Mouse::DoubleClick(position) => {
+                let len = self.visible(&self.event_log.borrow()).len();
+                let index = item_at(self.list_area, self.list_state.offset(), 1, position)
+                    .filter(|&index| index < len)?;
+                self.list_state.select(Some(index));
+            }
+            Mouse::ScrollUp(_) if mouse.is_in(self.list_area) => self.list_state.select_previous(),
+            Mouse::ScrollDown(_) if mouse.is_in(self.list_area) => self.list_state.select_next(),
+            _ => return None,
+        }
+        self.detail_scroll = 0;
+        None
+    }
+
+    fn keybindings(&self) -> &'static [Keybinding] {
+        match self.mode {
+            Mode::Browse => BROWSE_KEYBINDINGS,
+            Mode::Filter => FILTER_KEYBINDINGS,
+            Mode::Edit(_) => EDIT_KEYBINDINGS,
+        }
+    }
+}
+
+fn list_item(entry: &LoggedEvent, theme: &Theme) -> ListItem<'static> {
+    let direction_style = match entry.direction {
+        EventDirection::Sent => theme.accent,
+        EventDirection::Received => theme.title,
+    };
+    let status_style = match entry.status {
+        EventStatus::Pending | EventStatus::Progress { .. } => theme.warning,
+        EventStatus::Ok(_) => theme.success,
+        EventStatus::Failed(_) => theme.error,
+        EventStatus::Broadcast => theme.muted,
+    };
+    let request_id = entry
+        .request_id
+        .map(|request_id| format!("#{request_id} "))
+        .unwrap_or_default();
+    ListItem::new(Line::from(vec![
+        Span::styled(format!("{:>8.3}s ", entry.at.as_secs_f64()), theme.muted),
+        Span::styled(format!("{} ", entry.direction), direction_style),
+        Span::raw(format!("{request_id}{} ", entry.name)),
+        Span::styled(entry.status.to_string(), status_style),
+    ]))
+}
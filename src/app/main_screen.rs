@@ -1,128 +1,479 @@
-use super::{AppActions, ScreenType};
+use super::event_log::LoggedSender;
+use super::projects_screen::{self, ProjectData};
+use super::screen::{no_entities, Keybinding, Screen, ScreenInfo, SCREENS};
+use super::store::Store;
+use super::{bin_screen, connection_style, AppActions};
+use crate::keymap::{Action, Context, Keymap};
+use crate::phoenix::connection::{ConnectionState, ConnectionStats};
 use crate::phoenix::event::PhoenixEvent;
-use crossterm::event::{KeyCode, KeyEvent};
-use ratatui::prelude::Stylize;
-use ratatui::style::Style;
-use ratatui::widgets::{Block, BorderType, Borders, List, ListItem, ListState};
-use ratatui::Frame;
-
-enum Menu {
-    Todos,
-    Notes,
-    Bin,
-    Projects,
-}
+use crate::phoenix::protocol::{bin::BinRequest, todos::TodosRequest, Feature, Request};
+use crate::phoenix::topic::TopicCommand;
+use crate::theme::Theme;
+use crate::tui::mouse::{item_at, Mouse};
+use chrono::{Local, TimeDelta};
+use cli_log::info;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::watch;
 
-impl Menu {
-    fn from_str(s: &str) -> Option<Self> {
-        match s {
-            "Notes" => Some(Menu::Notes),
-            "Bin" => Some(Menu::Bin),
-            "Projects" => Some(Menu::Projects),
-            "Todos" => Some(Menu::Todos),
-            _ => None,
-        }
-    }
+pub static INFO: ScreenInfo = ScreenInfo {
+    id: "main",
+    title: "MeowUI",
+    feature: None,
+    extra_topics: &[Feature::Bin, Feature::Todos],
+    in_menu: false,
+    open: |app| {
+        Box::new(MainScreen::new(
+            app.screen_sender.clone(),
+            app.topic_sender.clone(),
+            app.store.subscribe(),
+            app.connection_state.clone(),
+            app.connection_stats.clone(),
+            app.theme,
+        ))
+    },
+    entities: no_entities,
+};
 
-    fn items() -> Vec<&'static str> {
-        vec!["Notes", "Bin", "Projects", "Todos"]
-    }
-}
+const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::list(Action::Down, "next item"),
+    Keybinding::list(Action::Up, "previous item"),
+    Keybinding::list(Action::First, "first item"),
+    Keybinding::list(Action::Last, "last item"),
+    Keybinding::list(Action::Open, "open the screen, bin or project"),
+    Keybinding::list(
+        Action::SwitchPane,
+        "switch between screens, bins and projects",
+    ),
+    Keybinding::list(Action::Retry, "reconnect"),
+    Keybinding::global(Action::Quit, "quit"),
+];
+
+/// Bins expiring within this many hours are listed.
+const EXPIRING_HOURS: i64 = 24;
 
+/// Most recently opened projects listed.
+const RECENT_PROJECTS: usize = 5;
+
+/// Home screen: the screens of the menu, with bins about to expire, recently opened projects,
+/// open todos and the connection. Bins and todos come from the store the feature screens read,
+/// and are fetched when the dashboard is opened and after reconnecting. Their topics stay joined
+/// while the dashboard is open, so pushed changes keep it up to date.
 pub struct MainScreen {
-    list_items: Vec<&'static str>,
+    screen_sender: LoggedSender<PhoenixEvent>,
+    topic_sender: Sender<TopicCommand>,
+    store: watch::Receiver<Store>,
+    connection_state: watch::Receiver<ConnectionState>,
+    connection_stats: watch::Receiver<ConnectionStats>,
+    /// Registered screens listed in the menu.
+    list_items: Vec<&'static ScreenInfo>,
     list_state: ListState,
+    /// Read from the projects table when the dashboard is shown.
+    recent_projects: Vec<ProjectData>,
+    bins_state: ListState,
+    projects_state: ListState,
+    focused_panel: Panel,
+    /// Where the menu, bins and projects were rendered, inside their borders.
+    list_area: Rect,
+    bins_area: Rect,
+    projects_area: Rect,
+    theme: Theme,
+}
+
+/// Panels taking list keys, in the order they are switched.
+#[derive(Clone, Copy, PartialEq)]
+enum Panel {
+    Menu,
+    Bins,
+    Projects,
+}
+
+impl Panel {
+    const ALL: [Panel; 3] = [Panel::Menu, Panel::Bins, Panel::Projects];
+
+    fn next(self) -> Self {
+        match self {
+            Panel::Menu => Panel::Bins,
+            Panel::Bins => Panel::Projects,
+            Panel::Projects => Panel::Menu,
+        }
+    }
 }
 
 impl MainScreen {
-    pub fn new() -> Self {
-        Self {
-            list_items: Menu::items(),
+    pub fn new(
+        screen_sender: LoggedSender<PhoenixEvent>,
+        topic_sender: Sender<TopicCommand>,
+        store: watch::Receiver<Store>,
+        connection_state: watch::Receiver<ConnectionState>,
+        connection_stats: watch::Receiver<ConnectionStats>,
+        theme: Theme,
+    ) -> Self {
+        let mut main_screen = Self {
+            screen_sender,
+            topic_sender,
+            store,
+            connection_state,
+            connection_stats,
+            list_items: SCREENS
+                .iter()
+                .copied()
+                .filter(|info| info.in_menu)
+                .collect(),
             list_state: ListState::default().with_selected(Some(0)),
+            recent_projects: Vec::new(),
+            bins_state: ListState::default(),
+            projects_state: ListState::default(),
+            focused_panel: Panel::Menu,
+            list_area: Rect::default(),
+            bins_area: Rect::default(),
+            projects_area: Rect::default(),
+            theme,
+        };
+        main_screen.push_events();
+        main_screen.load_recent_projects();
+        main_screen
+    }
+
+    /// Fetch what the bins and todos panels show into the store.
+    fn push_events(&self) {
+        let requests = [
+            Request::Bin(BinRequest::GetAll),
+            Request::Todos(TodosRequest::GetAll),
+        ];
+        for request in requests {
+            match self.screen_sender.try_send(PhoenixEvent::new(request)) {
+                Ok(()) => info!("sent message"),
+                Err(e) => info!("{e}"),
+            }
+        }
+    }
+
+    fn load_recent_projects(&mut self) {
+        self.recent_projects =
+            projects_screen::recent_projects(RECENT_PROJECTS).unwrap_or_else(|e| {
+                info!("cannot read recent projects: {e}");
+                Vec::new()
+            });
+    }
+
+    /// IDs of the bins listed in the bins panel, in order.
+    fn expiring_bin_ids(&self) -> Vec<u64> {
+        let now = Local::now();
+        self.store
+            .borrow()
+            .bins_expiring(now, now + TimeDelta::hours(EXPIRING_HOURS))
+            .iter()
+            .map(|bin| bin.id)
+            .collect()
+    }
+
+    fn state_mut(&mut self, panel: Panel) -> &mut ListState {
+        match panel {
+            Panel::Menu => &mut self.list_state,
+            Panel::Bins => &mut self.bins_state,
+            Panel::Projects => &mut self.projects_state,
+        }
+    }
+
+    fn area(&self, panel: Panel) -> Rect {
+        match panel {
+            Panel::Menu => self.list_area,
+            Panel::Bins => self.bins_area,
+            Panel::Projects => self.projects_area,
+        }
+    }
+
+    fn len(&self, panel: Panel) -> usize {
+        match panel {
+            Panel::Menu => self.list_items.len(),
+            Panel::Bins => self.expiring_bin_ids().len(),
+            Panel::Projects => self.recent_projects.len(),
+        }
+    }
+
+    /// Open the screen, bin or project selected in the focused panel.
+    fn open_selected(&self) -> Option<AppActions> {
+        match self.focused_panel {
+            Panel::Menu => {
+                let info = self.list_items.get(self.list_state.selected()?)?;
+                Some(AppActions::Push(info))
+            }
+            Panel::Bins => {
+                let id = *self.expiring_bin_ids().get(self.bins_state.selected()?)?;
+                Some(AppActions::Open(&bin_screen::INFO, id.to_string()))
+            }
+            Panel::Projects => {
+                let project = self.recent_projects.get(self.projects_state.selected()?)?;
+                Some(AppActions::Open(
+                    &projects_screen::INFO,
+                    project.id.to_string(),
+                ))
+            }
         }
     }
 
-    pub fn render(&mut self, f: &mut Frame) {
+    fn panel_block(&self, title: String, panel: Option<Panel>) -> Block<'static> {
+        let focused = panel.is_some_and(|panel| panel == self.focused_panel);
+        Block::new()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .border_style(self.theme.border_style(focused))
+            .title_style(self.theme.title)
+            .style(self.theme.text)
+            .title(title)
+    }
+
+    fn menu_widget(&mut self, area: Rect, f: &mut Frame) {
         let items = self
             .list_items
             .iter()
-            .map(|&todo_item| ListItem::from(todo_item));
+            .map(|info| ListItem::from(info.title));
+
+        let block = self.panel_block(INFO.title.to_string(), Some(Panel::Menu));
+        self.list_area = block.inner(area);
 
         let list = List::new(items)
-            .block(
-                Block::new()
-                    .border_type(BorderType::Rounded)
-                    .borders(Borders::ALL)
-                    .title("MeowUI"),
-            )
-            .highlight_style(Style::new().reversed())
-            .style(Style::new().green())
+            .block(block)
+            .highlight_style(self.theme.highlight)
+            .style(self.theme.text)
             .highlight_symbol("-> ")
             .repeat_highlight_symbol(true);
-        f.render_stateful_widget(list, f.area(), &mut self.list_state);
+        f.render_stateful_widget(list, area, &mut self.list_state);
+    }
+
+    fn bins_widget(&mut self, area: Rect, f: &mut Frame) {
+        let now = Local::now();
+        let store = self.store.borrow();
+        let bins = store.bins_expiring(now, now + TimeDelta::hours(EXPIRING_HOURS));
+
+        let block = self.panel_block(
+            format!("Expiring within {EXPIRING_HOURS}h ({})", bins.len()),
+            Some(Panel::Bins),
+        );
+        self.bins_area = block.inner(area);
+        if bins.is_empty() {
+            let empty = Line::styled("No bin expires soon", self.theme.muted);
+            f.render_widget(Paragraph::new(empty).block(block), area);
+            return;
+        }
+
+        let items = bins.iter().map(|bin| {
+            ListItem::new(Line::from(vec![
+                Span::raw(bin.title.clone()),
+                Span::styled(
+                    format!("  in {}", time_left(bin.expire_at - now)),
+                    self.theme.warning,
+                ),
+            ]))
+        });
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(self.theme.highlight)
+            .style(self.theme.text)
+            .highlight_symbol("-> ");
+        f.render_stateful_widget(list, area, &mut self.bins_state);
+    }
+
+    fn projects_widget(&mut self, area: Rect, f: &mut Frame) {
+        let block = self.panel_block("Recent projects".to_string(), Some(Panel::Projects));
+        self.projects_area = block.inner(area);
+        if self.recent_projects.is_empty() {
+            let empty = Line::styled("No project opened yet", self.theme.muted);
+            f.render_widget(Paragraph::new(empty).block(block), area);
+            return;
+        }
+
+        let items = self.recent_projects.iter().map(|project| {
+            ListItem::new(Line::from(vec![
+                Span::raw(project.name.clone()),
+                Span::styled(format!("  {}", project.path), self.theme.muted),
+            ]))
+        });
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(self.theme.highlight)
+            .style(self.theme.text)
+            .highlight_symbol("-> ");
+        f.render_stateful_widget(list, area, &mut self.projects_state);
+    }
+
+    fn connection_widget(&self, area: Rect, f: &mut Frame) {
+        let state = self.connection_state.borrow();
+        let stats = self.connection_stats.borrow();
+        let latency = match stats.latency {
+            Some(latency) => format!("{}ms", latency.as_millis()),
+            None => "-".to_string(),
+        };
+        let last_heartbeat = match stats.last_heartbeat {
+            Some(last_heartbeat) => format!("{}s ago", last_heartbeat.elapsed().as_secs()),
+            None => "never".to_string(),
+        };
+
+        let lines = vec![
+            Line::from(vec![
+                Span::styled("● ", connection_style(&state, &self.theme)),
+                Span::raw(state.to_string()),
+            ]),
+            Line::styled(format!("Latency: {latency}"), self.theme.muted),
+            Line::styled(format!("Last heard: {last_heartbeat}"), self.theme.muted),
+            Line::styled(format!("Pending: {}", stats.pending), self.theme.muted),
+        ];
+        let block = self.panel_block("Connection".to_string(), None);
+        f.render_widget(Paragraph::new(lines).block(block), area);
     }
 
-    pub fn handle_key(&mut self, e: KeyEvent) -> Option<AppActions> {
-        match e.code {
-            KeyCode::Char('q') | KeyCode::Esc => Some(AppActions::Quit),
-            KeyCode::Char('h') | KeyCode::Left => {
-                self.select_none();
+    fn todos_widget(&self, area: Rect, f: &mut Frame) {
+        let store = self.store.borrow();
+        let todos: Vec<Line> = store
+            .open_todos()
+            .map(|todo| Line::from(format!("[ ] {}", todo.title)))
+            .collect();
+
+        let block = self.panel_block(format!("Open todos ({})", todos.len()), None);
+        let paragraph = if todos.is_empty() {
+            Paragraph::new(Line::styled("Nothing to do", self.theme.muted))
+        } else {
+            Paragraph::new(todos)
+        };
+        f.render_widget(paragraph.block(block), area);
+    }
+
+    fn help_widget(&self, area: Rect, f: &mut Frame, keymap: &Keymap) {
+        let help = Line::from(format!(
+            "{}: open  {}: switch panel  {}: reconnect  {}: commands  {}: notifications  {}: keys",
+            keymap.hint(Context::List, Action::Open),
+            keymap.hint(Context::List, Action::SwitchPane),
+            keymap.hint(Context::List, Action::Retry),
+            keymap.hint(Context::Global, Action::Palette),
+            keymap.hint(Context::Global, Action::Notifications),
+            keymap.hint(Context::Global, Action::Help),
+        ))
+        .style(self.theme.muted);
+        f.render_widget(Paragraph::new(help), area);
+    }
+}
+
+impl Screen for MainScreen {
+    fn info(&self) -> &'static ScreenInfo {
+        &INFO
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        let [body, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(area);
+        let [menu, middle, right] = Layout::horizontal([
+            Constraint::Length(24),
+            Constraint::Min(0),
+            Constraint::Percentage(35),
+        ])
+        .areas(body);
+        let [bins, projects] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(middle);
+        let [connection, todos] =
+            Layout::vertical([Constraint::Length(6), Constraint::Min(0)]).areas(right);
+
+        self.menu_widget(menu, f);
+        self.bins_widget(bins, f);
+        self.projects_widget(projects, f);
+        self.connection_widget(connection, f);
+        self.todos_widget(todos, f);
+        self.help_widget(help, f, keymap);
+    }
+
+    fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions> {
+        let panel = self.focused_panel;
+        match keymap.action(&[Context::List, Context::Global], &e)? {
+            Action::Quit => Some(AppActions::Quit),
+            Action::Deselect => {
+                self.state_mut(panel).select(None);
                 None
             }
-            KeyCode::Char('j') | KeyCode::Down => {
-                self.select_next();
+            Action::Down => {
+                self.state_mut(panel).select_next();
                 None
             }
-            KeyCode::Char('k') | KeyCode::Up => {
-                self.select_previous();
+            Action::Up => {
+                self.state_mut(panel).select_previous();
                 None
             }
-            KeyCode::Char('g') | KeyCode::Home => {
-                self.select_first();
+            Action::First => {
+                self.state_mut(panel).select_first();
                 None
             }
-            KeyCode::Char('G') | KeyCode::End => {
-                self.select_last();
+            Action::Last => {
+                self.state_mut(panel).select_last();
                 None
             }
-            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => {
-                if let Some(i) = self.list_state.selected() {
-                    let item = self.list_items[i];
-                    let screen = match Menu::from_str(item)? {
-                        Menu::Notes => ScreenType::Notes,
-                        Menu::Bin => ScreenType::Bin,
-                        Menu::Todos => ScreenType::Todos,
-                        Menu::Projects => ScreenType::Projects,
-                    };
-                    Some(AppActions::ChangeScreen(screen))
-                } else {
-                    None
+            Action::SwitchPane => {
+                self.focused_panel = panel.next();
+                let state = self.state_mut(self.focused_panel);
+                if state.selected().is_none() {
+                    state.select_first();
                 }
+                None
             }
+            Action::Retry => {
+                let _ = self.topic_sender.try_send(TopicCommand::Reconnect);
+                None
+            }
+            Action::Open => self.open_selected(),
             _ => None,
         }
     }
 
-    fn select_none(&mut self) {
-        self.list_state.select(None);
+    fn handle_mouse(&mut self, mouse: Mouse) -> Option<AppActions> {
+        let panel = Panel::ALL
+            .into_iter()
+            .find(|&panel| mouse.is_in(self.area(panel)))?;
+        self.focused_panel = panel;
+        match mouse {
+            Mouse::Click(position) | Mouse::DoubleClick(position) => {
+                let area = self.area(panel);
+                let len = self.len(panel);
+                let state = self.state_mut(panel);
+                let index = item_at(area, state.offset(), 1, position).filter(|&i| i < len)?;
+                state.select(Some(index));
+                if let Mouse::DoubleClick(_) = mouse {
+                    return self.open_selected();
+                }
+            }
+            Mouse::ScrollUp(_) => self.state_mut(panel).select_previous(),
+            Mouse::ScrollDown(_) => self.state_mut(panel).select_next(),
+        }
+        None
     }
 
-    fn select_next(&mut self) {
-        self.list_state.select_next();
+    fn on_reconnect(&mut self) {
+        self.push_events();
     }
-    fn select_previous(&mut self) {
-        self.list_state.select_previous();
+
+    fn on_resume(&mut self) {
+        self.load_recent_projects();
     }
 
-    fn select_first(&mut self) {
-        self.list_state.select_first();
+    /// The heartbeat age and the time left before bins expire.
+    fn changes_with_time(&self) -> bool {
+        true
     }
 
-    fn select_last(&mut self) {
-        self.list_state.select_last();
+    fn keybindings(&self) -> &'static [Keybinding] {
+        KEYBINDINGS
     }
+}
 
-    pub fn handle_socket_event(&self, event: PhoenixEvent) {
-        println!("{event}");
+/// Time left before a bin expires, like `3h 20m` or `12m`.
+fn time_left(delta: TimeDelta) -> String {
+    let minutes = delta.num_minutes().max(0);
+    match minutes / 60 {
+        0 => format!("{minutes}m"),
+        hours => format!("{hours}h {}m", minutes % 60),
     }
 }
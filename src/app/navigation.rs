@@ -0,0 +1,95 @@
+/// Screens opened on top of each other. Going back returns to the screen below as it was left,
+/// with its selection and scroll position.
+pub struct Navigation<S> {
+    current: S,
+    /// Screens below the current one, the first being the root.
+    history: Vec<S>,
+}
+
+impl<S> Navigation<S> {
+    pub fn new(root: S) -> Self {
+        Self {
+            current: root,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn current(&self) -> &S {
+        &self.current
+    }
+
+    pub fn current_mut(&mut self) -> &mut S {
+        &mut self.current
+    }
+
+    /// Open a screen over the current one.
+    pub fn push(&mut self, screen: S) {
+        let below = std::mem::replace(&mut self.current, screen);
+        self.history.push(below);
+    }
+
+    /// Close the current screen and return it. The root screen is never closed.
+    pub fn pop(&mut self) -> Option<S> {
+        let below = self.history.pop()?;
+        Some(std::mem::replace(&mut self.current, below))
+    }
+
+    /// Put a screen in place of the current one and return the one it replaced. The screens
+    /// below are kept, so going back skips the replaced screen.
+    pub fn replace(&mut self, screen: S) -> S {
+        std::mem::replace(&mut self.current, screen)
+    }
+
+    /// Close every screen above the root.
+    pub fn pop_to_root(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Every open screen, from the root to the current one.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &S> {
+        self.history.iter().chain(std::iter::once(&self.current))
+    }
+
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut S> {
+        self.history
+            .iter_mut()
+            .chain(std::iter::once(&mut self.current))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screens(navigation: &Navigation<&'static str>) -> Vec<&'static str> {
+        navigation.iter().copied().collect()
+    }
+
+    #[test]
+    fn push_pop_and_replace() {
+        let mut navigation = Navigation::new("list");
+        navigation.push("show");
+        navigation.push("edit");
+        assert_eq!(screens(&navigation), ["list", "show", "edit"]);
+
+        assert_eq!(navigation.replace("preview"), "edit");
+        assert_eq!(screens(&navigation), ["list", "show", "preview"]);
+
+        assert_eq!(navigation.pop(), Some("preview"));
+        assert_eq!(*navigation.current(), "show");
+        assert_eq!(navigation.pop(), Some("show"));
+        // The root is never closed, but can be replaced.
+        assert_eq!(navigation.pop(), None);
+        assert_eq!(navigation.replace("dashboard"), "list");
+        assert_eq!(screens(&navigation), ["dashboard"]);
+    }
+
+    #[test]
+    fn pop_to_root_keeps_the_root() {
+        let mut navigation = Navigation::new("list");
+        navigation.push("show");
+        navigation.push("edit");
+        navigation.pop_to_root();
+        assert_eq!(screens(&navigation), ["list"]);
+    }
+}
@@ -1,62 +1,109 @@
-use super::{AppActions, ScreenType};
+use super::event_log::LoggedSender;
+use super::screen::{no_entities, Keybinding, Screen, ScreenInfo};
+use super::store::Store;
+use super::AppActions;
+use crate::keymap::{Action, Context, Keymap};
 use crate::phoenix::event::PhoenixEvent;
+use crate::phoenix::protocol::{notes::NotesRequest, Feature, Request};
+use crate::theme::Theme;
 use cli_log::info;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::KeyEvent;
 use ratatui::{
-    style::{Color, Style, Stylize},
+    layout::Rect,
+    text::Line,
     widgets::{Block, BorderType, Borders, Paragraph},
     Frame,
 };
-use serde_json::json;
-use tokio::sync::mpsc::Sender;
+use tokio::sync::watch;
 
-/// ## Events
-///
-/// ### Get Workspaces
-/// screen -> socket: `{"name": "notes", "payload": {"action": "get-workspaces"}}`
-///
-/// socket -> screen: `{"name": "notes", "payload": {"action": "get-workspaces", "data": [...]}}`
+pub static INFO: ScreenInfo = ScreenInfo {
+    id: "notes",
+    title: "Notes",
+    feature: Some(Feature::Notes),
+    extra_topics: &[],
+    in_menu: true,
+    open: |app| {
+        Box::new(NotesScreen::new(
+            app.screen_sender.clone(),
+            app.store.subscribe(),
+            app.theme,
+        ))
+    },
+    entities: no_entities,
+};
+
+const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::global(Action::Back, "back"),
+    Keybinding::global(Action::Quit, "quit"),
+];
 
+/// Notes screen. The events exchanged with the socket are described on `NotesRequest` and
+/// `NotesResponse`.
 pub struct NotesScreen {
-    pub screen_sender: Sender<PhoenixEvent>,
+    pub screen_sender: LoggedSender<PhoenixEvent>,
+    store: watch::Receiver<Store>,
+    theme: Theme,
 }
 
 impl NotesScreen {
-    pub fn new(screen_sender: Sender<PhoenixEvent>) -> Self {
-        let notes_screen = Self { screen_sender };
+    pub fn new(
+        screen_sender: LoggedSender<PhoenixEvent>,
+        store: watch::Receiver<Store>,
+        theme: Theme,
+    ) -> Self {
+        let notes_screen = Self {
+            screen_sender,
+            store,
+            theme,
+        };
         notes_screen.push_event();
         notes_screen
     }
 
-    pub fn render(&mut self, f: &mut Frame) {
+    fn push_event(&self) {
+        let request = Request::Notes(NotesRequest::GetWorkspaces);
+        match self.screen_sender.try_send(PhoenixEvent::new(request)) {
+            Ok(()) => info!("sent message"),
+            Err(e) => info!("{e}"),
+        }
+    }
+}
+
+impl Screen for NotesScreen {
+    fn info(&self) -> &'static ScreenInfo {
+        &INFO
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect, _keymap: &Keymap) {
         let block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Green))
-            .style(Style::new().green())
+            .border_style(self.theme.border)
+            .title_style(self.theme.title)
+            .style(self.theme.text)
             .title("Notes");
-        f.render_widget(Paragraph::new("TODO").block(block), f.area());
+        let store = self.store.borrow();
+        let workspaces: Vec<Line> = store
+            .workspaces
+            .iter()
+            .map(|workspace| Line::from(workspace.name.as_str()))
+            .collect();
+        f.render_widget(Paragraph::new(workspaces).block(block), area);
     }
 
-    pub fn handle_key(&mut self, e: KeyEvent) -> Option<AppActions> {
-        match e.code {
-            KeyCode::Char('q') | KeyCode::Esc => Some(AppActions::Quit),
-            KeyCode::Char('b') => Some(AppActions::ChangeScreen(ScreenType::Main)),
+    fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions> {
+        match keymap.action(&[Context::Global], &e)? {
+            Action::Quit => Some(AppActions::Quit),
+            Action::Back => Some(AppActions::Back),
             _ => None,
         }
     }
 
-    pub fn handle_socket_event(&self, event: PhoenixEvent) {
-        println!("{event}");
+    fn on_reconnect(&mut self) {
+        self.push_event();
     }
 
-    fn push_event(&self) {
-        match self.screen_sender.try_send(PhoenixEvent {
-            name: "notes".to_string(),
-            payload: Some(json!({"action": "get-all"})),
-        }) {
-            Ok(()) => info!("sent message"),
-            Err(e) => info!("{e}"),
-        }
+    fn keybindings(&self) -> &'static [Keybinding] {
+        KEYBINDINGS
     }
 }
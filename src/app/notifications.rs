@@ -0,0 +1,208 @@
+use crate::theme::Theme;
+use chrono::{DateTime, Local};
+use ratatui::style::Style;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// How long a notification stays over the screens as a toast.
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+/// Oldest notifications are dropped past this many.
+const CAPACITY: usize = 200;
+
+/// Notifications of this session, shown as toasts while they are recent and in the history
+/// screen after.
+pub struct Notifications {
+    entries: VecDeque<Notification>,
+}
+
+pub struct Notification {
+    pub level: Level,
+    pub message: String,
+    pub at: DateTime<Local>,
+    /// When the toast was shown, to hide it after `TOAST_DURATION`.
+    shown: Instant,
+    dismissed: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Success,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn style(self, theme: &Theme) -> Style {
+        match self {
+            Level::Success => theme.success,
+            Level::Warning => theme.warning,
+            Level::Error => theme.error,
+        }
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Success => write!(f, "✓"),
+            Level::Warning => write!(f, "⚠"),
+            Level::Error => write!(f, "✗"),
+        }
+    }
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Every notification, newest first.
+    pub fn history(&self) -> impl Iterator<Item = &Notification> {
+        self.entries.iter().rev()
+    }
+
+    /// Notifications still shown as toasts, newest first.
+    pub fn toasts(&self) -> impl Iterator<Item = &Notification> {
+        self.history()
+            .take_while(|notification| notification.shown.elapsed() < TOAST_DURATION)
+            .filter(|notification| !notification.dismissed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn push(&mut self, level: Level, message: String) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Notification {
+            level,
+            message,
+            at: Local::now(),
+            shown: Instant::now(),
+            dismissed: false,
+        });
+    }
+
+    /// Hide the toasts, keeping them in the history.
+    pub fn dismiss(&mut self) {
+        for notification in self.entries.iter_mut().rev() {
+            if notification.dismissed {
+                break;
+            }
+            notification.dismissed = true;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Pushes notifications from anywhere in the app. Clones share the same notifications.
+#[derive(Clone)]
+pub struct Notifier {
+    notifications: watch::Sender<Notifications>,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Self {
+            notifications: watch::Sender::new(Notifications::new()),
+        }
+    }
+
+    pub fn success(&self, message: impl Into<String>) {
+        self.push(Level::Success, message.into());
+    }
+
+    pub fn warning(&self, message: impl Into<String>) {
+        self.push(Level::Warning, message.into());
+    }
+
+    pub fn error(&self, message: impl Into<String>) {
+        self.push(Level::Error, message.into());
+    }
+
+    fn push(&self, level: Level, message: String) {
+        self.notifications
+            .send_modify(|notifications| notifications.push(level, message));
+    }
+
+    pub fn dismiss(&self) {
+        self.notifications
+            .send_modify(|notifications| notifications.dismiss());
+    }
+
+    pub fn clear(&self) {
+        self.notifications
+            .send_modify(|notifications| notifications.clear());
+    }
+
+    pub fn borrow(&self) -> watch::Ref<'_, Notifications> {
+        self.notifications.borrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages<'a>(notifications: impl Iterator<Item = &'a Notification>) -> Vec<&'a str> {
+        notifications
+            .map(|notification| notification.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn notifications_are_listed_newest_first() {
+        let notifier = Notifier::new();
+        notifier.success("Bin created");
+        notifier.error("Cannot delete the bin");
+
+        let notifications = notifier.borrow();
+        assert_eq!(
+            messages(notifications.history()),
+            ["Cannot delete the bin", "Bin created"]
+        );
+        assert_eq!(
+            messages(notifications.toasts()),
+            ["Cannot delete the bin", "Bin created"]
+        );
+        assert!(notifications.history().next().unwrap().level == Level::Error);
+    }
+
+    #[test]
+    fn toasts_expire_and_are_dismissed_but_stay_in_the_history() {
+        let mut notifications = Notifications::new();
+        notifications.push(Level::Success, "old".to_string());
+        notifications.entries[0].shown = Instant::now() - TOAST_DURATION;
+        notifications.push(Level::Warning, "dismissed".to_string());
+        assert_eq!(messages(notifications.toasts()), ["dismissed"]);
+
+        notifications.dismiss();
+        assert_eq!(notifications.toasts().count(), 0);
+        notifications.push(Level::Error, "new".to_string());
+        assert_eq!(messages(notifications.toasts()), ["new"]);
+        assert_eq!(notifications.len(), 3);
+
+        notifications.clear();
+        assert_eq!(notifications.len(), 0);
+    }
+
+    #[test]
+    fn the_oldest_notifications_are_dropped() {
+        let mut notifications = Notifications::new();
+        for i in 0..CAPACITY + 2 {
+            notifications.push(Level::Success, i.to_string());
+        }
+        assert_eq!(notifications.len(), CAPACITY);
+        assert_eq!(notifications.history().last().unwrap().message, "2");
+    }
+}
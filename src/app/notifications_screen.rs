@@ -0,0 +1,158 @@
+use super::notifications::{Notification, Notifier};
+use super::screen::{no_entities, Keybinding, Screen, ScreenInfo};
+use super::AppActions;
+use crate::keymap::{Action, Context, Keymap};
+use crate::theme::Theme;
+use crate::tui::mouse::{item_at, Mouse};
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+pub static INFO: ScreenInfo = ScreenInfo {
+    id: "notifications",
+    title: "Notifications",
+    feature: None,
+    extra_topics: &[],
+    in_menu: false,
+    open: |app| Box::new(NotificationsScreen::new(app.notifier.clone(), app.theme)),
+    entities: no_entities,
+};
+
+const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::list(Action::Down, "older notification"),
+    Keybinding::list(Action::Up, "newer notification"),
+    Keybinding::list(Action::First, "newest notification"),
+    Keybinding::list(Action::Last, "oldest notification"),
+    Keybinding::list(Action::Delete, "clear the history"),
+    Keybinding::global(Action::Back, "back"),
+    Keybinding::global(Action::Quit, "quit"),
+];
+
+/// Notifications of this session, newest first, including the ones whose toast is gone.
+pub struct NotificationsScreen {
+    notifier: Notifier,
+    list_state: ListState,
+    /// Where the notifications were rendered, inside the borders.
+    list_area: Rect,
+    theme: Theme,
+}
+
+impl NotificationsScreen {
+    pub fn new(notifier: Notifier, theme: Theme) -> Self {
+        // Everything shown as a toast is listed here.
+        notifier.dismiss();
+        Self {
+            notifier,
+            list_state: ListState::default().with_selected(Some(0)),
+            list_area: Rect::default(),
+            theme,
+        }
+    }
+}
+
+impl Screen for NotificationsScreen {
+    fn info(&self) -> &'static ScreenInfo {
+        &INFO
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(area);
+
+        let notifications = self.notifier.borrow();
+        let items: Vec<ListItem> = notifications
+            .history()
+            .map(|notification| list_item(notification, &self.theme))
+            .collect();
+        let block = Block::new()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .border_style(self.theme.border)
+            .title_style(self.theme.title)
+            .title(format!("Notifications ({})", notifications.len()));
+        self.list_area = block.inner(chunks[0]);
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(self.theme.highlight)
+            .style(self.theme.text);
+        f.render_stateful_widget(list, chunks[0], &mut self.list_state);
+
+        let help = Line::from(format!(
+            "{}: clear  {}/{}: back",
+            keymap.hint(Context::List, Action::Delete),
+            keymap.hint(Context::Global, Action::Back),
+            keymap.hint(Context::Global, Action::Notifications),
+        ))
+        .style(self.theme.muted);
+        f.render_widget(Paragraph::new(help), chunks[1]);
+    }
+
+    // Keyboard event handler
+
+    fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions> {
+        match keymap.action(&[Context::List, Context::Global], &e)? {
+            Action::Quit => Some(AppActions::Quit),
+            Action::Back => Some(AppActions::Back),
+            Action::Down => {
+                self.list_state.select_next();
+                None
+            }
+            Action::Up => {
+                self.list_state.select_previous();
+                None
+            }
+            Action::First => {
+                self.list_state.select_first();
+                None
+            }
+            Action::Last => {
+                self.list_state.select_last();
+                None
+            }
+            Action::Delete => {
+                self.notifier.clear();
+                self.list_state.select_first();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: Mouse) -> Option<AppActions> {
+        match mouse {
+            Mouse::Click(position) | Mouse::DoubleClick(position) => {
+                let len = self.notifier.borrow().len();
+                let index = item_at(self.list_area, self.list_state.offset(), 1, position)
+                    .filter(|&index| index < len)?;
+                self.list_state.select(Some(index));
+            }
+            Mouse::ScrollUp(_) => self.list_state.select_previous(),
+            Mouse::ScrollDown(_) => self.list_state.select_next(),
+        }
+        None
+    }
+
+    fn keybindings(&self) -> &'static [Keybinding] {
+        KEYBINDINGS
+    }
+}
+
+fn list_item(notification: &Notification, theme: &Theme) -> ListItem<'static> {
+    ListItem::new(Line::from(vec![
+        Span::styled(
+            format!("{} ", notification.at.format("%H:%M:%S")),
+            theme.muted,
+        ),
+        Span::styled(
+            format!("{} ", notification.level),
+            notification.level.style(theme),
+        ),
+        Span::raw(notification.message.clone()),
+    ]))
+}
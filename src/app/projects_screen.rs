@@ -1,60 +1,79 @@
 use std::{env::current_dir, path::Path};
 
-use super::{AppActions, ScreenType};
-use crate::{phoenix::event::PhoenixEvent, sqlite::Sqlite};
+use super::screen::{Entity, Keybinding, Screen, ScreenInfo};
+use super::AppActions;
+use crate::keymap::{Action, Context, Keymap};
+use crate::sqlite::Sqlite;
+use crate::theme::Theme;
+use crate::tui::mouse::{item_at, Mouse};
 use cli_log::info;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::KeyEvent;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style, Stylize},
+    style::{Modifier, Stylize},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Paragraph, Row, Table, TableState},
     Frame,
 };
 use rusqlite::Result as SqliteResult;
 
+pub static INFO: ScreenInfo = ScreenInfo {
+    id: "projects",
+    title: "Projects",
+    feature: None,
+    extra_topics: &[],
+    in_menu: true,
+    open: |app| Box::new(ProjectsScreen::new(app.theme)),
+    entities: |_| project_entities().unwrap_or_default(),
+};
+
+const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::list(Action::Down, "next project"),
+    Keybinding::list(Action::Up, "previous project"),
+    Keybinding::list(Action::Open, "open project"),
+    Keybinding::global(Action::Back, "back"),
+    Keybinding::global(Action::Quit, "quit"),
+];
+
 pub struct ProjectsScreen {
     db: Sqlite,
     data: Vec<ProjectData>,
     table_state: TableState,
+    /// Where the rows were rendered, below the header.
+    rows_area: Rect,
+    theme: Theme,
 }
 
 #[derive(Debug)]
-struct ProjectData {
-    id: i32,
-    name: String,
-    editor: String,
-    path: String,
+pub struct ProjectData {
+    pub id: i32,
+    pub name: String,
+    pub editor: String,
+    pub path: String,
 }
 
 impl ProjectsScreen {
-    pub fn new() -> Self {
+    pub fn new(theme: Theme) -> Self {
         let sqlite = Sqlite::new();
 
         if !sqlite.check_table_exists("projects") {
             Self::create_projects_table(&sqlite);
+        } else if let Err(e) = migrate(&sqlite) {
+            info!("cannot migrate the projects table: {e}");
         }
 
         let mut projects = Self {
             db: sqlite,
             data: vec![],
             table_state: TableState::default().with_selected(Some(0)),
+            rows_area: Rect::default(),
+            theme,
         };
         projects.data = projects.get_projects();
 
         projects
     }
 
-    pub fn render(&mut self, f: &mut Frame) {
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(1), Constraint::Length(3)])
-            .split(f.area());
-
-        self.main_widget(chunks[0], f);
-        self.help_widget(chunks[1], f);
-    }
-
     // UIs
 
     fn main_widget(&mut self, area: Rect, f: &mut Frame) {
@@ -69,38 +88,42 @@ impl ProjectsScreen {
         let block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Green))
-            .style(Style::new().green())
+            .border_style(self.theme.border)
+            .title_style(self.theme.title)
+            .style(self.theme.text)
             .title("Projects");
+        let [_, rows_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(block.inner(area));
+        self.rows_area = rows_area;
 
         let table = Table::new(rows, widths)
             .column_spacing(1)
-            .style(Style::new().blue())
+            .style(self.theme.accent)
             .header(
                 Row::new(vec!["Name", "Editor", "Path"])
-                    .style(
-                        Style::new()
-                            .italic()
-                            .bold()
-                            .underline_color(Color::Blue)
-                            .fg(Color::Blue),
-                    )
+                    .style(self.theme.accent.italic().bold())
                     .slow_blink()
                     .add_modifier(Modifier::UNDERLINED),
             )
-            .row_highlight_style(Style::new().reversed())
-            .cell_highlight_style(Style::new().blue())
+            .row_highlight_style(self.theme.highlight)
+            .cell_highlight_style(self.theme.accent)
             .block(block)
             .highlight_symbol("->");
 
         f.render_stateful_widget(table, area, &mut self.table_state);
     }
 
-    fn help_widget(&mut self, area: Rect, f: &mut Frame) {
-        let help_message =
-            "(q | Esc) to quit / (b) back to main menu / (n) to add new entry / (d) to delete an entry / (e) to edit an entry";
+    fn help_widget(&mut self, area: Rect, f: &mut Frame, keymap: &Keymap) {
+        let help_message = format!(
+            "({}) to quit / ({}) to go back / ({}) to add new entry / ({}) to delete an entry / ({}) to edit an entry",
+            keymap.label(Context::Global, Action::Quit),
+            keymap.hint(Context::Global, Action::Back),
+            keymap.hint(Context::List, Action::New),
+            keymap.hint(Context::List, Action::Delete),
+            keymap.hint(Context::List, Action::Edit),
+        );
 
-        let help_widget = Span::styled(help_message, Style::default().fg(Color::Blue));
+        let help_widget = Span::styled(help_message, self.theme.muted);
         let help_widget = Paragraph::new(Line::from(help_widget)).block(
             Block::default()
                 .borders(Borders::ALL)
@@ -109,61 +132,6 @@ impl ProjectsScreen {
         f.render_widget(help_widget, area);
     }
 
-    pub fn handle_key(&mut self, e: KeyEvent) -> Option<AppActions> {
-        match e.code {
-            KeyCode::Char('q') | KeyCode::Esc => Some(AppActions::Quit),
-            KeyCode::Char('b') => Some(AppActions::ChangeScreen(ScreenType::Main)),
-            KeyCode::Char('j') => {
-                self.select_next();
-                None
-            }
-            KeyCode::Char('k') => {
-                self.select_previous();
-                None
-            }
-            KeyCode::Char('n') => {
-                // new
-                self.select_previous();
-                None
-            }
-            KeyCode::Char('d') => {
-                // delete
-                self.select_previous();
-                None
-            }
-            KeyCode::Char('e') => {
-                // edit
-                self.select_previous();
-                None
-            }
-            KeyCode::Enter => {
-                if let Some(index) = self.table_state.selected() {
-                    let selected = &self.data[index];
-
-                    let project_path = Path::new(".")
-                        .join(selected.path.clone())
-                        .into_os_string()
-                        .into_string()
-                        .unwrap();
-
-                    info!("Path: {project_path}");
-
-                    // maybe exit with command in stdout?
-
-                    // what to do when editor is vim?
-                    // std::process::Command::new("nvim")
-                    //     .arg(project_path)
-                    //     .spawn()
-                    //     .expect("Error: Failed to run editor")
-                    //     .wait()
-                    //     .expect("Error: Editor returned a non-zero status");
-                }
-                None
-            }
-            _ => None,
-        }
-    }
-
     fn select_next(&mut self) {
         self.table_state.select_next();
     }
@@ -172,8 +140,29 @@ impl ProjectsScreen {
         self.table_state.select_previous();
     }
 
-    pub fn handle_socket_event(&self, event: PhoenixEvent) {
-        println!("{event}");
+    fn open_selected(&self) {
+        if let Some(index) = self.table_state.selected() {
+            let selected = &self.data[index];
+            self.record_opened(selected.id);
+
+            let project_path = Path::new(".")
+                .join(selected.path.clone())
+                .into_os_string()
+                .into_string()
+                .unwrap();
+
+            info!("Path: {project_path}");
+
+            // maybe exit with command in stdout?
+
+            // what to do when editor is vim?
+            // std::process::Command::new("nvim")
+            //     .arg(project_path)
+            //     .spawn()
+            //     .expect("Error: Failed to run editor")
+            //     .wait()
+            //     .expect("Error: Editor returned a non-zero status");
+        }
     }
 
     // db stuff
@@ -184,7 +173,8 @@ impl ProjectsScreen {
                 id INTEGER PRIMARY KEY,
                 name TEXT NOT NULL,
                 path TEXT NOT NULL,
-                editor TEXT NOT NULL
+                editor TEXT NOT NULL,
+                opened_at INTEGER
             )",
             (),
         );
@@ -193,11 +183,22 @@ impl ProjectsScreen {
         result.unwrap();
     }
 
+    /// Remember when the project was opened, for the dashboard.
+    fn record_opened(&self, id: i32) {
+        let result = self.db.execute_query_with_params(
+            "UPDATE projects SET opened_at = strftime('%s', 'now') WHERE id = ?1",
+            [id],
+        );
+        if let Err(e) = result {
+            info!("cannot record project {id} as opened: {e}");
+        }
+    }
+
     fn get_projects(&self) -> Vec<ProjectData> {
         let mut stmt = self
             .db
             .connection
-            .prepare("SELECT * FROM projects")
+            .prepare("SELECT id, name, path, editor FROM projects")
             .unwrap();
 
         let projects = stmt
@@ -214,3 +215,124 @@ impl ProjectsScreen {
         projects.map(|v| v.unwrap()).collect()
     }
 }
+
+impl Screen for ProjectsScreen {
+    fn info(&self) -> &'static ScreenInfo {
+        &INFO
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(area);
+
+        self.main_widget(chunks[0], f);
+        self.help_widget(chunks[1], f, keymap);
+    }
+
+    fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions> {
+        match keymap.action(&[Context::List, Context::Global], &e)? {
+            Action::Quit => Some(AppActions::Quit),
+            Action::Back => Some(AppActions::Back),
+            Action::Down => {
+                self.select_next();
+                None
+            }
+            Action::Up => {
+                self.select_previous();
+                None
+            }
+            Action::New => {
+                // new
+                self.select_previous();
+                None
+            }
+            Action::Delete => {
+                // delete
+                self.select_previous();
+                None
+            }
+            Action::Edit => {
+                // edit
+                self.select_previous();
+                None
+            }
+            Action::Open => {
+                self.open_selected();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: Mouse) -> Option<AppActions> {
+        match mouse {
+            Mouse::Click(position) | Mouse::DoubleClick(position) => {
+                let index = item_at(self.rows_area, self.table_state.offset(), 1, position)
+                    .filter(|&index| index < self.data.len())?;
+                self.table_state.select(Some(index));
+                if let Mouse::DoubleClick(_) = mouse {
+                    self.open_selected();
+                }
+            }
+            Mouse::ScrollUp(_) => self.select_previous(),
+            Mouse::ScrollDown(_) => self.select_next(),
+        }
+        None
+    }
+
+    fn open_entity(&mut self, id: &str) {
+        let id: Option<i32> = id.parse().ok();
+        if let Some(index) = self.data.iter().position(|project| Some(project.id) == id) {
+            self.table_state.select(Some(index));
+        }
+    }
+
+    fn keybindings(&self) -> &'static [Keybinding] {
+        KEYBINDINGS
+    }
+}
+
+/// Bring a projects table created by an older version up to date. Does nothing without the
+/// table.
+fn migrate(db: &Sqlite) -> SqliteResult<()> {
+    // Tables created before projects were listed on the dashboard lack `opened_at`.
+    if db.check_table_exists("projects") && !db.check_column_exists("projects", "opened_at") {
+        db.execute_query("ALTER TABLE projects ADD COLUMN opened_at INTEGER")?;
+    }
+    Ok(())
+}
+
+/// Project names for the command palette, without creating the table.
+fn project_entities() -> SqliteResult<Vec<Entity>> {
+    let db = Sqlite::new();
+    let mut stmt = db.connection.prepare("SELECT id, name FROM projects")?;
+    let projects = stmt.query_map([], |row| {
+        Ok(Entity {
+            id: row.get::<_, i32>(0)?.to_string(),
+            title: row.get(1)?,
+        })
+    })?;
+    projects.collect()
+}
+
+/// Projects opened last, most recent first, without creating the table. Migrates it first, as
+/// the dashboard is shown before the Projects screen is ever opened.
+pub fn recent_projects(limit: usize) -> SqliteResult<Vec<ProjectData>> {
+    let db = Sqlite::new();
+    migrate(&db)?;
+    let mut stmt = db.connection.prepare(
+        "SELECT id, name, path, editor FROM projects
+        WHERE opened_at IS NOT NULL ORDER BY opened_at DESC LIMIT ?1",
+    )?;
+    let projects = stmt.query_map([limit], |row| {
+        Ok(ProjectData {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            path: row.get(2)?,
+            editor: row.get(3)?,
+        })
+    })?;
+    projects.collect()
+}
@@ -0,0 +1,137 @@
+use super::{
+    bin_screen, inspector_screen, main_screen, notes_screen, notifications_screen, projects_screen,
+    todos_screen,
+};
+use super::{App, AppActions};
+use crate::keymap::{Action, Context, Keymap};
+use crate::phoenix::event::SocketEvent;
+use crate::phoenix::protocol::Feature;
+use crate::tui::mouse::Mouse;
+use cli_log::info;
+use crossterm::event::KeyEvent;
+use ratatui::{layout::Rect, Frame};
+
+/// Every screen of the app. A screen is listed in the main menu, gets the events of its feature
+/// and can be opened from anywhere once it is registered here.
+pub static SCREENS: &[&ScreenInfo] = &[
+    &main_screen::INFO,
+    &notes_screen::INFO,
+    &bin_screen::INFO,
+    &projects_screen::INFO,
+    &todos_screen::INFO,
+    &inspector_screen::INFO,
+    &notifications_screen::INFO,
+];
+
+/// Registration of a screen.
+pub struct ScreenInfo {
+    pub id: &'static str,
+    pub title: &'static str,
+    /// Feature whose events are routed to the screen, and whose topic stays joined while the
+    /// screen is open.
+    pub feature: Option<Feature>,
+    /// Topics of other features whose data the screen shows from the store, also kept joined
+    /// while it is open.
+    pub extra_topics: &'static [Feature],
+    /// Listed in the main menu.
+    pub in_menu: bool,
+    pub open: fn(&App) -> Box<dyn Screen>,
+    /// What the screen lists, searchable from the command palette.
+    pub entities: fn(&App) -> Vec<Entity>,
+}
+
+impl ScreenInfo {
+    /// Every topic kept joined while the screen is open.
+    pub fn topics(&self) -> impl Iterator<Item = Feature> {
+        self.feature
+            .into_iter()
+            .chain(self.extra_topics.iter().copied())
+    }
+}
+
+/// Something a screen lists, like a bin or a project.
+pub struct Entity {
+    /// Passed back to `Screen::open_entity`.
+    pub id: String,
+    pub title: String,
+}
+
+/// For screens that don't list anything.
+pub fn no_entities(_: &App) -> Vec<Entity> {
+    Vec::new()
+}
+
+/// A screen `App` can open. Screens only see the events of their feature; everything else they
+/// need is read from the store or the channels they were opened with.
+pub trait Screen {
+    fn info(&self) -> &'static ScreenInfo;
+
+    /// Help lines show the keys bound in `keymap`.
+    fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap);
+
+    fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions>;
+
+    /// Clicks and the wheel, over what the screen rendered last.
+    fn handle_mouse(&mut self, _mouse: Mouse) -> Option<AppActions> {
+        None
+    }
+
+    fn handle_socket_event(&mut self, event: SocketEvent) {
+        info!("Unhandled event: {event:?}");
+    }
+
+    /// Re-issue the screen's initial fetch after the channel has been rejoined.
+    fn on_reconnect(&mut self) {}
+
+    /// The screens opened over this one were closed.
+    fn on_resume(&mut self) {}
+
+    /// Whether the screen shows something that changes with time, like an age, so it is redrawn
+    /// every tick.
+    fn changes_with_time(&self) -> bool {
+        false
+    }
+
+    /// Show an entity picked in the command palette.
+    fn open_entity(&mut self, _id: &str) {}
+
+    /// Keys the screen handles in its current state.
+    fn keybindings(&self) -> &'static [Keybinding];
+
+    fn title(&self) -> &'static str {
+        self.info().title
+    }
+
+    fn feature(&self) -> Option<Feature> {
+        self.info().feature
+    }
+}
+
+/// An action a screen handles and what it does. Its keys are looked up in the keymap.
+pub struct Keybinding {
+    pub context: Context,
+    pub action: Action,
+    pub description: &'static str,
+}
+
+impl Keybinding {
+    pub const fn new(context: Context, action: Action, description: &'static str) -> Self {
+        Self {
+            context,
+            action,
+            description,
+        }
+    }
+
+    pub const fn global(action: Action, description: &'static str) -> Self {
+        Self::new(Context::Global, action, description)
+    }
+
+    pub const fn list(action: Action, description: &'static str) -> Self {
+        Self::new(Context::List, action, description)
+    }
+
+    pub const fn form(action: Action, description: &'static str) -> Self {
+        Self::new(Context::Form, action, description)
+    }
+}
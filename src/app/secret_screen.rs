@@ -0,0 +1,208 @@
+use super::AppActions;
+use crate::keymap::{Action, Context, Keymap};
+use crate::secret::{Secret, SecretError, SecretSource};
+use crate::theme::Theme;
+use crate::tui::input_widget::InputWidget;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+/// Asks for the socket secret when the profile's source has none, or the server rejected it.
+/// The secret is stored in the source, except for environment variables where it is only used
+/// for the session.
+pub struct SecretScreen {
+    source: SecretSource,
+    /// Why the secret is needed, or why the last attempt failed.
+    message: String,
+    mode: Mode,
+    secret_input: InputWidget<'static>,
+    passphrase_input: InputWidget<'static>,
+    focused_element: SecretElements,
+    theme: Theme,
+}
+
+enum Mode {
+    /// Enter a new secret, and a passphrase for vaults.
+    Enter,
+    /// Enter the passphrase of an existing vault.
+    Unlock,
+}
+
+enum SecretElements {
+    Secret,
+    Passphrase,
+}
+
+impl SecretScreen {
+    pub fn new(source: SecretSource, error: SecretError, theme: Theme) -> Self {
+        let mode = match error {
+            SecretError::Locked { .. } | SecretError::WrongPassphrase => Mode::Unlock,
+            _ => Mode::Enter,
+        };
+        let focused_element = match mode {
+            Mode::Enter => SecretElements::Secret,
+            Mode::Unlock => SecretElements::Passphrase,
+        };
+        Self {
+            source,
+            message: error.to_string(),
+            mode,
+            secret_input: masked_input(&theme),
+            passphrase_input: masked_input(&theme),
+            focused_element,
+            theme,
+        }
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect, keymap: &Keymap) {
+        let block = Block::new()
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .border_style(self.theme.warning)
+            .title_style(self.theme.title)
+            .title("Secret");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+
+        let mut lines = vec![
+            Line::from(self.message.as_str()).style(self.theme.warning),
+            Line::from(""),
+            Line::from(format!("Source: {}", self.source)).style(self.theme.muted),
+        ];
+        if matches!(self.source, SecretSource::Env { .. }) && matches!(self.mode, Mode::Enter) {
+            lines.push(
+                Line::from("The secret will only be used until meowui exits.")
+                    .style(self.theme.muted),
+            );
+        }
+        f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), chunks[0]);
+
+        let mut fields = chunks[1..3].iter();
+        if matches!(self.mode, Mode::Enter) {
+            let focused = matches!(self.focused_element, SecretElements::Secret);
+            let input =
+                self.secret_input
+                    .clone()
+                    .block(field_block("Secret", focused, &self.theme));
+            f.render_widget(&input, *fields.next().unwrap());
+        }
+        if self.source.needs_passphrase() {
+            let focused = matches!(self.focused_element, SecretElements::Passphrase);
+            let input = self.passphrase_input.clone().block(field_block(
+                "Passphrase",
+                focused,
+                &self.theme,
+            ));
+            f.render_widget(&input, *fields.next().unwrap());
+        }
+
+        let confirm = keymap.hint(Context::Form, Action::Confirm);
+        let cancel = keymap.hint(Context::Form, Action::Cancel);
+        let help = match self.mode {
+            Mode::Enter => format!(
+                "{confirm}: connect  {}: next field  {cancel}: quit",
+                keymap.hint(Context::Form, Action::NextField),
+            ),
+            Mode::Unlock => format!(
+                "{confirm}: unlock  {}: replace the secret  {cancel}: quit",
+                keymap.hint(Context::Form, Action::ReplaceSecret),
+            ),
+        };
+        f.render_widget(
+            Paragraph::new(Line::from(Span::styled(help, self.theme.muted))),
+            chunks[4],
+        );
+    }
+
+    // Keyboard event handler
+
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions> {
+        match keymap.action_in_text(&[Context::Form], &e) {
+            Some(Action::Cancel) => Some(AppActions::Quit),
+            Some(Action::Confirm) => self.submit(),
+            Some(Action::ReplaceSecret) if matches!(self.mode, Mode::Unlock) => {
+                self.mode = Mode::Enter;
+                self.focused_element = SecretElements::Secret;
+                self.message = format!("Enter a new secret to replace {}.", self.source);
+                None
+            }
+            Some(Action::NextField) => {
+                if matches!(self.mode, Mode::Enter) && self.source.needs_passphrase() {
+                    self.focused_element = match self.focused_element {
+                        SecretElements::Secret => SecretElements::Passphrase,
+                        SecretElements::Passphrase => SecretElements::Secret,
+                    };
+                }
+                None
+            }
+            _ => {
+                match self.focused_element {
+                    SecretElements::Secret => self.secret_input.handle_key(keymap, e),
+                    SecretElements::Passphrase => self.passphrase_input.handle_key(keymap, e),
+                }
+                None
+            }
+        }
+    }
+
+    fn submit(&mut self) -> Option<AppActions> {
+        let passphrase = self.passphrase_input.content().to_string();
+        let result = match self.mode {
+            Mode::Unlock => self.source.unlock(&passphrase),
+            Mode::Enter => {
+                let secret = self.secret_input.content().trim().to_string();
+                if secret.is_empty() {
+                    self.message = "Enter the secret.".to_string();
+                    return None;
+                }
+                if self.source.needs_passphrase() && passphrase.is_empty() {
+                    self.message = "Choose a passphrase for the vault.".to_string();
+                    return None;
+                }
+                let secret = Secret::new(secret);
+                match self.source {
+                    SecretSource::Env { .. } => Ok(secret),
+                    _ => self.source.store(&secret, &passphrase).map(|()| secret),
+                }
+            }
+        };
+
+        match result {
+            Ok(secret) => Some(AppActions::UseSecret(secret)),
+            Err(e) => {
+                self.message = e.to_string();
+                self.passphrase_input = masked_input(&self.theme);
+                None
+            }
+        }
+    }
+}
+
+fn masked_input(theme: &Theme) -> InputWidget<'static> {
+    InputWidget::new(String::new(), theme).masked('•')
+}
+
+fn field_block(title: &'static str, focused: bool, theme: &Theme) -> Block<'static> {
+    Block::new()
+        .border_type(BorderType::Rounded)
+        .borders(Borders::ALL)
+        .border_style(theme.border_style(focused))
+        .title_style(theme.title)
+        .style(theme.text)
+        .title(title)
+}
@@ -0,0 +1,163 @@
+use crate::phoenix::protocol::bin::{Bin, BinResponse};
+use crate::phoenix::protocol::notes::{NotesResponse, Workspace};
+use crate::phoenix::protocol::todos::{Todo, TodosResponse};
+use crate::phoenix::protocol::Response;
+use chrono::{DateTime, Local};
+
+/// Data received from the socket, kept by `App` whichever screen is open. Screens hold a
+/// `watch::Receiver<Store>` and render from it, so pushes for screens that aren't visible are not
+/// lost.
+#[derive(Debug, Default)]
+pub struct Store {
+    pub bins: Vec<Bin>,
+    pub workspaces: Vec<Workspace>,
+    pub todos: Vec<Todo>,
+}
+
+impl Store {
+    /// Apply a response or broadcast. Returns whether anything changed.
+    pub fn ingest(&mut self, response: &Response) -> bool {
+        match response {
+            Response::Bin(BinResponse::GetAll { data }) => {
+                self.bins = data.clone();
+                true
+            }
+            Response::Bin(BinResponse::Edit { data }) => {
+                match self.bins.iter_mut().find(|bin| bin.id == data.id) {
+                    Some(bin) => *bin = data.clone(),
+                    None => self.bins.push(data.clone()),
+                }
+                true
+            }
+            // Only carry a status, the list is refetched afterwards.
+            Response::Bin(BinResponse::New { .. } | BinResponse::Delete { .. }) => false,
+            Response::Bin(
+                BinResponse::UploadStart { .. }
+                | BinResponse::UploadChunk { .. }
+                | BinResponse::UploadFinish { .. }
+                | BinResponse::Viewing { .. },
+            ) => false,
+            Response::Notes(NotesResponse::GetWorkspaces { data }) => {
+                self.workspaces = data.clone();
+                true
+            }
+            Response::Todos(TodosResponse::GetAll { data }) => {
+                self.todos = data.clone();
+                true
+            }
+        }
+    }
+
+    pub fn bin(&self, id: u64) -> Option<&Bin> {
+        self.bins.iter().find(|bin| bin.id == id)
+    }
+
+    /// Bins still alive at `now` that expire before `until`, soonest first.
+    pub fn bins_expiring(&self, now: DateTime<Local>, until: DateTime<Local>) -> Vec<&Bin> {
+        let mut bins: Vec<&Bin> = self
+            .bins
+            .iter()
+            .filter(|bin| bin.expire_at > now && bin.expire_at <= until)
+            .collect();
+        bins.sort_by_key(|bin| bin.expire_at);
+        bins
+    }
+
+    pub fn open_todos(&self) -> impl Iterator<Item = &Todo> {
+        self.todos.iter().filter(|todo| !todo.done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phoenix::protocol::StatusEvent;
+    use chrono::TimeDelta;
+
+    fn bin(id: u64, title: &str, expire_at: DateTime<Local>) -> Bin {
+        Bin {
+            id,
+            title: title.to_string(),
+            content: String::new(),
+            expire_at,
+            files: Vec::new(),
+        }
+    }
+
+    fn titles(bins: &[&Bin]) -> Vec<String> {
+        bins.iter().map(|bin| bin.title.clone()).collect()
+    }
+
+    fn get_all(bins: Vec<Bin>) -> Response {
+        Response::Bin(BinResponse::GetAll { data: bins })
+    }
+
+    #[test]
+    fn bins_are_replaced_and_edited() {
+        let now = Local::now();
+        let mut store = Store::default();
+        assert!(store.ingest(&get_all(vec![bin(1, "a", now), bin(2, "b", now)])));
+        assert!(store.ingest(&get_all(vec![bin(2, "b", now)])));
+        assert!(store.bin(1).is_none());
+
+        let edit = |bin| Response::Bin(BinResponse::Edit { data: bin });
+        assert!(store.ingest(&edit(bin(2, "b v2", now))));
+        assert!(store.ingest(&edit(bin(3, "c", now))));
+        let all: Vec<&Bin> = store.bins.iter().collect();
+        assert_eq!(titles(&all), ["b v2", "c"]);
+        assert_eq!(store.bin(3).unwrap().title, "c");
+    }
+
+    #[test]
+    fn statuses_change_nothing() {
+        let mut store = Store::default();
+        let ok = StatusEvent {
+            status: "OK".to_string(),
+            message: None,
+        };
+        assert!(!store.ingest(&Response::Bin(BinResponse::New { data: ok.clone() })));
+        assert!(!store.ingest(&Response::Bin(BinResponse::Delete { data: ok.clone() })));
+        assert!(!store.ingest(&Response::Bin(BinResponse::Viewing { data: ok })));
+        assert!(store.bins.is_empty());
+    }
+
+    #[test]
+    fn expiring_bins_are_listed_soonest_first() {
+        let now = Local::now();
+        let hours = |hours| now + TimeDelta::hours(hours);
+        let mut store = Store::default();
+        store.ingest(&get_all(vec![
+            bin(1, "expired", hours(-1)),
+            bin(2, "later", hours(20)),
+            bin(3, "soon", hours(1)),
+            bin(4, "next week", hours(24 * 7)),
+            bin(5, "at the limit", hours(24)),
+        ]));
+        let expiring = store.bins_expiring(now, hours(24));
+        assert_eq!(titles(&expiring), ["soon", "later", "at the limit"]);
+    }
+
+    #[test]
+    fn workspaces_and_todos_are_replaced() {
+        let mut store = Store::default();
+        let workspaces = vec![Workspace {
+            id: 1,
+            name: "Home".to_string(),
+        }];
+        let response = Response::Notes(NotesResponse::GetWorkspaces { data: workspaces });
+        assert!(store.ingest(&response));
+        assert_eq!(store.workspaces[0].name, "Home");
+
+        let todo = |id, done| Todo {
+            id,
+            title: format!("todo {id}"),
+            done,
+        };
+        let response = Response::Todos(TodosResponse::GetAll {
+            data: vec![todo(1, true), todo(2, false)],
+        });
+        assert!(store.ingest(&response));
+        let open: Vec<u64> = store.open_todos().map(|todo| todo.id).collect();
+        assert_eq!(open, [2]);
+    }
+}
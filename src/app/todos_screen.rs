@@ -1,38 +1,112 @@
-use super::{AppActions, ScreenType};
+use super::event_log::LoggedSender;
+use super::screen::{no_entities, Keybinding, Screen, ScreenInfo};
+use super::store::Store;
+use super::AppActions;
+use crate::keymap::{Action, Context, Keymap};
 use crate::phoenix::event::PhoenixEvent;
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::phoenix::protocol::{todos::TodosRequest, Feature, Request};
+use crate::theme::Theme;
+use cli_log::info;
+use crossterm::event::KeyEvent;
 use ratatui::{
-    style::{Color, Style, Stylize},
+    layout::Rect,
+    text::Line,
     widgets::{Block, BorderType, Borders, Paragraph},
     Frame,
 };
+use tokio::sync::watch;
 
-pub struct TodosScreen {}
+pub static INFO: ScreenInfo = ScreenInfo {
+    id: "todos",
+    title: "Todos",
+    feature: Some(Feature::Todos),
+    extra_topics: &[],
+    in_menu: true,
+    open: |app| {
+        Box::new(TodosScreen::new(
+            app.screen_sender.clone(),
+            app.store.subscribe(),
+            app.theme,
+        ))
+    },
+    entities: no_entities,
+};
+
+const KEYBINDINGS: &[Keybinding] = &[
+    Keybinding::global(Action::Back, "back"),
+    Keybinding::global(Action::Quit, "quit"),
+];
+
+/// Todos screen. The events exchanged with the socket are described on `TodosRequest` and
+/// `TodosResponse`.
+pub struct TodosScreen {
+    pub screen_sender: LoggedSender<PhoenixEvent>,
+    store: watch::Receiver<Store>,
+    theme: Theme,
+}
 
 impl TodosScreen {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(
+        screen_sender: LoggedSender<PhoenixEvent>,
+        store: watch::Receiver<Store>,
+        theme: Theme,
+    ) -> Self {
+        let todos_screen = Self {
+            screen_sender,
+            store,
+            theme,
+        };
+        todos_screen.push_event();
+        todos_screen
+    }
+
+    fn push_event(&self) {
+        let request = Request::Todos(TodosRequest::GetAll);
+        match self.screen_sender.try_send(PhoenixEvent::new(request)) {
+            Ok(()) => info!("sent message"),
+            Err(e) => info!("{e}"),
+        }
+    }
+}
+
+impl Screen for TodosScreen {
+    fn info(&self) -> &'static ScreenInfo {
+        &INFO
     }
 
-    pub fn render(&mut self, f: &mut Frame) {
+    fn render(&mut self, f: &mut Frame, area: Rect, _keymap: &Keymap) {
         let block = Block::new()
             .border_type(BorderType::Rounded)
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Green))
-            .style(Style::new().green())
+            .border_style(self.theme.border)
+            .title_style(self.theme.title)
+            .style(self.theme.text)
             .title("Todos");
-        f.render_widget(Paragraph::new("TODO").block(block), f.area());
+        let store = self.store.borrow();
+        let todos: Vec<Line> = store
+            .todos
+            .iter()
+            .map(|todo| {
+                let mark = if todo.done { "[x]" } else { "[ ]" };
+                Line::from(format!("{mark} {}", todo.title))
+            })
+            .collect();
+        f.render_widget(Paragraph::new(todos).block(block), area);
     }
 
-    pub fn handle_key(&mut self, e: KeyEvent) -> Option<AppActions> {
-        match e.code {
-            KeyCode::Char('q') | KeyCode::Esc => Some(AppActions::Quit),
-            KeyCode::Char('b') => Some(AppActions::ChangeScreen(ScreenType::Main)),
+    fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) -> Option<AppActions> {
+        match keymap.action(&[Context::Global], &e)? {
+            Action::Quit => Some(AppActions::Quit),
+            Action::Back => Some(AppActions::Back),
             _ => None,
         }
     }
 
-    pub fn handle_socket_event(&self, event: PhoenixEvent) {
-        println!("{event}");
+    fn on_reconnect(&mut self) {
+        self.push_event();
+    }
+
+    fn keybindings(&self) -> &'static [Keybinding] {
+        KEYBINDINGS
     }
 }
@@ -0,0 +1,482 @@
+use crate::app::AppChannels;
+use crate::phoenix::connection::ConnectionState;
+use crate::phoenix::event::{PhoenixEvent, SocketEvent};
+use crate::phoenix::protocol::bin::{
+    Bin, BinId, BinRequest, BinResponse, Expire, ExpireUnit, NewBin,
+};
+use crate::phoenix::protocol::{Request, Response};
+use crate::phoenix::request::RequestError;
+use anyhow::{anyhow, bail};
+use std::io::{IsTerminal, Read};
+
+pub const USAGE: &str = "\
+Usage:
+  meowui [--profile <name>]
+  meowui bin list [--json]
+  meowui bin show <id> [--json]
+  meowui bin new --title <title> --expire <time> [--json] < content
+  meowui bin delete <id> [--json]
+
+<time> is a number of minutes, hours or days, like 30m, 2h or 7d.";
+
+/// `meowui bin ...`, run against the server of the profile without the TUI.
+#[derive(Debug, PartialEq)]
+pub struct Cli {
+    command: BinCommand,
+    /// Print the bins or the server's status as JSON instead of text.
+    json: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum BinCommand {
+    List,
+    Show { id: u64 },
+    New { title: String, expire: Expire },
+    Delete { id: u64 },
+}
+
+/// Exit status of a command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Exit {
+    Ok = 0,
+    /// The reply could not be used, or the content could not be read.
+    Failed = 1,
+    Usage = 2,
+    /// Not connected, or no reply in time.
+    Unavailable = 3,
+    /// The server answered with a status other than `OK`.
+    Rejected = 4,
+    /// No bin has the ID.
+    NotFound = 5,
+    /// The config or the profile's secret can't be used, or the server refused the secret.
+    Config = 6,
+}
+
+impl Exit {
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+}
+
+/// The command in the arguments, None to start the TUI. `--profile` is read separately, by
+/// `config::profile_arg`.
+pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Option<Cli>> {
+    let mut positional = Vec::new();
+    let mut json = false;
+    let mut title = None;
+    let mut expire = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .map(str::to_string)
+                .or_else(|| args.next())
+                .ok_or_else(|| anyhow!("{name} needs a value"))
+        };
+        match flag.as_str() {
+            "--profile" | "-p" => {
+                value(&flag)?;
+            }
+            "--json" if inline.is_some() => bail!("--json takes no value"),
+            "--json" => json = true,
+            "--title" => title = Some(value(&flag)?),
+            "--expire" => expire = Some(parse_expire(&value(&flag)?)?),
+            _ if arg.starts_with('-') => bail!("unknown option {arg}"),
+            _ => positional.push(arg),
+        }
+    }
+
+    let command = match positional
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => return Ok(None),
+        ["bin", "list"] => BinCommand::List,
+        ["bin", "show", id] => BinCommand::Show { id: parse_id(id)? },
+        ["bin", "new"] => BinCommand::New {
+            title: title.ok_or_else(|| anyhow!("bin new needs --title"))?,
+            expire: expire.ok_or_else(|| anyhow!("bin new needs --expire"))?,
+        },
+        ["bin", "delete", id] => BinCommand::Delete { id: parse_id(id)? },
+        _ => bail!("unknown command: {}", positional.join(" ")),
+    };
+    Ok(Some(Cli { command, json }))
+}
+
+fn parse_id(id: &str) -> anyhow::Result<u64> {
+    id.parse().map_err(|_| anyhow!("{id} is not a bin ID"))
+}
+
+/// `30m`, `2h` or `7d`.
+fn parse_expire(expire: &str) -> anyhow::Result<Expire> {
+    let invalid = || anyhow!("{expire} is not a time like 30m, 2h or 7d");
+    let unit = match expire.chars().last() {
+        Some('m') => ExpireUnit::Minute,
+        Some('h') => ExpireUnit::Hour,
+        Some('d') => ExpireUnit::Day,
+        _ => return Err(invalid()),
+    };
+    let time = expire[..expire.len() - 1].parse().map_err(|_| invalid())?;
+    Ok(Expire { time, unit })
+}
+
+/// Wait for the transport to connect, run the command and print its result.
+pub async fn run(cli: Cli, mut channels: AppChannels) -> Exit {
+    if let Err(exit) = connect(&mut channels).await {
+        return exit;
+    }
+    let result = match cli.command {
+        BinCommand::List => list(&mut channels, cli.json).await,
+        BinCommand::Show { id } => show(&mut channels, id, cli.json).await,
+        BinCommand::New { title, expire } => match read_content() {
+            Ok(content) => new(&mut channels, title, expire, content, cli.json).await,
+            Err(exit) => Err(exit),
+        },
+        BinCommand::Delete { id } => delete(&mut channels, id, cli.json).await,
+    };
+    result.err().unwrap_or(Exit::Ok)
+}
+
+/// The first attempt has to succeed: there is nobody to wait for a reconnect.
+async fn connect(channels: &mut AppChannels) -> Result<(), Exit> {
+    let state = channels
+        .connection_state
+        .wait_for(|state| *state != ConnectionState::Connecting)
+        .await
+        .map(|state| state.clone())
+        .map_err(|_| Exit::Unavailable)?;
+    match state {
+        ConnectionState::Connected => Ok(()),
+        ConnectionState::Reconnecting { .. } => fail(Exit::Unavailable, "cannot connect"),
+        ConnectionState::Rejected => fail(Exit::Config, "the server refused the secret"),
+        state => fail(Exit::Unavailable, state),
+    }
+}
+
+async fn list(channels: &mut AppChannels, json: bool) -> Result<(), Exit> {
+    let bins = get_all(channels).await?;
+    if json {
+        return print_json(&bins);
+    }
+    for bin in bins {
+        println!(
+            "{}\t{}\t{}",
+            bin.id,
+            bin.expire_at.format("%Y-%m-%d %H:%M"),
+            bin.title
+        );
+    }
+    Ok(())
+}
+
+async fn show(channels: &mut AppChannels, id: u64, json: bool) -> Result<(), Exit> {
+    let bins = get_all(channels).await?;
+    let Some(bin) = bins.into_iter().find(|bin| bin.id == id) else {
+        return fail(Exit::NotFound, format!("no bin with id {id}"));
+    };
+    if json {
+        return print_json(&bin);
+    }
+    println!("{}", bin.title);
+    println!("Expire at: {}", bin.expire_at.format("%d/%m/%Y %I:%M %p"));
+    for file in &bin.files {
+        println!("File: {} ({})", file.name, file.type_name);
+    }
+    println!();
+    println!("{}", bin.content);
+    Ok(())
+}
+
+/// Content of `bin new`. Only piped content is read, so it doesn't wait on the terminal.
+fn read_content() -> Result<String, Exit> {
+    let mut content = String::new();
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        if let Err(e) = stdin.lock().read_to_string(&mut content) {
+            return fail(Exit::Failed, format!("cannot read the content: {e}"));
+        }
+    }
+    Ok(content)
+}
+
+async fn new(
+    channels: &mut AppChannels,
+    title: String,
+    expire: Expire,
+    content: String,
+    json: bool,
+) -> Result<(), Exit> {
+    let data = NewBin {
+        title: title.clone(),
+        content,
+        expire,
+        uploads: Vec::new(),
+    };
+    let response = request(channels, BinRequest::New { data }).await?;
+    check_status(&response, json)?;
+    if !json {
+        println!("Created {title}");
+    }
+    Ok(())
+}
+
+async fn delete(channels: &mut AppChannels, id: u64, json: bool) -> Result<(), Exit> {
+    // A missing bin exits like it does for `show`, rather than as a refused delete.
+    if !get_all(channels).await?.iter().any(|bin| bin.id == id) {
+        return fail(Exit::NotFound, format!("no bin with id {id}"));
+    }
+    let data = BinId { id };
+    let response = request(channels, BinRequest::Delete { data }).await?;
+    check_status(&response, json)?;
+    if !json {
+        println!("Deleted bin {id}");
+    }
+    Ok(())
+}
+
+async fn get_all(channels: &mut AppChannels) -> Result<Vec<Bin>, Exit> {
+    match request(channels, BinRequest::GetAll).await? {
+        BinResponse::GetAll { data } => Ok(data),
+        response => fail(Exit::Failed, format!("unexpected reply: {response:?}")),
+    }
+}
+
+/// Send the request and wait for its reply, skipping broadcasts.
+async fn request(channels: &mut AppChannels, request: BinRequest) -> Result<BinResponse, Exit> {
+    let event = PhoenixEvent::new(Request::Bin(request));
+    let request_id = event.request_id;
+    if channels.screen_sender.send(event).await.is_err() {
+        return fail(Exit::Unavailable, "not connected");
+    }
+
+    while let Some(event) = channels.socket_receiver.recv().await {
+        match event {
+            SocketEvent::Response {
+                request_id: Some(id),
+                response: Response::Bin(response),
+            } if id == request_id => return Ok(response),
+            SocketEvent::Failed {
+                request_id: id,
+                error,
+                ..
+            } if id == request_id => {
+                let exit = match error {
+                    RequestError::NotConnected | RequestError::Timeout(_) => Exit::Unavailable,
+                    RequestError::Rejected(_) => Exit::Rejected,
+                    RequestError::InvalidPayload(_)
+                    | RequestError::InvalidReply(_)
+                    | RequestError::Call(_) => Exit::Failed,
+                };
+                return fail(exit, error);
+            }
+            _ => {}
+        }
+    }
+    fail(Exit::Unavailable, "connection closed")
+}
+
+/// Fail unless the reply's status is `OK`. With `json`, the `StatusEvent` is printed either way,
+/// like `{"status": "OK", "message": null}`.
+fn check_status(response: &BinResponse, json: bool) -> Result<(), Exit> {
+    let Some(status) = response.status() else {
+        return fail(Exit::Failed, format!("unexpected reply: {response:?}"));
+    };
+    if json {
+        print_json(status)?;
+    }
+    if !status.is_ok() {
+        return fail(Exit::Rejected, status.error_message());
+    }
+    Ok(())
+}
+
+fn print_json(value: &impl serde::Serialize) -> Result<(), Exit> {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            println!("{json}");
+            Ok(())
+        }
+        Err(e) => fail(Exit::Failed, e),
+    }
+}
+
+fn fail<T>(exit: Exit, message: impl std::fmt::Display) -> Result<T, Exit> {
+    eprintln!("meowui: {message}");
+    Err(exit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phoenix::connection::ConnectionStats;
+    use crate::phoenix::presence::{LocalSession, Presence};
+    use crate::transport::memory::MemoryTransport;
+    use crate::transport::{Transport, TransportChannels};
+    use tokio::sync::{mpsc, watch};
+
+    fn parse_args(args: &str) -> anyhow::Result<Option<Cli>> {
+        parse(args.split_whitespace().map(str::to_string))
+    }
+
+    fn parse_error(args: &str) -> String {
+        parse_args(args).unwrap_err().to_string()
+    }
+
+    fn cli(command: BinCommand, json: bool) -> Option<Cli> {
+        Some(Cli { command, json })
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        assert!(parse_args("").unwrap().is_none());
+        assert!(parse_args("--profile staging").unwrap().is_none());
+        assert_eq!(
+            parse_args("bin list").unwrap(),
+            cli(BinCommand::List, false)
+        );
+        assert_eq!(
+            parse_args("-p staging bin show 12 --json").unwrap(),
+            cli(BinCommand::Show { id: 12 }, true)
+        );
+        assert_eq!(
+            parse_args("bin delete 3").unwrap(),
+            cli(BinCommand::Delete { id: 3 }, false)
+        );
+        assert_eq!(
+            parse_args("bin new --title Log --expire 2h").unwrap(),
+            cli(
+                BinCommand::New {
+                    title: "Log".to_string(),
+                    expire: Expire {
+                        time: 2,
+                        unit: ExpireUnit::Hour,
+                    },
+                },
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn inline_values_are_parsed() {
+        assert_eq!(
+            parse_args("--profile=staging bin new --title=a=b --expire=7d").unwrap(),
+            cli(
+                BinCommand::New {
+                    title: "a=b".to_string(),
+                    expire: Expire {
+                        time: 7,
+                        unit: ExpireUnit::Day,
+                    },
+                },
+                false
+            )
+        );
+        assert_eq!(parse_error("bin list --json=yes"), "--json takes no value");
+    }
+
+    #[test]
+    fn wrong_arguments_are_refused() {
+        assert_eq!(parse_error("bin new --title"), "--title needs a value");
+        assert_eq!(parse_error("bin list --profile"), "--profile needs a value");
+        assert_eq!(
+            parse_error("bin list --verbose"),
+            "unknown option --verbose"
+        );
+        assert_eq!(parse_error("bin list -v"), "unknown option -v");
+        assert_eq!(parse_error("bin new --expire 2h"), "bin new needs --title");
+        assert_eq!(parse_error("bin new --title Log"), "bin new needs --expire");
+        assert_eq!(parse_error("bin show twelve"), "twelve is not a bin ID");
+        assert_eq!(parse_error("bin show"), "unknown command: bin show");
+        assert_eq!(parse_error("bin rename 1"), "unknown command: bin rename 1");
+    }
+
+    #[test]
+    fn expiry_times_are_parsed() {
+        let expire = |time, unit| Expire { time, unit };
+        assert_eq!(parse_expire("30m").unwrap(), expire(30, ExpireUnit::Minute));
+        assert_eq!(parse_expire("2h").unwrap(), expire(2, ExpireUnit::Hour));
+        assert_eq!(parse_expire("7d").unwrap(), expire(7, ExpireUnit::Day));
+
+        for invalid in ["", "m", "10x", "10", "-5m", "1.5h", "h2", "5 m", "é"] {
+            assert_eq!(
+                parse_expire(invalid).unwrap_err().to_string(),
+                format!("{invalid} is not a time like 30m, 2h or 7d")
+            );
+        }
+    }
+
+    /// App side of a running `MemoryTransport`.
+    fn channels() -> AppChannels {
+        let (socket_tx, socket_receiver) = mpsc::channel(100);
+        let (screen_sender, screen_rx) = mpsc::channel(100);
+        let (topic_sender, topic_rx) = mpsc::channel(10);
+        let (upload_sender, upload_rx) = mpsc::channel(10);
+        let state_tx = watch::Sender::new(ConnectionState::Connecting);
+        let stats_tx = watch::Sender::new(ConnectionStats::default());
+        let presence_tx = watch::Sender::new(Presence::new(LocalSession::new()));
+        let channels = AppChannels {
+            socket_receiver,
+            screen_sender,
+            topic_sender,
+            upload_sender,
+            connection_state: state_tx.subscribe(),
+            connection_stats: stats_tx.subscribe(),
+            presence: presence_tx.subscribe(),
+            secret_sender: watch::Sender::new(None),
+        };
+        let mut transport = MemoryTransport::new(TransportChannels {
+            socket_tx,
+            screen_rx,
+            topic_rx,
+            upload_rx,
+            state_tx,
+            stats_tx,
+            presence_tx,
+        });
+        tokio::spawn(async move { transport.run().await });
+        channels
+    }
+
+    #[tokio::test]
+    async fn a_missing_bin_exits_with_not_found() {
+        for command in [BinCommand::Show { id: 42 }, BinCommand::Delete { id: 42 }] {
+            for json in [false, true] {
+                let cli = Cli {
+                    command: command.clone(),
+                    json,
+                };
+                assert_eq!(run(cli, channels()).await, Exit::NotFound);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn created_bins_can_be_deleted() {
+        let mut channels = channels();
+        connect(&mut channels).await.unwrap();
+        let title = "Log".to_string();
+        let expire = Expire {
+            time: 1,
+            unit: ExpireUnit::Day,
+        };
+        let content = "log".to_string();
+        assert_eq!(
+            new(&mut channels, title, expire, content, true).await,
+            Ok(())
+        );
+        let bins = get_all(&mut channels).await.unwrap();
+        assert_eq!(bins.len(), 1);
+        assert_eq!(bins[0].content, "log");
+
+        assert_eq!(delete(&mut channels, bins[0].id, true).await, Ok(()));
+        assert!(get_all(&mut channels).await.unwrap().is_empty());
+    }
+}
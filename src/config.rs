@@ -0,0 +1,294 @@
+use crate::keymap::KeyOverrides;
+use crate::secret::SecretSource;
+use crate::theme::ThemeConfig;
+use anyhow::{anyhow, Context};
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+/// Environment variable selecting the profile, overridden by `--profile`.
+pub const PROFILE_ENV: &str = "MEOWUI_PROFILE";
+
+const DEV_PROFILE: &str = "dev";
+const PROD_PROFILE: &str = "prod";
+
+/// Settings read from `$XDG_CONFIG_HOME/meowui/config.toml`, e.g.
+///
+/// ```toml
+/// profile = "staging"
+///
+/// [profiles.staging]
+/// socket_url = "wss://staging.example.com/tui/websocket"
+/// http_url = "https://staging.example.com"
+/// secret = { source = "env", var = "MEOWUI_STAGING_SECRET" }
+///
+/// [keys.form]
+/// save = ["ctrl-s", "ctrl-w"]
+///
+/// [theme]
+/// preset = "light"
+/// ```
+///
+/// The built-in `dev` and `prod` profiles are always available and can be overridden. Keys are
+/// described on `KeyOverrides` and colors on `Theme`.
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Profile used when none is given on the command line or in the environment.
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+    /// Keys replacing the default bindings.
+    #[serde(default)]
+    pub keys: KeyOverrides,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+}
+
+/// Server to connect to.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Websocket endpoint, e.g. `wss://host/tui/websocket`.
+    pub socket_url: String,
+    /// Base URL file links are relative to.
+    pub http_url: String,
+    /// Where the socket secret is read from.
+    #[serde(default)]
+    pub secret: SecretSource,
+}
+
+impl Config {
+    /// Read the config file, or the built-in profiles if there is none.
+    pub fn load() -> anyhow::Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("cannot read {}", path.display())),
+        };
+
+        Self::parse(&contents).with_context(|| format!("invalid {}", path.display()))
+    }
+
+    /// The built-in profiles with the contents of a config file on top.
+    fn parse(contents: &str) -> anyhow::Result<Self> {
+        let file: Config = toml::from_str(contents)?;
+        let mut profiles = Self::default().profiles;
+        profiles.extend(file.profiles);
+        Ok(Self { profiles, ..file })
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("meowui").join("config.toml"))
+    }
+
+    /// Pick a profile by name: `selected` (from `--profile`), then `MEOWUI_PROFILE`, then the
+    /// file's `profile`, then `dev` in debug builds and `prod` in release builds.
+    pub fn profile(&self, selected: Option<&str>) -> anyhow::Result<(String, Profile)> {
+        self.select_profile(selected, env::var(PROFILE_ENV).ok())
+    }
+
+    fn select_profile(
+        &self,
+        selected: Option<&str>,
+        from_env: Option<String>,
+    ) -> anyhow::Result<(String, Profile)> {
+        let name = selected
+            .map(str::to_string)
+            .or(from_env)
+            .or_else(|| self.profile.clone())
+            .unwrap_or_else(|| {
+                let name = if cfg!(debug_assertions) {
+                    DEV_PROFILE
+                } else {
+                    PROD_PROFILE
+                };
+                name.to_string()
+            });
+
+        let profile = self.profiles.get(&name).cloned().ok_or_else(|| {
+            let mut names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            names.sort();
+            anyhow!(
+                "unknown profile '{name}', expected one of: {}",
+                names.join(", ")
+            )
+        })?;
+        url::Url::parse(&profile.socket_url)
+            .with_context(|| format!("invalid socket_url '{}'", profile.socket_url))?;
+        Ok((name, profile))
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let profiles = HashMap::from([
+            (
+                DEV_PROFILE.to_string(),
+                Profile {
+                    socket_url: "ws://localhost:4000/tui/websocket".to_string(),
+                    http_url: "http://localhost:4000".to_string(),
+                    secret: SecretSource::default(),
+                },
+            ),
+            (
+                PROD_PROFILE.to_string(),
+                Profile {
+                    socket_url: "wss://phoenix.aayushsahu.com/tui/websocket".to_string(),
+                    http_url: "https://phoenix.aayushsahu.com".to_string(),
+                    secret: SecretSource::default(),
+                },
+            ),
+        ]);
+        Self {
+            profile: None,
+            profiles,
+            keys: KeyOverrides::new(),
+            theme: ThemeConfig::default(),
+        }
+    }
+}
+
+impl Profile {
+    /// Link to a file served by the server, from its `access_path`.
+    pub fn file_url(&self, access_path: &str) -> String {
+        format!("{}{}", self.http_url.trim_end_matches('/'), access_path)
+    }
+}
+
+/// Value of `--profile <name>` or `--profile=<name>` in the arguments.
+pub fn profile_arg(args: impl IntoIterator<Item = String>) -> anyhow::Result<Option<String>> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--profile" || arg == "-p" {
+            return args
+                .next()
+                .map(Some)
+                .ok_or_else(|| anyhow!("{arg} needs a profile name"));
+        }
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Ok(Some(name.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+profile = "staging"
+
+[profiles.staging]
+socket_url = "wss://staging.example.com/tui/websocket"
+http_url = "https://staging.example.com/"
+
+[profiles.dev]
+socket_url = "ws://localhost:4001/tui/websocket"
+http_url = "http://localhost:4001"
+"#;
+
+    fn args(args: &str) -> impl Iterator<Item = String> + '_ {
+        args.split_whitespace().map(str::to_string)
+    }
+
+    #[test]
+    fn the_file_is_merged_with_the_built_in_profiles() {
+        let config = Config::parse(CONFIG).unwrap();
+        let mut names: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["dev", "prod", "staging"]);
+        assert_eq!(
+            config.profiles["dev"].socket_url,
+            "ws://localhost:4001/tui/websocket"
+        );
+        assert_eq!(config.profiles["prod"], Config::default().profiles["prod"]);
+    }
+
+    #[test]
+    fn unknown_settings_are_refused() {
+        assert!(Config::parse("colour = \"red\"").is_err());
+        assert!(Config::parse("[profiles.staging]\nsocket_url = \"wss://x\"").is_err());
+    }
+
+    #[test]
+    fn profiles_are_picked_in_order() {
+        let config = Config::parse(CONFIG).unwrap();
+        let name = |selected, from_env: Option<&str>| {
+            let (name, _) = config
+                .select_profile(selected, from_env.map(str::to_string))
+                .unwrap();
+            name
+        };
+        assert_eq!(name(Some("prod"), Some("dev")), "prod");
+        assert_eq!(name(None, Some("dev")), "dev");
+        assert_eq!(name(None, None), "staging");
+
+        let default = if cfg!(debug_assertions) {
+            "dev"
+        } else {
+            "prod"
+        };
+        let (name, _) = Config::default().select_profile(None, None).unwrap();
+        assert_eq!(name, default);
+    }
+
+    #[test]
+    fn unknown_or_invalid_profiles_are_refused() {
+        let config = Config::parse(CONFIG).unwrap();
+        assert_eq!(
+            config
+                .select_profile(Some("qa"), None)
+                .unwrap_err()
+                .to_string(),
+            "unknown profile 'qa', expected one of: dev, prod, staging"
+        );
+
+        let config = Config::parse(
+            r#"
+[profiles.broken]
+socket_url = "not a url"
+http_url = "https://example.com"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config
+                .select_profile(Some("broken"), None)
+                .unwrap_err()
+                .to_string(),
+            "invalid socket_url 'not a url'"
+        );
+    }
+
+    #[test]
+    fn file_urls_are_relative_to_the_server() {
+        let config = Config::parse(CONFIG).unwrap();
+        let url = "https://staging.example.com/files/1/log.txt";
+        assert_eq!(config.profiles["staging"].file_url("/files/1/log.txt"), url);
+        let url = "http://localhost:4001/files/1/log.txt";
+        assert_eq!(config.profiles["dev"].file_url("/files/1/log.txt"), url);
+    }
+
+    #[test]
+    fn the_profile_argument_is_found() {
+        assert_eq!(profile_arg(args("")).unwrap(), None);
+        assert_eq!(profile_arg(args("bin list")).unwrap(), None);
+        let staging = Some("staging".to_string());
+        assert_eq!(profile_arg(args("--profile staging")).unwrap(), staging);
+        assert_eq!(profile_arg(args("-p staging bin list")).unwrap(), staging);
+        assert_eq!(
+            profile_arg(args("bin list --profile=staging")).unwrap(),
+            staging
+        );
+        assert_eq!(
+            profile_arg(args("bin list -p")).unwrap_err().to_string(),
+            "-p needs a profile name"
+        );
+    }
+}
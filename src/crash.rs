@@ -0,0 +1,69 @@
+use crate::app::event_log::EventLog;
+use crate::tui;
+use chrono::Local;
+use std::backtrace::Backtrace;
+use std::fmt::Write;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::{env, fs, thread};
+
+/// Most recent events written to a crash report.
+const REPORTED_EVENTS: usize = 50;
+
+/// Panic of the UI thread, with its backtrace, waiting to be written to the crash report.
+static PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Restore the terminal before any panic is printed, so the message isn't lost on the alternate
+/// screen. A panic of the calling thread is also kept for the crash report.
+pub fn install_panic_hook() {
+    let ui_thread = thread::current().id();
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        tui::restore();
+        if thread::current().id() == ui_thread {
+            let report = format!("{info}\n\n{}", Backtrace::force_capture());
+            if let Ok(mut panic) = PANIC.lock() {
+                *panic = Some(report);
+            }
+        }
+        previous(info);
+    }));
+}
+
+/// Write the panic and the last events of the session to
+/// `$XDG_STATE_HOME/meowui/crash-<time>.txt`, returning the path.
+pub fn write_report(event_log: &EventLog) -> std::io::Result<PathBuf> {
+    let now = Local::now();
+    let dir = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .unwrap_or_else(env::temp_dir)
+        .join("meowui");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("crash-{}.txt", now.format("%Y%m%d-%H%M%S")));
+
+    let panic = PANIC
+        .lock()
+        .ok()
+        .and_then(|mut panic| panic.take())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    let mut report = format!(
+        "meowui {} crashed at {}\n\n{panic}\n\nLast events, oldest first:\n",
+        env!("CARGO_PKG_VERSION"),
+        now.to_rfc3339(),
+    );
+    let entries: Vec<_> = event_log.entries().collect();
+    for entry in &entries[entries.len().saturating_sub(REPORTED_EVENTS)..] {
+        let _ = writeln!(
+            report,
+            "{:>9.3}s {} {} [{}] {}",
+            entry.at.as_secs_f64(),
+            entry.direction,
+            entry.name,
+            entry.status,
+            entry.json,
+        );
+    }
+    fs::write(&path, report)?;
+    Ok(path)
+}
@@ -0,0 +1,555 @@
+use anyhow::bail;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Keys bound in the `[keys.<context>]` tables of the config file, e.g.
+///
+/// ```toml
+/// [keys.global]
+/// quit = "ctrl-q"
+///
+/// [keys.list]
+/// delete = ["d", "delete"]
+/// ```
+pub type KeyOverrides = HashMap<Context, HashMap<Action, Keys>>;
+
+/// Where a key is pressed. Screens look a key up in the contexts of what is focused, in order.
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Context {
+    /// Everywhere, unless a text field is focused.
+    Global,
+    /// Lists, tables and menus.
+    List,
+    /// Screens with fields, like the new bin form.
+    Form,
+    /// Inside a text field, where other characters are typed.
+    TextInput,
+}
+
+/// What a key does.
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    // Global
+    Quit,
+    Back,
+    Help,
+    Palette,
+    Inspector,
+    Notifications,
+    Interrupt,
+    Suspend,
+    // List
+    Up,
+    Down,
+    First,
+    Last,
+    PageUp,
+    PageDown,
+    Open,
+    Deselect,
+    New,
+    Edit,
+    Delete,
+    OpenFile,
+    SwitchPane,
+    Discard,
+    Retry,
+    Filter,
+    // Form
+    Save,
+    Cancel,
+    NextField,
+    Confirm,
+    RemoveLastFile,
+    ToggleRemove,
+    ReplaceSecret,
+    // Text input
+    Left,
+    Right,
+    DeleteChar,
+    Newline,
+    NextUnit,
+    PreviousUnit,
+}
+
+/// Actions opened from anywhere, even from a text field. They can't be bound to characters that
+/// would be typed.
+const APP_ACTIONS: &[Action] = &[
+    Action::Help,
+    Action::Palette,
+    Action::Inspector,
+    Action::Notifications,
+    Action::Interrupt,
+    Action::Suspend,
+];
+
+const DEFAULTS: &[(Context, Action, &[&str])] = &[
+    (Context::Global, Action::Quit, &["q", "esc"]),
+    (Context::Global, Action::Back, &["b"]),
+    (Context::Global, Action::Help, &["f1"]),
+    (Context::Global, Action::Palette, &["ctrl-p"]),
+    (Context::Global, Action::Inspector, &["f12"]),
+    (Context::Global, Action::Notifications, &["f2"]),
+    (Context::Global, Action::Interrupt, &["ctrl-c"]),
+    (Context::Global, Action::Suspend, &["ctrl-z"]),
+    (Context::List, Action::Up, &["k", "up", "ctrl-k"]),
+    (Context::List, Action::Down, &["j", "down", "ctrl-n"]),
+    (Context::List, Action::First, &["g", "home"]),
+    (Context::List, Action::Last, &["G", "end"]),
+    (Context::List, Action::PageUp, &["pgup"]),
+    (Context::List, Action::PageDown, &["pgdn"]),
+    (Context::List, Action::Open, &["l", "right", "enter"]),
+    (Context::List, Action::Deselect, &["h", "left"]),
+    (Context::List, Action::New, &["n"]),
+    (Context::List, Action::Edit, &["e"]),
+    (Context::List, Action::Delete, &["d"]),
+    (Context::List, Action::OpenFile, &["o"]),
+    (Context::List, Action::SwitchPane, &["tab"]),
+    (Context::List, Action::Discard, &["x"]),
+    (Context::List, Action::Retry, &["r"]),
+    (Context::List, Action::Filter, &["/"]),
+    (Context::Form, Action::Save, &["ctrl-s"]),
+    (Context::Form, Action::Cancel, &["esc"]),
+    (Context::Form, Action::NextField, &["tab"]),
+    (Context::Form, Action::Confirm, &["enter"]),
+    (Context::Form, Action::RemoveLastFile, &["ctrl-x"]),
+    (Context::Form, Action::ToggleRemove, &["r"]),
+    (Context::Form, Action::ReplaceSecret, &["ctrl-n"]),
+    (Context::TextInput, Action::Left, &["left"]),
+    (Context::TextInput, Action::Right, &["right"]),
+    (Context::TextInput, Action::Up, &["up"]),
+    (Context::TextInput, Action::Down, &["down"]),
+    (Context::TextInput, Action::DeleteChar, &["backspace"]),
+    (Context::TextInput, Action::Newline, &["enter"]),
+    (Context::TextInput, Action::NextUnit, &["l"]),
+    (Context::TextInput, Action::PreviousUnit, &["h"]),
+];
+
+/// Keys of every action, per context: the defaults with the config file's bindings over them.
+pub struct Keymap {
+    bindings: HashMap<Context, Vec<(Action, Vec<Key>)>>,
+}
+
+impl Keymap {
+    /// The default bindings, replacing the keys of every action bound in `overrides`. An empty
+    /// list unbinds the action.
+    pub fn new(overrides: &KeyOverrides) -> anyhow::Result<Self> {
+        let mut keymap = Self::default();
+        for (context, actions) in overrides {
+            let bindings = keymap.bindings.entry(*context).or_default();
+            for (action, keys) in actions {
+                let Some((_, bound)) = bindings.iter_mut().find(|(a, _)| a == action) else {
+                    bail!("{action} cannot be bound in [keys.{context}]");
+                };
+                bound.clone_from(&keys.0);
+            }
+        }
+        keymap.validate()?;
+        Ok(keymap)
+    }
+
+    /// Two actions of a context can't share a key, and app-wide actions can't take a character.
+    fn validate(&self) -> anyhow::Result<()> {
+        for (context, bindings) in &self.bindings {
+            for (i, (action, keys)) in bindings.iter().enumerate() {
+                for key in keys {
+                    if let Some((other, _)) = bindings[i + 1..]
+                        .iter()
+                        .find(|(_, other_keys)| other_keys.contains(key))
+                    {
+                        bail!("{key} is bound to both {action} and {other} in [keys.{context}]");
+                    }
+                    if APP_ACTIONS.contains(action) && key.is_typed() {
+                        bail!("{action} cannot be bound to {key}, which is typed in text fields");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Action of the key in the first of `contexts` binding it.
+    pub fn action(&self, contexts: &[Context], e: &KeyEvent) -> Option<Action> {
+        contexts.iter().find_map(|context| {
+            self.bindings
+                .get(context)?
+                .iter()
+                .find(|(_, keys)| keys.iter().any(|key| key.matches(e)))
+                .map(|(action, _)| *action)
+        })
+    }
+
+    /// Like `action`, but characters are left to the focused text field even when bound.
+    pub fn action_in_text(&self, contexts: &[Context], e: &KeyEvent) -> Option<Action> {
+        if Key::from(*e).is_typed() {
+            None
+        } else {
+            self.action(contexts, e)
+        }
+    }
+
+    pub fn keys(&self, context: Context, action: Action) -> &[Key] {
+        self.bindings
+            .get(&context)
+            .and_then(|bindings| bindings.iter().find(|(a, _)| *a == action))
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Every key of the action, e.g. `j/Down`.
+    pub fn label(&self, context: Context, action: Action) -> String {
+        let keys: Vec<String> = self
+            .keys(context, action)
+            .iter()
+            .map(Key::to_string)
+            .collect();
+        keys.join("/")
+    }
+
+    /// First key of the action, for help lines.
+    pub fn hint(&self, context: Context, action: Action) -> String {
+        match self.keys(context, action).first() {
+            Some(key) => key.to_string(),
+            None => "unbound".to_string(),
+        }
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut bindings: HashMap<Context, Vec<(Action, Vec<Key>)>> = HashMap::new();
+        for (context, action, keys) in DEFAULTS {
+            let keys = keys
+                .iter()
+                .map(|key| key.parse().expect("default keys are valid"))
+                .collect();
+            bindings.entry(*context).or_default().push((*action, keys));
+        }
+        Self { bindings }
+    }
+}
+
+/// A key with Ctrl or Alt, as written in the config file: `q`, `G`, `ctrl-s`, `f12`, `pgdn`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Shift is part of the character, so `G` matches with or without it.
+    pub fn matches(&self, e: &KeyEvent) -> bool {
+        *self == Key::from(*e)
+    }
+
+    /// A character typed into a text field rather than a shortcut.
+    pub fn is_typed(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && self.modifiers.is_empty()
+    }
+
+    pub fn event(&self) -> KeyEvent {
+        KeyEvent::new(self.code, self.modifiers)
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(e: KeyEvent) -> Self {
+        Self {
+            code: e.code,
+            modifiers: e.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s.trim();
+        while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" => modifiers |= KeyModifiers::CONTROL,
+                "alt" => modifiers |= KeyModifiers::ALT,
+                _ => break,
+            }
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.is_empty() => KeyCode::Char(c),
+            (Some(c), None) => KeyCode::Char(c.to_ascii_lowercase()),
+            _ => match rest.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pgup" | "pageup" => KeyCode::PageUp,
+                "pgdn" | "pagedown" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) if (1..=24).contains(&n) => KeyCode::F(n),
+                    _ => bail!("unknown key '{s}'"),
+                },
+            },
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if !self.modifiers.is_empty() => {
+                write!(f, "{}", c.to_ascii_uppercase())
+            }
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            code => write!(f, "{code}"),
+        }
+    }
+}
+
+/// Keys of an action, written as one key or a list of keys.
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(try_from = "KeyNames")]
+pub struct Keys(Vec<Key>);
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum KeyNames {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl TryFrom<KeyNames> for Keys {
+    type Error = anyhow::Error;
+
+    fn try_from(names: KeyNames) -> Result<Self, Self::Error> {
+        let names = match names {
+            KeyNames::One(name) => vec![name],
+            KeyNames::Many(names) => names,
+        };
+        let keys = names
+            .iter()
+            .map(|name| name.parse())
+            .collect::<Result<_, _>>()?;
+        Ok(Self(keys))
+    }
+}
+
+impl Display for Context {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Context::Global => "global",
+            Context::List => "list",
+            Context::Form => "form",
+            Context::TextInput => "text_input",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Action::Quit => "quit",
+            Action::Back => "back",
+            Action::Help => "help",
+            Action::Palette => "palette",
+            Action::Inspector => "inspector",
+            Action::Notifications => "notifications",
+            Action::Interrupt => "interrupt",
+            Action::Suspend => "suspend",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::Open => "open",
+            Action::Deselect => "deselect",
+            Action::New => "new",
+            Action::Edit => "edit",
+            Action::Delete => "delete",
+            Action::OpenFile => "open_file",
+            Action::SwitchPane => "switch_pane",
+            Action::Discard => "discard",
+            Action::Retry => "retry",
+            Action::Filter => "filter",
+            Action::Save => "save",
+            Action::Cancel => "cancel",
+            Action::NextField => "next_field",
+            Action::Confirm => "confirm",
+            Action::RemoveLastFile => "remove_last_file",
+            Action::ToggleRemove => "toggle_remove",
+            Action::ReplaceSecret => "replace_secret",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::DeleteChar => "delete_char",
+            Action::Newline => "newline",
+            Action::NextUnit => "next_unit",
+            Action::PreviousUnit => "previous_unit",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(config: &str) -> KeyOverrides {
+        toml::from_str(config).unwrap()
+    }
+
+    fn key(name: &str) -> Key {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn defaults_parse_without_conflicts() {
+        let keymap = Keymap::new(&KeyOverrides::new()).unwrap();
+        for (context, action, keys) in DEFAULTS {
+            assert_eq!(keymap.keys(*context, *action).len(), keys.len());
+        }
+        assert_eq!(keymap.label(Context::List, Action::Down), "j/Down/Ctrl-N");
+    }
+
+    #[test]
+    fn keys_are_parsed() {
+        assert_eq!(key("q").code, KeyCode::Char('q'));
+        assert_eq!(key("G").code, KeyCode::Char('G'));
+        assert_eq!(key("ctrl-S"), key("ctrl-s"));
+        assert_eq!(key("Ctrl-s").modifiers, KeyModifiers::CONTROL);
+        assert_eq!(
+            key("ctrl-alt-x").modifiers,
+            KeyModifiers::CONTROL | KeyModifiers::ALT
+        );
+        assert_eq!(key("-").code, KeyCode::Char('-'));
+        assert_eq!(key("ctrl--").code, KeyCode::Char('-'));
+        assert_eq!(key("f12").code, KeyCode::F(12));
+        assert_eq!(key("PgDn").code, KeyCode::PageDown);
+        assert_eq!(key("space").code, KeyCode::Char(' '));
+        assert_eq!(key("ctrl-s").to_string(), "Ctrl-S");
+
+        for name in ["", "f0", "f25", "shift-a", "ctrl-", "nope"] {
+            assert!(name.parse::<Key>().is_err(), "{name:?} should be refused");
+        }
+    }
+
+    #[test]
+    fn overrides_replace_the_defaults() {
+        let config = overrides(
+            r#"
+            [global]
+            quit = "ctrl-q"
+
+            [list]
+            down = ["j", "ctrl-j"]
+            delete = []
+            "#,
+        );
+        let keymap = Keymap::new(&config).unwrap();
+
+        assert_eq!(keymap.keys(Context::Global, Action::Quit), [key("ctrl-q")]);
+        assert_eq!(
+            keymap.keys(Context::List, Action::Down),
+            [key("j"), key("ctrl-j")]
+        );
+        assert!(keymap.keys(Context::List, Action::Delete).is_empty());
+        assert_eq!(keymap.hint(Context::List, Action::Delete), "unbound");
+        assert_eq!(keymap.keys(Context::Global, Action::Back), [key("b")]);
+
+        let contexts = [Context::List, Context::Global];
+        assert_eq!(
+            keymap.action(&contexts, &key("ctrl-q").event()),
+            Some(Action::Quit)
+        );
+        assert_eq!(keymap.action(&contexts, &key("q").event()), None);
+        assert_eq!(keymap.action(&contexts, &key("d").event()), None);
+    }
+
+    #[test]
+    fn rebinding_frees_the_default_key() {
+        let config = overrides(
+            r#"
+            [list]
+            delete = ["d", "x"]
+            discard = "ctrl-x"
+            "#,
+        );
+        assert!(Keymap::new(&config).is_ok());
+    }
+
+    #[test]
+    fn conflicting_overrides_are_refused() {
+        let config = overrides(
+            r#"
+            [list]
+            delete = "j"
+            "#,
+        );
+        let error = Keymap::new(&config).err().unwrap().to_string();
+        assert!(error.contains("[keys.list]"), "{error}");
+        assert!(
+            error.contains("down") && error.contains("delete"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn app_actions_cannot_take_a_character() {
+        let config = overrides(
+            r#"
+            [global]
+            help = "?"
+            "#,
+        );
+        assert!(Keymap::new(&config).is_err());
+    }
+
+    #[test]
+    fn actions_are_bound_in_their_context() {
+        let config = overrides(
+            r#"
+            [text_input]
+            quit = "ctrl-q"
+            "#,
+        );
+        let error = Keymap::new(&config).err().unwrap().to_string();
+        assert_eq!(error, "quit cannot be bound in [keys.text_input]");
+    }
+
+    #[test]
+    fn invalid_keys_are_refused_when_read() {
+        let config = r#"
+            [list]
+            down = ["j", "hyper-j"]
+        "#;
+        assert!(toml::from_str::<KeyOverrides>(config).is_err());
+    }
+}
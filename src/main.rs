@@ -1,54 +1,221 @@
 mod app;
+mod cli;
+mod config;
+mod crash;
+mod keymap;
 mod phoenix;
+mod secret;
 mod sqlite;
+mod theme;
+mod transport;
 mod tui;
 
-use app::App;
-use phoenix::event::PhoenixEvent;
+use anyhow::Context;
+use app::{App, AppChannels};
+use cli_log::info;
+use config::Config;
+use futures_util::FutureExt;
+use keymap::Keymap;
+use phoenix::connection::{ConnectionState, ConnectionStats};
+use phoenix::event::{PhoenixEvent, SocketEvent};
+use phoenix::presence::{LocalSession, Presence};
+use phoenix::topic::TopicCommand;
+use phoenix::upload::Upload;
 use phoenix::Phoenix;
+use sqlite::outbox::Outbox;
 use std::env;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use theme::Theme;
 use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use transport::memory::MemoryTransport;
+use transport::record;
+use transport::replay::ReplayTransport;
+use transport::stub_server::StubServer;
+use transport::{Transport, TransportChannels};
 use tui::TUI;
 
-const SOCKET_ENDPOINT: &str = if cfg!(debug_assertions) {
-    "ws://localhost:4000/tui/websocket"
-} else {
-    "wss://phoenix.aayushsahu.com/tui/websocket"
-};
-
 #[tokio::main]
 async fn main() {
     // TODO: enable this for dev mode only
     cli_log::init_cli_log!();
 
-    let (socket_tx, socket_rx) = mpsc::channel::<PhoenixEvent>(100);
+    let command = cli::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("meowui: {e:#}\n\n{}", cli::USAGE);
+        std::process::exit(cli::Exit::Usage.code());
+    });
+    // Commands tell config errors apart from failed requests.
+    let config_exit = match command {
+        Some(_) => cli::Exit::Config.code(),
+        None => 1,
+    };
+    let (profile, keymap, theme) = load_config().unwrap_or_else(|e| exit_with_code(e, config_exit));
+
+    let (socket_tx, socket_rx) = mpsc::channel::<SocketEvent>(100);
     let (screen_tx, screen_rx) = mpsc::channel::<PhoenixEvent>(100);
-    let (signal_close_tx, mut signal_close_rx) = watch::channel(false);
+    let (topic_tx, topic_rx) = mpsc::channel::<TopicCommand>(10);
+    let (upload_tx, upload_rx) = mpsc::channel::<Upload>(10);
+    let (signal_close_tx, signal_close_rx) = watch::channel(false);
+    let (connection_tx, connection_rx) = watch::channel(ConnectionState::Connecting);
+    let (stats_tx, stats_rx) = watch::channel(ConnectionStats::default());
+    let (presence_tx, presence_rx) = watch::channel(Presence::new(LocalSession::new()));
+    let (secret_tx, secret_rx) = watch::channel(None);
+    // Asked for in the app when the profile's source has no usable secret.
+    let mut secret_error = None;
+
+    let channels = TransportChannels {
+        socket_tx,
+        screen_rx,
+        topic_rx,
+        upload_rx,
+        state_tx: connection_tx,
+        stats_tx,
+        presence_tx,
+    };
+    // Capture the session for a bug report or a demo.
+    let channels = match env::var("MEOWUI_RECORD") {
+        Ok(path) => record::record(channels, Path::new(&path)).unwrap_or_else(|e| exit_with(e)),
+        Err(_) => channels,
+    };
+
+    // `memory` and `stub` run against a local fake backend instead of the server, and `replay`
+    // plays back the session recorded in `MEOWUI_REPLAY`.
+    let transport_handle = match env::var("MEOWUI_TRANSPORT").as_deref() {
+        Ok("replay") => {
+            let path = env::var("MEOWUI_REPLAY")
+                .unwrap_or_else(|_| exit_with(anyhow::anyhow!("MEOWUI_REPLAY is not set")));
+            let replay =
+                ReplayTransport::load(Path::new(&path), channels).unwrap_or_else(|e| exit_with(e));
+            spawn_transport(replay, signal_close_rx)
+        }
+        Ok("memory") => spawn_transport(MemoryTransport::new(channels), signal_close_rx),
+        Ok("stub") => {
+            let server = StubServer::bind("127.0.0.1:0")
+                .await
+                .context("cannot start the stub server")
+                .unwrap_or_else(|e| exit_with(e));
+            let url = server
+                .url()
+                .context("cannot start the stub server")
+                .unwrap_or_else(|e| exit_with(e));
+            tokio::spawn(server.serve());
+            let phoenix = Phoenix::new(&url, channels).unwrap_or_else(|e| exit_with(e));
+            spawn_transport(with_outbox(phoenix, &command), signal_close_rx)
+        }
+        _ => {
+            match profile.secret.read() {
+                Ok(secret) => {
+                    secret_tx.send_replace(Some(secret));
+                }
+                Err(e) => secret_error = Some(e),
+            }
+            let phoenix = Phoenix::new(&profile.socket_url, channels)
+                .unwrap_or_else(|e| exit_with_code(e, config_exit))
+                .with_secret(secret_rx);
+            spawn_transport(with_outbox(phoenix, &command), signal_close_rx)
+        }
+    };
+
+    let channels = AppChannels {
+        socket_receiver: socket_rx,
+        screen_sender: screen_tx,
+        topic_sender: topic_tx,
+        upload_sender: upload_tx,
+        connection_state: connection_rx,
+        connection_stats: stats_rx,
+        presence: presence_rx,
+        secret_sender: secret_tx,
+    };
+    if let Some(command) = command {
+        // Nobody can enter a missing secret.
+        if let Some(e) = secret_error {
+            exit_with_code(anyhow::anyhow!("{e}"), cli::Exit::Config.code());
+        }
+        let exit = cli::run(command, channels).await;
+        let _ = signal_close_tx.send(true);
+        let _ = tokio::join!(transport_handle);
+        std::process::exit(exit.code());
+    }
 
-    let meowui_secret = env::var("MEOWUI_SECRET").unwrap();
-    let phoenix_endpoint = format!("{}?secret={}", SOCKET_ENDPOINT, meowui_secret);
+    let mut app = App::new(channels, profile, keymap, theme, Outbox::new());
+    if let Some(e) = secret_error {
+        app.request_secret(e);
+    }
+    let event_log = app.event_log.clone();
+    crash::install_panic_hook();
+    let mut tui = TUI::new();
+    // A panic of the app still closes the transport, after writing the crash report.
+    let crashed = match AssertUnwindSafe(tui.run(app)).catch_unwind().await {
+        Ok(Ok(())) => false,
+        Ok(Err(e)) => {
+            eprintln!("{e}");
+            false
+        }
+        Err(_) => {
+            match crash::write_report(&event_log.borrow()) {
+                Ok(path) => eprintln!("meowui: crash report written to {}", path.display()),
+                Err(e) => eprintln!("meowui: cannot write the crash report: {e}"),
+            }
+            true
+        }
+    };
 
-    let mut phoenix = Phoenix::new(phoenix_endpoint.as_str(), socket_tx, screen_rx);
-    let phoenix_handle = tokio::spawn(async move {
-        phoenix.setup().await;
+    let _ = signal_close_tx.send(true);
+    let _ = tokio::join!(transport_handle);
+    if crashed {
+        std::process::exit(101);
+    }
+}
 
+/// Profile selected with `--profile`, `MEOWUI_PROFILE` or the config file, and the keys and colors
+/// set in the config file.
+fn load_config() -> anyhow::Result<(config::Profile, Keymap, Theme)> {
+    let config = Config::load()?;
+    let selected = config::profile_arg(env::args().skip(1))?;
+    let (name, profile) = config.profile(selected.as_deref())?;
+    info!("Using profile {name}: {}", profile.socket_url);
+    let keymap = Keymap::new(&config.keys).context("invalid [keys]")?;
+    Ok((profile, keymap, Theme::new(&config.theme)))
+}
+
+fn exit_with(e: anyhow::Error) -> ! {
+    exit_with_code(e, 1)
+}
+
+fn exit_with_code(e: anyhow::Error, code: i32) -> ! {
+    eprintln!("meowui: {e:#}");
+    std::process::exit(code);
+}
+
+/// The app replays requests queued while offline. One-shot commands leave them to it. The app
+/// notifies when the outbox can't be opened.
+fn with_outbox(phoenix: Phoenix, command: &Option<cli::Cli>) -> Phoenix {
+    if command.is_some() {
+        return phoenix;
+    }
+    match Outbox::new() {
+        Ok(outbox) => phoenix.with_outbox(outbox),
+        Err(e) => {
+            info!("cannot open the outbox: {e}");
+            phoenix
+        }
+    }
+}
+
+/// Run the transport until it gives up or the app closes.
+fn spawn_transport<T: Transport + 'static>(
+    mut transport: T,
+    mut signal_close_rx: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
         tokio::select! {
-            _ = phoenix.run() => {
+            _ = transport.run() => {
                 // TODO: add print statement for `dev` only
             },
             _ = signal_close_rx.changed() => {}
         }
 
-        phoenix.disassemble().await;
-    });
-
-    let app = App::new(socket_rx, screen_tx);
-    let mut tui = TUI::new();
-    match tui.run(app) {
-        Ok(()) => {}
-        Err(e) => eprintln!("{e}"),
-    }
-
-    let _ = signal_close_tx.send(true);
-    let _ = tokio::join!(phoenix_handle);
+        transport.disassemble().await;
+    })
 }
@@ -1,106 +1,520 @@
+pub mod connection;
 pub mod event;
+pub mod presence;
+pub mod protocol;
+pub mod request;
+pub mod topic;
+pub mod upload;
 
+use crate::secret::Secret;
+use crate::sqlite::outbox::{Outbox, OutboxStatus};
+use crate::transport::{Transport, TransportChannels};
+use anyhow::Context;
 use cli_log::info;
-use event::PhoenixEvent;
-use phoenix_channels_client::{Channel, Event, Payload, Socket, Topic};
-use serde_json::json;
+use connection::{Backoff, ConnectionState, ConnectionStats};
+use event::{PhoenixEvent, SocketEvent};
+use phoenix_channels_client::{
+    Channel, ConnectError, Event, Payload, Socket, SocketStatus, Topic, WebSocketError,
+};
+use presence::Presence;
+use protocol::{Feature, Request, Response};
+use request::{RequestError, DEFAULT_TIMEOUT};
+use std::collections::HashMap;
+use std::time::Instant;
 use std::{sync::Arc, time::Duration};
-use tokio::sync::mpsc::{Receiver, Sender};
-use tokio::task::JoinHandle;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use topic::{JoinedTopic, TopicCommand, TopicUse};
+use upload::Upload;
 use url::Url;
 
-const CHANNEL_TOPIC: &str = "events";
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 
 pub struct Phoenix {
     pub url: Url,
-    pub socket_tx: Sender<PhoenixEvent>,
+    pub socket_tx: Sender<SocketEvent>,
     pub screen_rx: Receiver<PhoenixEvent>,
+    pub topic_rx: Receiver<TopicCommand>,
+    pub upload_rx: Receiver<Upload>,
+    pub state_tx: watch::Sender<ConnectionState>,
+    pub stats_tx: watch::Sender<ConnectionStats>,
+    pub presence_tx: watch::Sender<Presence>,
     pub socket: Option<Arc<Socket>>,
-    pub channel: Option<Arc<Channel>>,
+    /// Secret sent as the `secret` connect param, if the server needs one. Connecting waits
+    /// until it is set, and a rejected secret waits for the next one.
+    secret: Option<watch::Receiver<Option<Secret>>>,
+    /// Channels joined on the current socket.
+    channels: HashMap<Feature, JoinedTopic>,
+    /// Topics in use, kept across reconnects so they can be rejoined.
+    topics: HashMap<Feature, TopicUse>,
+    lost_tx: Sender<Feature>,
+    lost_rx: Receiver<Feature>,
+    in_flight: JoinSet<()>,
+    /// Replayed after connecting. One-shot commands run without it, leaving it to the app.
+    outbox: Option<Outbox>,
 }
 
 impl Phoenix {
-    pub fn new(
-        url: &str,
-        socket_tx: Sender<PhoenixEvent>,
-        screen_rx: Receiver<PhoenixEvent>,
-    ) -> Self {
-        let url = Url::parse(url).unwrap();
-
-        Self {
+    pub fn new(url: &str, channels: TransportChannels) -> anyhow::Result<Self> {
+        let url = Url::parse(url).with_context(|| format!("invalid socket URL '{url}'"))?;
+        let TransportChannels {
+            socket_tx,
+            screen_rx,
+            topic_rx,
+            upload_rx,
+            state_tx,
+            stats_tx,
+            presence_tx,
+        } = channels;
+        let (lost_tx, lost_rx) = mpsc::channel(10);
+
+        Ok(Self {
             url,
             socket_tx,
             screen_rx,
+            topic_rx,
+            upload_rx,
+            state_tx,
+            stats_tx,
+            presence_tx,
             socket: None,
-            channel: None,
+            secret: None,
+            channels: HashMap::new(),
+            topics: HashMap::new(),
+            lost_tx,
+            lost_rx,
+            in_flight: JoinSet::new(),
+            outbox: None,
+        })
+    }
+
+    pub fn with_secret(mut self, secret: watch::Receiver<Option<Secret>>) -> Self {
+        self.secret = Some(secret);
+        self
+    }
+
+    /// Replay the outbox after connecting.
+    pub fn with_outbox(mut self, outbox: Outbox) -> Self {
+        // Nothing is being sent before this transport starts replaying.
+        match outbox.requeue_interrupted() {
+            Ok(0) => {}
+            Ok(count) => info!("requeued {count} interrupted outbox items"),
+            Err(e) => info!("error requeuing interrupted outbox items: {e}"),
+        }
+        self.outbox = Some(outbox);
+        self
+    }
+
+    /// Socket URL with the connect params.
+    fn endpoint(&self) -> Url {
+        let mut url = self.url.clone();
+        if let Some(secret) = self
+            .secret
+            .as_ref()
+            .and_then(|secret| secret.borrow().clone())
+        {
+            url.query_pairs_mut().append_pair("secret", secret.expose());
+        }
+        url
+    }
+
+    /// Wait until a secret is set, if one is needed. False once the app is gone.
+    async fn wait_for_secret(&mut self) -> bool {
+        match &mut self.secret {
+            Some(secret) => secret.wait_for(Option::is_some).await.is_ok(),
+            None => true,
+        }
+    }
+
+    /// Connect the socket and rejoin the topics that were in use before a reconnect.
+    pub async fn setup(&mut self) -> anyhow::Result<()> {
+        let socket = Socket::spawn(self.endpoint(), None).await?;
+        self.socket = Some(socket.clone());
+        socket.connect(DEFAULT_TIMEOUT).await?;
+        self.stats_tx.send_modify(ConnectionStats::record_heartbeat);
+
+        let features: Vec<Feature> = self.topics.keys().copied().collect();
+        for feature in features {
+            self.join(feature).await?;
+        }
+        Ok(())
+    }
+
+    /// Channel for the feature's topic, joining it first if needed. Topics joined only to send a
+    /// request count as released, so they are left again once idle.
+    async fn channel(&mut self, feature: Feature) -> anyhow::Result<Arc<Channel>> {
+        self.topics
+            .entry(feature)
+            .or_insert(TopicUse::Released(Instant::now()));
+        self.join(feature).await
+    }
+
+    async fn join(&mut self, feature: Feature) -> anyhow::Result<Arc<Channel>> {
+        if let Some(joined) = self.channels.get(&feature) {
+            return Ok(joined.channel.clone());
+        }
+
+        let socket = self
+            .socket
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("socket not spawned"))?;
+        // Identifies this session in the topic's presence.
+        let params = self.presence_tx.borrow().session().join_params();
+        let params = Payload::json_from_serialized(params.to_string())?;
+        let channel = socket
+            .channel(
+                Topic::from_string(feature.topic().to_string()),
+                Some(params),
+            )
+            .await?;
+        channel.join(DEFAULT_TIMEOUT).await?;
+        info!("joined {} topic", feature.topic());
+
+        let joined = JoinedTopic::new(
+            feature,
+            channel.clone(),
+            self.socket_tx.clone(),
+            self.presence_tx.clone(),
+            self.stats_tx.clone(),
+            self.lost_tx.clone(),
+        );
+        self.channels.insert(feature, joined);
+        Ok(channel)
+    }
+
+    async fn handle_topic_command(&mut self, command: TopicCommand) {
+        match command {
+            TopicCommand::Join(feature) => {
+                self.topics.insert(feature, TopicUse::Held);
+                if let Err(e) = self.join(feature).await {
+                    info!("error joining {} topic: {e}", feature.topic());
+                }
+            }
+            TopicCommand::Release(feature) => {
+                if let Some(topic_use) = self.topics.get_mut(&feature) {
+                    *topic_use = TopicUse::Released(Instant::now());
+                }
+            }
+            // Handled by `serve` and `run`. The outbox is replayed anyway once connected again.
+            TopicCommand::Reconnect | TopicCommand::ReplayOutbox => {}
+        }
+    }
+
+    /// Keep track of the topics in use until the app asks to reconnect. False once the app is
+    /// gone.
+    async fn wait_for_reconnect(&mut self) -> bool {
+        while let Some(command) = self.topic_rx.recv().await {
+            if command == TopicCommand::Reconnect {
+                return true;
+            }
+            self.handle_topic_command(command).await;
         }
+        false
     }
 
-    pub async fn setup(&mut self) {
-        let socket = Some(Socket::spawn(self.url.clone(), None).await.unwrap());
-        if let Some(socket) = socket {
-            socket.connect(Duration::from_secs(10)).await.unwrap();
-            let channel = socket
-                .channel(Topic::from_string(CHANNEL_TOPIC.to_string()), None)
-                .await
-                .unwrap();
-            channel.join(Duration::from_secs(10)).await.unwrap();
-            self.socket = Some(socket);
-            self.channel = Some(channel);
+    async fn leave_idle_topics(&mut self) {
+        let idle: Vec<Feature> = self
+            .topics
+            .iter()
+            .filter(|(_, topic_use)| topic_use.is_idle())
+            .map(|(feature, _)| *feature)
+            .collect();
+
+        for feature in idle {
+            self.topics.remove(&feature);
+            if let Some(joined) = self.channels.remove(&feature) {
+                joined.leave().await;
+                info!("left idle {} topic", feature.topic());
+            }
         }
     }
 
-    pub async fn disassemble(&mut self) {
-        if let Some(socket) = &self.socket {
-            if let Some(channel) = &self.channel {
-                channel.leave().await.unwrap();
-                self.channel = None;
+    async fn replay_outbox(&mut self) -> anyhow::Result<()> {
+        let Some(mut outbox) = self.outbox.take() else {
+            return Ok(());
+        };
+        let result = self.replay(&mut outbox).await;
+        self.outbox = Some(outbox);
+        result
+    }
+
+    /// Send events queued while offline, one at a time and in the order they were queued.
+    /// Rejected events stay in the outbox as failed; a dropped connection stops the replay.
+    /// Replies are forwarded like broadcasts, as no screen is waiting for them.
+    async fn replay(&mut self, outbox: &mut Outbox) -> anyhow::Result<()> {
+        for (id, request) in outbox.queued()? {
+            let feature = request.feature();
+            let channel = self.channel(feature).await?;
+            let payload = encode(&request)?;
+            outbox.set_status(id, OutboxStatus::Sending, None)?;
+
+            let event = Event::from_string(feature.name().to_string());
+            match channel.call(event, payload, DEFAULT_TIMEOUT).await {
+                Ok(reply) => {
+                    let rejection = match Response::decode(feature, reply) {
+                        Ok(response) => {
+                            let rejection = response
+                                .status()
+                                .filter(|status| !status.is_ok())
+                                .map(|status| status.error_message());
+                            let event = SocketEvent::Response {
+                                request_id: None,
+                                response,
+                            };
+                            let _ = self.socket_tx.send(event).await;
+                            rejection
+                        }
+                        Err(e) => Some(e.to_string()),
+                    };
+                    match rejection {
+                        None => outbox.remove(id)?,
+                        Some(message) => {
+                            outbox.set_status(id, OutboxStatus::Failed, Some(message))?
+                        }
+                    }
+                }
+                Err(e) => {
+                    outbox.set_status(id, OutboxStatus::Queued, None)?;
+                    return Err(e.into());
+                }
             }
-            socket.disconnect().await.unwrap();
-            self.socket = None;
         }
+        Ok(())
     }
 
-    pub async fn run(&mut self) -> JoinHandle<()> {
-        let channel = self.channel.as_ref().unwrap().clone();
-        let events = channel.events();
+    /// Forward requests from screens to their topics and manage topic membership. Returns once
+    /// the socket or one of the joined channels is lost.
+    async fn serve(&mut self) {
+        let Some(socket) = self.socket.clone() else {
+            return;
+        };
+        let statuses = socket.statuses();
+        let mut idle_check = tokio::time::interval(IDLE_CHECK_INTERVAL);
 
         loop {
             tokio::select! {
-                _ = self.handle_screen_events() => {},
+                Some(value) = self.screen_rx.recv() => self.handle_screen_event(value).await,
+
+                Some(command) = self.topic_rx.recv() => match command {
+                    TopicCommand::Reconnect => {
+                        info!("reconnect requested");
+                        return;
+                    }
+                    TopicCommand::ReplayOutbox => {
+                        if let Err(e) = self.replay_outbox().await {
+                            info!("error replaying the outbox: {e}");
+                        }
+                    }
+                    command => self.handle_topic_command(command).await,
+                },
 
-                event = events.event() => {
-                    if let Ok(event) = event {
-                        let _ = self.socket_tx.send(event.payload.into());
+                Some(upload) = self.upload_rx.recv() => self.handle_upload(upload).await,
+
+                Some(_) = self.in_flight.join_next(), if !self.in_flight.is_empty() => {
+                    self.update_pending();
+                }
+
+                _ = idle_check.tick() => self.leave_idle_topics().await,
+
+                Some(feature) = self.lost_rx.recv() => {
+                    info!("{} channel lost", feature.topic());
+                    return;
+                }
+
+                status = statuses.status() => match status {
+                    Ok(Ok(SocketStatus::Connected)) => {}
+                    status => {
+                        info!("socket status: {status:?}");
+                        return;
                     }
                 }
             }
         }
     }
 
-    pub async fn handle_screen_events(&mut self) {
-        if let Some(value) = self.screen_rx.recv().await {
-            if let Some(channel) = &self.channel {
-                let payload = if let Some(payload) = value.payload {
-                    payload
-                } else {
-                    json!({})
+    fn set_state(&self, state: ConnectionState) {
+        let _ = self.state_tx.send(state);
+    }
+
+    fn update_pending(&self) {
+        let pending = self.in_flight.len();
+        self.stats_tx.send_if_modified(|stats| {
+            let changed = stats.pending != pending;
+            stats.pending = pending;
+            changed
+        });
+    }
+
+    /// Send a screen event without waiting for its reply. Each request runs in its own task with
+    /// its own timeout, and the reply (or error) is sent back tagged with the request ID.
+    pub async fn handle_screen_event(&mut self, value: PhoenixEvent) {
+        let feature = value.request.feature();
+        let channel = match self.channel(feature).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                info!("error joining {} topic: {e}", feature.topic());
+                let event = SocketEvent::failed(&value, RequestError::NotConnected);
+                let _ = self.socket_tx.send(event).await;
+                return;
+            }
+        };
+
+        let payload = match encode(&value.request) {
+            Ok(payload) => payload,
+            Err(e) => {
+                let error = RequestError::InvalidPayload(e.to_string());
+                let _ = self
+                    .socket_tx
+                    .send(SocketEvent::failed(&value, error))
+                    .await;
+                return;
+            }
+        };
+
+        let socket_tx = self.socket_tx.clone();
+        let stats_tx = self.stats_tx.clone();
+        self.in_flight.spawn(async move {
+            let name = Event::from_string(value.name().to_string());
+            let started = Instant::now();
+            let reply = channel.call(name, payload, value.timeout).await;
+            if reply.is_ok() {
+                stats_tx.send_modify(|stats| stats.record_reply(started.elapsed()));
+            }
+            let event = match reply {
+                Ok(payload) => match Response::decode(feature, payload) {
+                    Ok(response) => SocketEvent::Response {
+                        request_id: Some(value.request_id),
+                        response,
+                    },
+                    Err(e) => {
+                        SocketEvent::failed(&value, RequestError::InvalidReply(e.to_string()))
+                    }
+                },
+                Err(e) => SocketEvent::failed(&value, RequestError::from_call(e, value.timeout)),
+            };
+            let _ = socket_tx.send(event).await;
+        });
+        self.update_pending();
+    }
+
+    /// Start uploading a file on the `bin` topic. Progress and the result are sent to `App` as
+    /// socket events tagged with the upload's request ID.
+    pub async fn handle_upload(&mut self, upload: Upload) {
+        let channel = match self.channel(Feature::Bin).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                info!("error joining {} topic: {e}", Feature::Bin.topic());
+                let event = SocketEvent::Failed {
+                    request_id: upload.request_id,
+                    feature: Feature::Bin,
+                    error: RequestError::NotConnected,
                 };
+                let _ = self.socket_tx.send(event).await;
+                return;
+            }
+        };
+
+        self.in_flight
+            .spawn(upload::run(upload, channel, self.socket_tx.clone()));
+        self.update_pending();
+    }
+}
+
+impl Transport for Phoenix {
+    /// Supervise the connection: connect, serve until the connection drops, then reconnect with
+    /// backoff. Gives up after `MAX_RECONNECT_ATTEMPTS` consecutive failures, until the app asks
+    /// to reconnect.
+    async fn run(&mut self) {
+        let mut backoff = Backoff::new();
+
+        loop {
+            if !self.wait_for_secret().await {
+                return;
+            }
 
-                match channel
-                    .call(
-                        Event::from_string(value.name),
-                        Payload::json_from_serialized(payload.to_string()).unwrap(),
-                        Duration::from_secs(10),
-                    )
-                    .await
-                {
-                    Ok(payload) => {
-                        let _ = self.socket_tx.send(payload.into()).await;
+            let connected = match self.setup().await {
+                Ok(()) => self.replay_outbox().await,
+                Err(e) => Err(e),
+            };
+            let reason = match connected {
+                Ok(()) => {
+                    backoff.reset();
+                    self.set_state(ConnectionState::Connected);
+                    self.serve().await;
+                    "connection lost".to_string()
+                }
+                Err(e) if self.secret.is_some() && is_rejected(&e) => {
+                    info!("phoenix: secret rejected: {e}");
+                    self.disassemble().await;
+                    self.set_state(ConnectionState::Rejected);
+                    // Retry once a new secret is entered.
+                    if let Some(secret) = &mut self.secret {
+                        if secret.changed().await.is_err() {
+                            return;
+                        }
                     }
-                    Err(e) => info!("error: {e}"),
+                    backoff.reset();
+                    self.set_state(ConnectionState::Connecting);
+                    continue;
                 }
+                Err(e) => e.to_string(),
+            };
+            info!("phoenix: {reason}");
+            self.disassemble().await;
+
+            if backoff.attempt() >= MAX_RECONNECT_ATTEMPTS {
+                self.set_state(ConnectionState::Failed(reason));
+                if !self.wait_for_reconnect().await {
+                    return;
+                }
+                backoff.reset();
+                self.set_state(ConnectionState::Connecting);
+                continue;
             }
-        };
+
+            let delay = backoff.next_delay();
+            self.set_state(ConnectionState::Reconnecting {
+                attempt: backoff.attempt(),
+            });
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                reconnect = self.wait_for_reconnect() => {
+                    if !reconnect {
+                        return;
+                    }
+                    backoff.reset();
+                }
+            }
+        }
+    }
+
+    async fn disassemble(&mut self) {
+        for (_, joined) in self.channels.drain() {
+            joined.leave().await;
+        }
+        if let Some(socket) = self.socket.take() {
+            if let Err(e) = socket.disconnect().await {
+                info!("error disconnecting socket: {e}");
+            }
+        }
     }
 }
+
+/// Whether connecting failed because the server refused the connect params, answering the
+/// websocket upgrade with 401 or 403.
+fn is_rejected(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ConnectError>(),
+        Some(ConnectError::WebSocketError {
+            web_socket_error: WebSocketError::Http { response },
+        }) if matches!(response.status_code, 401 | 403)
+    )
+}
+
+/// Encode a request as the JSON payload of a channel event.
+fn encode(request: &Request) -> anyhow::Result<Payload> {
+    let payload = request.payload()?;
+    Ok(Payload::json_from_serialized(payload.to_string())?)
+}
@@ -0,0 +1,94 @@
+use std::collections::hash_map::RandomState;
+use std::fmt::Display;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+/// State of the socket connection, published by the `Phoenix` task.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Reconnecting {
+        attempt: u32,
+    },
+    Failed(String),
+    /// The server refused the secret. `Phoenix` waits for a new one.
+    Rejected,
+}
+
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionState::Connecting => write!(f, "Connecting..."),
+            ConnectionState::Connected => write!(f, "Connected"),
+            ConnectionState::Reconnecting { attempt } => {
+                write!(f, "Reconnecting (attempt {attempt})...")
+            }
+            ConnectionState::Failed(reason) => write!(f, "Connection failed: {reason}"),
+            ConnectionState::Rejected => write!(f, "Secret rejected"),
+        }
+    }
+}
+
+/// Health of the connection shown in the status bar, published by the transport alongside
+/// `ConnectionState`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionStats {
+    /// Round trip of the last `channel.call` that got a reply.
+    pub latency: Option<Duration>,
+    /// Last time the server was heard from: a reply, a pushed event or a successful connect.
+    /// The client answers the protocol's own heartbeats without exposing them.
+    pub last_heartbeat: Option<Instant>,
+    /// Requests and uploads waiting for their reply.
+    pub pending: usize,
+}
+
+impl ConnectionStats {
+    pub fn record_reply(&mut self, latency: Duration) {
+        self.latency = Some(latency);
+        self.record_heartbeat();
+    }
+
+    pub fn record_heartbeat(&mut self) {
+        self.last_heartbeat = Some(Instant::now());
+    }
+}
+
+/// Exponential backoff with jitter used between reconnect attempts.
+pub struct Backoff {
+    attempt: u32,
+}
+
+impl Backoff {
+    const BASE: Duration = Duration::from_millis(500);
+    const MAX: Duration = Duration::from_secs(30);
+
+    pub fn new() -> Self {
+        Self { attempt: 0 }
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Delay before the next attempt. Half of the delay is fixed and the other half is random so
+    /// that several clients don't hammer the server in lockstep.
+    pub fn next_delay(&mut self) -> Duration {
+        self.attempt += 1;
+        let exponential = Self::BASE
+            .saturating_mul(2u32.saturating_pow(self.attempt - 1))
+            .min(Self::MAX);
+        let half = exponential / 2;
+        half + half.mul_f64(jitter())
+    }
+}
+
+/// Random number in `0.0..1.0`, good enough for spreading out reconnects.
+fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random % 1000) as f64 / 1000.0
+}
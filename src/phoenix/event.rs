@@ -1,51 +1,89 @@
-use crate::app::ScreenType;
-use phoenix_channels_client::{Payload, JSON};
+use super::protocol::{Feature, ProtocolError, Request, Response};
+use super::request::{next_request_id, RequestError, RequestId, DEFAULT_TIMEOUT};
 use std::fmt::Display;
+use std::time::Duration;
 
-/// Generic event over phoenix socket used for both 'send' and 'receive'
-#[derive(serde::Serialize, serde::Deserialize)]
+/// Request sent from a screen to the socket.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct PhoenixEvent {
-    pub name: String,
-    pub payload: Option<serde_json::Value>,
+    pub request: Request,
+    pub request_id: RequestId,
+    pub timeout: Duration,
 }
 
 impl PhoenixEvent {
-    pub fn for_screen(&self) -> ScreenType {
-        match self.name.as_str() {
-            "main" => ScreenType::Main,
-            "bin" => ScreenType::Bin,
-            "notes" => ScreenType::Notes,
-            "projects" => ScreenType::Projects,
-            "todos" => ScreenType::Todos,
-            _ => panic!("Not implemented for event: {}", self.name.as_str()),
+    /// New request with a fresh request ID and the default timeout.
+    pub fn new(request: Request) -> Self {
+        Self {
+            request,
+            request_id: next_request_id(),
+            timeout: DEFAULT_TIMEOUT,
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        self.request.feature().name()
+    }
 }
 
 impl Display for PhoenixEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {:?}", self.name, self.payload)
+        write!(
+            f,
+            "#{} {} {}",
+            self.request_id,
+            self.name(),
+            self.request.action()
+        )
     }
 }
 
-impl From<Payload> for PhoenixEvent {
-    fn from(value: Payload) -> Self {
-        match value {
-            Payload::JSONPayload { json } => {
-                let json_value: serde_json::Value = JSON::into(json);
-                let json_value: PhoenixEvent = serde_json::from_value(json_value).unwrap();
-                Self {
-                    name: json_value.name,
-                    payload: json_value.payload,
-                }
-            }
-            _ => panic!("Expecting JSON, received Binary payload."),
+/// Message received from the socket.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SocketEvent {
+    /// Reply to a request (`request_id` is set) or a broadcast from the server.
+    Response {
+        request_id: Option<RequestId>,
+        response: Response,
+    },
+    /// A request did not get a usable reply.
+    Failed {
+        request_id: RequestId,
+        feature: Feature,
+        error: RequestError,
+    },
+    /// Bytes of an upload accepted by the server so far.
+    UploadProgress {
+        request_id: RequestId,
+        sent: u64,
+        total: u64,
+    },
+    /// Every chunk of an upload was accepted. `upload_id` can now be attached to a bin.
+    Uploaded {
+        request_id: RequestId,
+        upload_id: String,
+    },
+    /// The server sent something the client does not understand.
+    Diagnostic(ProtocolError),
+}
+
+impl SocketEvent {
+    pub fn failed(event: &PhoenixEvent, error: RequestError) -> Self {
+        SocketEvent::Failed {
+            request_id: event.request_id,
+            feature: event.request.feature(),
+            error,
         }
     }
-}
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct StatusEvent {
-    pub status: String,
-    pub message: Option<String>,
+    /// Feature the event should be routed to. Diagnostics don't belong to any feature.
+    pub fn feature(&self) -> Option<Feature> {
+        match self {
+            SocketEvent::Response { response, .. } => Some(response.feature()),
+            SocketEvent::Failed { feature, .. } => Some(*feature),
+            SocketEvent::UploadProgress { .. } | SocketEvent::Uploaded { .. } => Some(Feature::Bin),
+            SocketEvent::Diagnostic(_) => None,
+        }
+    }
 }
@@ -0,0 +1,282 @@
+use super::protocol::Feature;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+
+/// This meowui instance, sent when joining a topic so the server can track it.
+#[derive(Debug, Clone)]
+pub struct LocalSession {
+    pub id: String,
+    pub name: String,
+}
+
+impl LocalSession {
+    /// New session ID, named after the host.
+    pub fn new() -> Self {
+        let id = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        let name = std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "meowui".to_string());
+
+        Self {
+            id: format!("{id:016x}"),
+            name,
+        }
+    }
+
+    /// Params sent with `phx_join`.
+    pub fn join_params(&self) -> Value {
+        json!({"session_id": self.id, "name": self.name})
+    }
+}
+
+/// One connection of a session, as tracked by the server.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct PresenceMeta {
+    #[serde(default)]
+    pub phx_ref: String,
+    #[serde(default)]
+    pub name: String,
+    /// Bin the session has open, if any.
+    #[serde(default)]
+    pub bin_id: Option<u64>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct PresenceEntry {
+    pub metas: Vec<PresenceMeta>,
+}
+
+/// Payload of `presence_state`: every session on the topic, keyed by session ID.
+pub type PresenceState = HashMap<String, PresenceEntry>;
+
+/// Payload of `presence_diff`.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct PresenceDiff {
+    #[serde(default)]
+    pub joins: PresenceState,
+    #[serde(default)]
+    pub leaves: PresenceState,
+}
+
+/// Another session seen on a joined topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub id: String,
+    pub name: String,
+    pub bin_id: Option<u64>,
+}
+
+/// Sessions present on each joined topic, kept up to date by `Phoenix` from the `presence_state`
+/// and `presence_diff` events. Shared with `App` over a watch channel.
+#[derive(Debug, Clone)]
+pub struct Presence {
+    session: LocalSession,
+    topics: HashMap<Feature, PresenceState>,
+}
+
+impl Presence {
+    pub fn new(session: LocalSession) -> Self {
+        Self {
+            session,
+            topics: HashMap::new(),
+        }
+    }
+
+    pub fn session(&self) -> &LocalSession {
+        &self.session
+    }
+
+    pub fn sync_state(&mut self, feature: Feature, state: PresenceState) {
+        self.topics.insert(feature, state);
+    }
+
+    /// Apply joins and leaves the way the phoenix.js client does: metas are added and removed by
+    /// `phx_ref`, and a session is gone once it has no metas left.
+    pub fn sync_diff(&mut self, feature: Feature, diff: PresenceDiff) {
+        let state = self.topics.entry(feature).or_default();
+
+        for (key, joined) in diff.joins {
+            let entry = state
+                .entry(key)
+                .or_insert_with(|| PresenceEntry { metas: Vec::new() });
+            for meta in joined.metas {
+                entry
+                    .metas
+                    .retain(|current| current.phx_ref != meta.phx_ref);
+                entry.metas.push(meta);
+            }
+        }
+
+        for (key, left) in diff.leaves {
+            let Some(entry) = state.get_mut(&key) else {
+                continue;
+            };
+            entry
+                .metas
+                .retain(|meta| !left.metas.iter().any(|gone| gone.phx_ref == meta.phx_ref));
+            if entry.metas.is_empty() {
+                state.remove(&key);
+            }
+        }
+    }
+
+    /// Forget the topic's sessions after leaving it.
+    pub fn leave(&mut self, feature: Feature) {
+        self.topics.remove(&feature);
+    }
+
+    /// Other sessions on any joined topic, sorted by name.
+    pub fn sessions(&self) -> Vec<Session> {
+        let mut sessions: HashMap<&str, Session> = HashMap::new();
+        for (id, entry) in self.topics.values().flatten() {
+            if *id == self.session.id {
+                continue;
+            }
+            for meta in &entry.metas {
+                let session = sessions.entry(id).or_insert_with(|| Session {
+                    id: id.clone(),
+                    name: meta.name.clone(),
+                    bin_id: None,
+                });
+                session.bin_id = session.bin_id.or(meta.bin_id);
+            }
+        }
+
+        let mut sessions: Vec<Session> = sessions.into_values().collect();
+        sessions.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+        sessions
+    }
+
+    /// Other sessions that have the bin open.
+    pub fn viewing(&self, bin_id: u64) -> Vec<Session> {
+        self.sessions()
+            .into_iter()
+            .filter(|session| session.bin_id == Some(bin_id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local() -> LocalSession {
+        LocalSession {
+            id: "local".to_string(),
+            name: "laptop".to_string(),
+        }
+    }
+
+    fn state(value: Value) -> PresenceState {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn diff(value: Value) -> PresenceDiff {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn names(presence: &Presence) -> Vec<String> {
+        presence
+            .sessions()
+            .into_iter()
+            .map(|session| session.name)
+            .collect()
+    }
+
+    /// `desktop` on two connections, `phone` on one.
+    fn presence() -> Presence {
+        let mut presence = Presence::new(local());
+        presence.sync_state(
+            Feature::Bin,
+            state(json!({
+                "a": {"metas": [
+                    {"phx_ref": "1", "name": "desktop"},
+                    {"phx_ref": "2", "name": "desktop", "bin_id": 7},
+                ]},
+                "b": {"metas": [{"phx_ref": "3", "name": "phone"}]},
+            })),
+        );
+        presence
+    }
+
+    #[test]
+    fn a_session_stays_until_its_last_meta_leaves() {
+        let mut presence = presence();
+        assert_eq!(names(&presence), ["desktop", "phone"]);
+        assert_eq!(presence.viewing(7)[0].name, "desktop");
+
+        let leave = json!({"leaves": {"a": {"metas": [{"phx_ref": "2"}]}}});
+        presence.sync_diff(Feature::Bin, diff(leave));
+        assert_eq!(names(&presence), ["desktop", "phone"]);
+        assert!(presence.viewing(7).is_empty());
+
+        let leave = json!({"leaves": {"a": {"metas": [{"phx_ref": "1"}]}}});
+        presence.sync_diff(Feature::Bin, diff(leave));
+        assert_eq!(names(&presence), ["phone"]);
+    }
+
+    #[test]
+    fn joins_replace_metas_with_the_same_ref() {
+        let mut presence = presence();
+        let meta = json!({"phx_ref": "3", "name": "phone", "bin_id": 7});
+        let join = json!({"joins": {"b": {"metas": [meta]}}});
+        presence.sync_diff(Feature::Bin, diff(join));
+
+        let viewing: Vec<String> = presence
+            .viewing(7)
+            .into_iter()
+            .map(|session| session.name)
+            .collect();
+        assert_eq!(viewing, ["desktop", "phone"]);
+        assert_eq!(names(&presence), ["desktop", "phone"]);
+    }
+
+    #[test]
+    fn leaves_of_unknown_sessions_or_refs_are_ignored() {
+        let mut presence = presence();
+        let leave = json!({"leaves": {
+            "z": {"metas": [{"phx_ref": "9"}]},
+            "b": {"metas": [{"phx_ref": "9"}]},
+        }});
+        presence.sync_diff(Feature::Bin, diff(leave));
+        assert_eq!(names(&presence), ["desktop", "phone"]);
+
+        // A diff for a topic without a state yet.
+        presence.sync_diff(
+            Feature::Notes,
+            diff(json!({"leaves": {"b": {"metas": []}}})),
+        );
+        assert_eq!(names(&presence), ["desktop", "phone"]);
+    }
+
+    #[test]
+    fn the_local_session_is_not_listed() {
+        let mut presence = presence();
+        let meta = json!({"phx_ref": "4", "name": "laptop", "bin_id": 7});
+        let join = json!({"joins": {"local": {"metas": [meta]}}});
+        presence.sync_diff(Feature::Bin, diff(join));
+
+        assert_eq!(names(&presence), ["desktop", "phone"]);
+        assert!(presence
+            .viewing(7)
+            .iter()
+            .all(|session| session.id != "local"));
+    }
+
+    #[test]
+    fn sessions_on_several_topics_are_listed_once() {
+        let mut presence = presence();
+        let join = json!({"joins": {"b": {"metas": [{"phx_ref": "5", "name": "phone"}]}}});
+        presence.sync_diff(Feature::Todos, diff(join));
+        assert_eq!(names(&presence), ["desktop", "phone"]);
+
+        presence.leave(Feature::Bin);
+        assert_eq!(names(&presence), ["phone"]);
+    }
+}
@@ -0,0 +1,519 @@
+pub mod bin;
+pub mod notes;
+pub mod todos;
+
+use bin::{BinRequest, BinResponse};
+use notes::{NotesRequest, NotesResponse};
+use phoenix_channels_client::{Payload, JSON};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::Display;
+use todos::{TodosRequest, TodosResponse};
+
+/// Feature a message belongs to, sent as the event `name`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Feature {
+    Bin,
+    Notes,
+    Todos,
+}
+
+impl Feature {
+    /// Event name of the feature's messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::Bin => "bin",
+            Feature::Notes => "notes",
+            Feature::Todos => "todos",
+        }
+    }
+
+    /// Channel topic the feature's messages are exchanged on.
+    pub fn topic(&self) -> &'static str {
+        match self {
+            Feature::Bin => "bin",
+            Feature::Notes => "notes",
+            Feature::Todos => "todos",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bin" => Some(Feature::Bin),
+            "notes" => Some(Feature::Notes),
+            "todos" => Some(Feature::Todos),
+            _ => None,
+        }
+    }
+}
+
+/// Per-feature message enum, tagged on `action`.
+pub trait Message: DeserializeOwned {
+    /// Every `action` the enum knows about.
+    const ACTIONS: &'static [&'static str];
+}
+
+/// Message sent from a screen to the socket.
+///
+/// Encoded as `{"name": <feature>, "payload": {"action": ..., ...}}`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "name", content = "payload", rename_all = "lowercase")]
+pub enum Request {
+    Bin(BinRequest),
+    Notes(NotesRequest),
+    Todos(TodosRequest),
+}
+
+impl Request {
+    pub fn feature(&self) -> Feature {
+        match self {
+            Request::Bin(_) => Feature::Bin,
+            Request::Notes(_) => Feature::Notes,
+            Request::Todos(_) => Feature::Todos,
+        }
+    }
+
+    pub fn action(&self) -> &'static str {
+        match self {
+            Request::Bin(request) => request.action(),
+            Request::Notes(request) => request.action(),
+            Request::Todos(request) => request.action(),
+        }
+    }
+
+    /// Whether the request changes data on the server.
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            Request::Bin(request) => request.is_mutating(),
+            Request::Notes(_) | Request::Todos(_) => false,
+        }
+    }
+
+    /// The `payload` part of the request, as sent over the channel.
+    pub fn payload(&self) -> Result<Value, ProtocolError> {
+        let mut value = serde_json::to_value(self)?;
+        Ok(value["payload"].take())
+    }
+
+    /// Rebuild a request from its event name and payload.
+    pub fn decode(name: &str, payload: Value) -> Result<Self, ProtocolError> {
+        let feature = Feature::from_name(name).ok_or_else(|| ProtocolError::UnknownEvent {
+            name: name.to_string(),
+        })?;
+        Ok(match feature {
+            Feature::Bin => Request::Bin(decode_message(feature, payload)?),
+            Feature::Notes => Request::Notes(decode_message(feature, payload)?),
+            Feature::Todos => Request::Todos(decode_message(feature, payload)?),
+        })
+    }
+}
+
+/// Message received from the socket, either a reply to a request or a broadcast.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "name", content = "payload", rename_all = "lowercase")]
+pub enum Response {
+    Bin(BinResponse),
+    Notes(NotesResponse),
+    Todos(TodosResponse),
+}
+
+impl Response {
+    pub fn feature(&self) -> Feature {
+        match self {
+            Response::Bin(_) => Feature::Bin,
+            Response::Notes(_) => Feature::Notes,
+            Response::Todos(_) => Feature::Todos,
+        }
+    }
+
+    /// Status carried by replies to mutating requests.
+    pub fn status(&self) -> Option<&StatusEvent> {
+        match self {
+            Response::Bin(response) => response.status(),
+            Response::Notes(_) | Response::Todos(_) => None,
+        }
+    }
+
+    /// The `payload` part of the response, as sent over the channel.
+    pub fn payload(&self) -> Result<Value, ProtocolError> {
+        let mut value = serde_json::to_value(self)?;
+        Ok(value["payload"].take())
+    }
+
+    /// Decode a message pushed or replied on the feature's topic, e.g.
+    /// `{"action": "get-all", "data": [...]}`.
+    pub fn decode(feature: Feature, payload: Payload) -> Result<Self, ProtocolError> {
+        let payload: Value = match payload {
+            Payload::JSONPayload { json } => JSON::into(json),
+            Payload::Binary { bytes } => return Err(ProtocolError::Binary { len: bytes.len() }),
+        };
+        Self::decode_json(feature, payload)
+    }
+
+    fn decode_json(feature: Feature, payload: Value) -> Result<Self, ProtocolError> {
+        Ok(match feature {
+            Feature::Bin => Response::Bin(decode_message(feature, payload)?),
+            Feature::Notes => Response::Notes(decode_message(feature, payload)?),
+            Feature::Todos => Response::Todos(decode_message(feature, payload)?),
+        })
+    }
+}
+
+fn decode_message<M: Message>(feature: Feature, payload: Value) -> Result<M, ProtocolError> {
+    let action = payload["action"]
+        .as_str()
+        .ok_or(ProtocolError::MissingAction { feature })?;
+    if !M::ACTIONS.contains(&action) {
+        return Err(ProtocolError::UnknownAction {
+            feature,
+            action: action.to_string(),
+        });
+    }
+    Ok(serde_json::from_value(payload)?)
+}
+
+/// Result of a mutating request.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct StatusEvent {
+    pub status: String,
+    pub message: Option<String>,
+}
+
+impl StatusEvent {
+    pub fn is_ok(&self) -> bool {
+        self.status == "OK"
+    }
+
+    /// Message to show when the request was rejected.
+    pub fn error_message(&self) -> String {
+        self.message.clone().unwrap_or_else(|| self.status.clone())
+    }
+}
+
+/// Why a message from the socket could not be decoded.
+#[derive(Debug)]
+pub enum ProtocolError {
+    Binary {
+        len: usize,
+    },
+    MissingAction {
+        feature: Feature,
+    },
+    UnknownEvent {
+        name: String,
+    },
+    UnknownAction {
+        feature: Feature,
+        action: String,
+    },
+    Json(serde_json::Error),
+    /// Diagnostic read back from a recorded session, as it was displayed.
+    Recorded(String),
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::Binary { len } => {
+                write!(f, "expected JSON, received a {len} byte binary payload")
+            }
+            ProtocolError::MissingAction { feature } => {
+                write!(f, "{} event without an action", feature.name())
+            }
+            ProtocolError::UnknownEvent { name } => write!(f, "unknown event '{name}'"),
+            ProtocolError::UnknownAction { feature, action } => {
+                write!(f, "unknown {} action '{action}'", feature.name())
+            }
+            ProtocolError::Json(e) => write!(f, "invalid payload: {e}"),
+            ProtocolError::Recorded(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// Recorded as its message, and read back as `ProtocolError::Recorded`.
+impl serde::Serialize for ProtocolError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ProtocolError {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as serde::Deserialize>::deserialize(deserializer).map(ProtocolError::Recorded)
+    }
+}
+
+impl From<serde_json::Error> for ProtocolError {
+    fn from(value: serde_json::Error) -> Self {
+        ProtocolError::Json(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bin::{
+        Bin, BinId, EditBin, EditFile, Expire, ExpireUnit, File, NewBin, UploadId, UploadStart,
+        UploadStatus, Viewing,
+    };
+    use super::notes::Workspace;
+    use super::todos::Todo;
+    use super::*;
+    use chrono::{Local, TimeZone};
+    use serde_json::json;
+
+    fn ok() -> StatusEvent {
+        StatusEvent {
+            status: "OK".to_string(),
+            message: None,
+        }
+    }
+
+    fn file() -> File {
+        File {
+            id: "f1".to_string(),
+            name: "build.log".to_string(),
+            access_path: "/files/f1".to_string(),
+            type_name: "text/plain".to_string(),
+        }
+    }
+
+    fn bin() -> Bin {
+        Bin {
+            id: 1,
+            title: "Build log".to_string(),
+            content: "ok".to_string(),
+            expire_at: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+            files: vec![file()],
+        }
+    }
+
+    fn expire() -> Expire {
+        Expire {
+            time: 2,
+            unit: ExpireUnit::Hour,
+        }
+    }
+
+    fn upload_status(received: u64) -> UploadStatus {
+        UploadStatus {
+            status: ok(),
+            upload_id: Some("u1".to_string()),
+            received,
+        }
+    }
+
+    fn requests() -> Vec<Request> {
+        vec![
+            Request::Bin(BinRequest::GetAll),
+            Request::Bin(BinRequest::New {
+                data: NewBin {
+                    title: "Build log".to_string(),
+                    content: "ok".to_string(),
+                    expire: expire(),
+                    uploads: vec!["u1".to_string()],
+                },
+            }),
+            Request::Bin(BinRequest::Edit {
+                data: EditBin {
+                    id: 1,
+                    title: "Build log".to_string(),
+                    content: "failed".to_string(),
+                    files: vec![EditFile {
+                        file: file(),
+                        removed: true,
+                    }],
+                    expire: expire(),
+                    uploads: vec![],
+                },
+            }),
+            Request::Bin(BinRequest::Delete {
+                data: BinId { id: 1 },
+            }),
+            Request::Bin(BinRequest::UploadStart {
+                data: UploadStart {
+                    name: "build.log".to_string(),
+                    type_name: "text/plain".to_string(),
+                    size: 70_000,
+                },
+            }),
+            Request::Bin(BinRequest::UploadFinish {
+                data: UploadId {
+                    upload_id: "u1".to_string(),
+                },
+            }),
+            Request::Bin(BinRequest::Viewing {
+                data: Viewing { id: None },
+            }),
+            Request::Notes(NotesRequest::GetWorkspaces),
+            Request::Todos(TodosRequest::GetAll),
+        ]
+    }
+
+    fn responses() -> Vec<Response> {
+        vec![
+            Response::Bin(BinResponse::GetAll { data: vec![bin()] }),
+            Response::Bin(BinResponse::New { data: ok() }),
+            Response::Bin(BinResponse::Edit { data: bin() }),
+            Response::Bin(BinResponse::Delete {
+                data: StatusEvent {
+                    status: "error".to_string(),
+                    message: Some("no such bin".to_string()),
+                },
+            }),
+            Response::Bin(BinResponse::UploadStart {
+                data: upload_status(0),
+            }),
+            Response::Bin(BinResponse::UploadChunk {
+                data: upload_status(65_536),
+            }),
+            Response::Bin(BinResponse::UploadFinish {
+                data: upload_status(70_000),
+            }),
+            Response::Bin(BinResponse::Viewing { data: ok() }),
+            Response::Notes(NotesResponse::GetWorkspaces {
+                data: vec![Workspace {
+                    id: 1,
+                    name: "Home".to_string(),
+                }],
+            }),
+            Response::Todos(TodosResponse::GetAll {
+                data: vec![Todo {
+                    id: 1,
+                    title: "Water the plants".to_string(),
+                    done: false,
+                }],
+            }),
+        ]
+    }
+
+    #[test]
+    fn requests_round_trip() {
+        for request in requests() {
+            let payload = request.payload().unwrap();
+            assert_eq!(payload["action"], request.action());
+            let decoded = Request::decode(request.feature().name(), payload).unwrap();
+            assert_eq!(decoded, request);
+
+            let value = serde_json::to_value(&request).unwrap();
+            assert_eq!(serde_json::from_value::<Request>(value).unwrap(), request);
+        }
+    }
+
+    #[test]
+    fn responses_round_trip() {
+        for response in responses() {
+            let payload = response.payload().unwrap();
+            let decoded = Response::decode_json(response.feature(), payload).unwrap();
+            assert_eq!(decoded, response);
+
+            let value = serde_json::to_value(&response).unwrap();
+            assert_eq!(serde_json::from_value::<Response>(value).unwrap(), response);
+        }
+    }
+
+    /// Sorted `action` tags of the samples of a feature, as serialized.
+    fn tags(feature: Feature, samples: &[(Feature, Value)]) -> Vec<&str> {
+        let mut tags: Vec<&str> = samples
+            .iter()
+            .filter(|(sample_feature, _)| *sample_feature == feature)
+            .map(|(_, payload)| payload["action"].as_str().unwrap())
+            .collect();
+        tags.sort();
+        tags
+    }
+
+    fn sorted(actions: &[&'static str]) -> Vec<&'static str> {
+        let mut actions = actions.to_vec();
+        actions.sort();
+        actions
+    }
+
+    /// The samples hold one message of each variant, so `ACTIONS` lists exactly the tags serde
+    /// writes, each once. `requests_round_trip` checks `action()` against the same tags.
+    #[test]
+    fn actions_are_the_serialized_tags() {
+        let requests: Vec<(Feature, Value)> = requests()
+            .iter()
+            .map(|request| (request.feature(), request.payload().unwrap()))
+            .collect();
+        assert_eq!(tags(Feature::Bin, &requests), sorted(BinRequest::ACTIONS));
+        assert_eq!(
+            tags(Feature::Notes, &requests),
+            sorted(NotesRequest::ACTIONS)
+        );
+        assert_eq!(
+            tags(Feature::Todos, &requests),
+            sorted(TodosRequest::ACTIONS)
+        );
+
+        let responses: Vec<(Feature, Value)> = responses()
+            .iter()
+            .map(|response| (response.feature(), response.payload().unwrap()))
+            .collect();
+        assert_eq!(tags(Feature::Bin, &responses), sorted(BinResponse::ACTIONS));
+        assert_eq!(
+            tags(Feature::Notes, &responses),
+            sorted(NotesResponse::ACTIONS)
+        );
+        assert_eq!(
+            tags(Feature::Todos, &responses),
+            sorted(TodosResponse::ACTIONS)
+        );
+    }
+
+    #[test]
+    fn requests_match_the_documented_events() {
+        let get_all = Request::Bin(BinRequest::GetAll);
+        assert_eq!(get_all.payload().unwrap(), json!({"action": "get-all"}));
+
+        let delete = Request::Bin(BinRequest::Delete {
+            data: BinId { id: 1 },
+        });
+        assert_eq!(
+            serde_json::to_value(&delete).unwrap(),
+            json!({"name": "bin", "payload": {"action": "delete", "data": {"id": 1}}})
+        );
+    }
+
+    #[test]
+    fn upload_status_is_flattened() {
+        let payload = json!({
+            "action": "upload-chunk",
+            "data": {"status": "OK", "upload_id": "u1", "received": 65536},
+        });
+        let response = Response::decode_json(Feature::Bin, payload).unwrap();
+        assert_eq!(
+            response,
+            Response::Bin(BinResponse::UploadChunk {
+                data: upload_status(65_536),
+            })
+        );
+        assert!(response.status().unwrap().is_ok());
+    }
+
+    #[test]
+    fn undecodable_messages_are_refused() {
+        let error = Request::decode("chat", json!({"action": "get-all"})).unwrap_err();
+        assert!(matches!(error, ProtocolError::UnknownEvent { name } if name == "chat"));
+
+        let error = Request::decode("bin", json!({"data": {"id": 1}})).unwrap_err();
+        assert!(matches!(
+            error,
+            ProtocolError::MissingAction {
+                feature: Feature::Bin
+            }
+        ));
+
+        let error = Response::decode_json(Feature::Todos, json!({"action": "delete"})).unwrap_err();
+        assert!(matches!(
+            error,
+            ProtocolError::UnknownAction { feature: Feature::Todos, action } if action == "delete"
+        ));
+
+        let error = Request::decode("bin", json!({"action": "delete"})).unwrap_err();
+        assert!(matches!(error, ProtocolError::Json(_)));
+    }
+}
@@ -0,0 +1,255 @@
+use super::{Message, StatusEvent};
+use chrono::{DateTime, Local};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Bin {
+    pub id: u64,
+    pub title: String,
+    pub content: String,
+    pub expire_at: DateTime<Local>,
+    pub files: Vec<File>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct File {
+    pub id: String,
+    pub name: String,
+    pub access_path: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct NewBin {
+    pub title: String,
+    pub content: String,
+    pub expire: Expire,
+    /// IDs of finished uploads to attach.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uploads: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct EditBin {
+    pub id: u64,
+    pub title: String,
+    pub content: String,
+    pub files: Vec<EditFile>,
+    pub expire: Expire,
+    /// IDs of finished uploads to attach.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uploads: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct EditFile {
+    pub file: File,
+    pub removed: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct BinId {
+    pub id: u64,
+}
+
+/// Bin a session has open, `None` when it has none.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Viewing {
+    pub id: Option<u64>,
+}
+
+/// File about to be uploaded.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct UploadStart {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub size: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct UploadId {
+    pub upload_id: String,
+}
+
+/// Reply to each step of an upload.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct UploadStatus {
+    #[serde(flatten)]
+    pub status: StatusEvent,
+    #[serde(default)]
+    pub upload_id: Option<String>,
+    /// Bytes received so far.
+    #[serde(default)]
+    pub received: u64,
+}
+
+/// Prefix of the events carrying the binary chunks of an upload, followed by the upload ID.
+pub const UPLOAD_CHUNK_EVENT_PREFIX: &str = "bin:upload:";
+
+pub fn upload_chunk_event(upload_id: &str) -> String {
+    format!("{UPLOAD_CHUNK_EVENT_PREFIX}{upload_id}")
+}
+
+/// Expiry relative to now, e.g. `{"time": 2, "unit": "Hour"}`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Expire {
+    pub time: usize,
+    pub unit: ExpireUnit,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub enum ExpireUnit {
+    Day,
+    Minute,
+    Hour,
+}
+
+/// ## Events
+///
+/// Exchanged as `bin` events on the `bin` topic.
+///
+/// ### Get all bins
+/// screen -> socket: `{"action": "get-all"}`
+///
+/// ### New bin
+/// screen -> socket: `{"action": "new", "data": {"title": ..., "content": ..., "expire": ...}}`
+///
+/// ### Edit bin
+/// screen -> socket: `{"action": "edit", "data": {"id": 1, ...}}`
+///
+/// ### Delete bin
+/// screen -> socket: `{"action": "delete", "data": {"id": 1}}`
+///
+/// ### Upload a file
+/// 1. `{"action": "upload-start", "data": {"name": ..., "type": ..., "size": ...}}`
+/// 2. Each chunk as a binary payload on the `bin:upload:<upload_id>` event
+/// 3. `{"action": "upload-finish", "data": {"upload_id": ...}}`
+///
+/// The upload ID is then sent in the `uploads` of a `new` or `edit` event.
+///
+/// ### Open bin
+/// screen -> socket: `{"action": "viewing", "data": {"id": 1}}`
+///
+/// Sets the bin in the session's presence meta, `null` once no bin is open.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum BinRequest {
+    GetAll,
+    New { data: NewBin },
+    Edit { data: EditBin },
+    Delete { data: BinId },
+    UploadStart { data: UploadStart },
+    UploadFinish { data: UploadId },
+    Viewing { data: Viewing },
+}
+
+impl BinRequest {
+    pub fn action(&self) -> &'static str {
+        match self {
+            BinRequest::GetAll => "get-all",
+            BinRequest::New { .. } => "new",
+            BinRequest::Edit { .. } => "edit",
+            BinRequest::Delete { .. } => "delete",
+            BinRequest::UploadStart { .. } => "upload-start",
+            BinRequest::UploadFinish { .. } => "upload-finish",
+            BinRequest::Viewing { .. } => "viewing",
+        }
+    }
+
+    /// Whether the request changes bins. Upload steps aren't, they only make sense while online.
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            BinRequest::New { .. } | BinRequest::Edit { .. } | BinRequest::Delete { .. }
+        )
+    }
+
+    /// Title of the bin the request is about, if it carries one.
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            BinRequest::New { data } => Some(&data.title),
+            BinRequest::Edit { data } => Some(&data.title),
+            BinRequest::GetAll
+            | BinRequest::Delete { .. }
+            | BinRequest::UploadStart { .. }
+            | BinRequest::UploadFinish { .. }
+            | BinRequest::Viewing { .. } => None,
+        }
+    }
+}
+
+impl Message for BinRequest {
+    const ACTIONS: &'static [&'static str] = &[
+        "get-all",
+        "new",
+        "edit",
+        "delete",
+        "upload-start",
+        "upload-finish",
+        "viewing",
+    ];
+}
+
+/// ## Events
+///
+/// Exchanged as `bin` events on the `bin` topic.
+///
+/// ### Get all bins
+/// screen <- socket: `{"action": "get-all", "data": [...]}`
+///
+/// ### New bin
+/// screen <- socket: `{"action": "new", "data": {"status": "OK"}}`
+///
+/// ### Edit bin
+/// screen <- socket: `{"action": "edit", "data": {"id": 1, ...}}`
+///
+/// ### Delete bin
+/// screen <- socket: `{"action": "delete", "data": {"status": "OK"}}`
+///
+/// ### Upload a file
+/// Each step is answered with its action and an `UploadStatus`, e.g.
+/// `{"action": "upload-chunk", "data": {"status": "OK", "upload_id": ..., "received": 65536}}`.
+/// A file that is too large or of a refused type gets a non-`OK` status with a `message`.
+///
+/// ### Open bin
+/// screen <- socket: `{"action": "viewing", "data": {"status": "OK"}}`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum BinResponse {
+    GetAll { data: Vec<Bin> },
+    New { data: StatusEvent },
+    Edit { data: Bin },
+    Delete { data: StatusEvent },
+    UploadStart { data: UploadStatus },
+    UploadChunk { data: UploadStatus },
+    UploadFinish { data: UploadStatus },
+    Viewing { data: StatusEvent },
+}
+
+impl BinResponse {
+    pub fn status(&self) -> Option<&StatusEvent> {
+        match self {
+            BinResponse::New { data }
+            | BinResponse::Delete { data }
+            | BinResponse::Viewing { data } => Some(data),
+            BinResponse::UploadStart { data }
+            | BinResponse::UploadChunk { data }
+            | BinResponse::UploadFinish { data } => Some(&data.status),
+            BinResponse::GetAll { .. } | BinResponse::Edit { .. } => None,
+        }
+    }
+}
+
+impl Message for BinResponse {
+    const ACTIONS: &'static [&'static str] = &[
+        "get-all",
+        "new",
+        "edit",
+        "delete",
+        "upload-start",
+        "upload-chunk",
+        "upload-finish",
+        "viewing",
+    ];
+}
@@ -0,0 +1,47 @@
+use super::Message;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Workspace {
+    pub id: u64,
+    pub name: String,
+}
+
+/// ## Events
+///
+/// Exchanged as `notes` events on the `notes` topic.
+///
+/// ### Get Workspaces
+/// screen -> socket: `{"action": "get-workspaces"}`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum NotesRequest {
+    GetWorkspaces,
+}
+
+impl NotesRequest {
+    pub fn action(&self) -> &'static str {
+        match self {
+            NotesRequest::GetWorkspaces => "get-workspaces",
+        }
+    }
+}
+
+impl Message for NotesRequest {
+    const ACTIONS: &'static [&'static str] = &["get-workspaces"];
+}
+
+/// ## Events
+///
+/// Exchanged as `notes` events on the `notes` topic.
+///
+/// ### Get Workspaces
+/// socket -> screen: `{"action": "get-workspaces", "data": [...]}`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum NotesResponse {
+    GetWorkspaces { data: Vec<Workspace> },
+}
+
+impl Message for NotesResponse {
+    const ACTIONS: &'static [&'static str] = &["get-workspaces"];
+}
@@ -0,0 +1,48 @@
+use super::Message;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Todo {
+    pub id: u64,
+    pub title: String,
+    pub done: bool,
+}
+
+/// ## Events
+///
+/// Exchanged as `todos` events on the `todos` topic.
+///
+/// ### Get all todos
+/// screen -> socket: `{"action": "get-all"}`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum TodosRequest {
+    GetAll,
+}
+
+impl TodosRequest {
+    pub fn action(&self) -> &'static str {
+        match self {
+            TodosRequest::GetAll => "get-all",
+        }
+    }
+}
+
+impl Message for TodosRequest {
+    const ACTIONS: &'static [&'static str] = &["get-all"];
+}
+
+/// ## Events
+///
+/// Exchanged as `todos` events on the `todos` topic.
+///
+/// ### Get all todos
+/// socket -> screen: `{"action": "get-all", "data": [...]}`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum TodosResponse {
+    GetAll { data: Vec<Todo> },
+}
+
+impl Message for TodosResponse {
+    const ACTIONS: &'static [&'static str] = &["get-all"];
+}
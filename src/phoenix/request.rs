@@ -0,0 +1,78 @@
+use phoenix_channels_client::CallError;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Correlates an outgoing event with its reply.
+pub type RequestId = u64;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_request_id() -> RequestId {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Why a request did not get a reply.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub enum RequestError {
+    NotConnected,
+    Timeout(Duration),
+    InvalidPayload(String),
+    InvalidReply(String),
+    Call(String),
+    /// The server replied with an error status, e.g. a file too large or of a type it refuses.
+    Rejected(String),
+}
+
+impl RequestError {
+    /// Error of a channel call made with `timeout`.
+    pub fn from_call(error: CallError, timeout: Duration) -> Self {
+        match error {
+            CallError::Timeout => RequestError::Timeout(timeout),
+            error => RequestError::Call(error.to_string()),
+        }
+    }
+
+    /// Whether the request may not have reached the server, so sending it again can work.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            RequestError::NotConnected | RequestError::Timeout(_) | RequestError::Call(_) => true,
+            RequestError::InvalidPayload(_)
+            | RequestError::InvalidReply(_)
+            | RequestError::Rejected(_) => false,
+        }
+    }
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestError::NotConnected => write!(f, "not connected"),
+            RequestError::Timeout(timeout) => {
+                write!(f, "no reply within {}s", timeout.as_secs())
+            }
+            RequestError::InvalidPayload(e) => write!(f, "invalid payload: {e}"),
+            RequestError::InvalidReply(e) => write!(f, "invalid reply: {e}"),
+            RequestError::Call(e) => write!(f, "{e}"),
+            RequestError::Rejected(message) => write!(f, "rejected: {message}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn call_timeouts_are_reported_as_timeouts() {
+        let error = RequestError::from_call(CallError::Timeout, DEFAULT_TIMEOUT);
+        assert!(matches!(error, RequestError::Timeout(DEFAULT_TIMEOUT)));
+        assert!(error.is_transient());
+
+        let error = RequestError::from_call(CallError::SocketDisconnected, DEFAULT_TIMEOUT);
+        assert!(matches!(error, RequestError::Call(_)));
+        assert!(error.is_transient());
+    }
+}
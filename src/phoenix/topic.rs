@@ -0,0 +1,170 @@
+use super::connection::ConnectionStats;
+use super::event::SocketEvent;
+use super::presence::{Presence, PresenceDiff, PresenceState};
+use super::protocol::{Feature, ProtocolError, Response};
+use cli_log::info;
+use phoenix_channels_client::{Channel, ChannelStatus, Event, Payload, JSON};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// How long a released topic stays joined before it is left.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Sent by `App` as screens are opened and closed, or to reconnect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TopicCommand {
+    /// A screen for the feature was opened: join its topic and keep it joined.
+    Join(Feature),
+    /// No screen for the feature is open anymore: leave its topic once it has been idle.
+    Release(Feature),
+    /// Drop the connection and connect again right away, also after giving up.
+    Reconnect,
+    /// Send the requests queued in the outbox now if connected, rather than after the next
+    /// reconnect.
+    ReplayOutbox,
+}
+
+/// A joined channel and the task forwarding its events to `App`.
+pub struct JoinedTopic {
+    pub channel: Arc<Channel>,
+    feature: Feature,
+    presence_tx: watch::Sender<Presence>,
+    forwarder: JoinHandle<()>,
+}
+
+impl JoinedTopic {
+    /// Start forwarding events pushed on `channel`, and tracking its presence. `lost_tx` is
+    /// notified when the channel stops being joined.
+    pub fn new(
+        feature: Feature,
+        channel: Arc<Channel>,
+        socket_tx: Sender<SocketEvent>,
+        presence_tx: watch::Sender<Presence>,
+        stats_tx: watch::Sender<ConnectionStats>,
+        lost_tx: Sender<Feature>,
+    ) -> Self {
+        let forwarder = tokio::spawn(forward(
+            feature,
+            channel.clone(),
+            socket_tx,
+            presence_tx.clone(),
+            stats_tx,
+            lost_tx,
+        ));
+        Self {
+            channel,
+            feature,
+            presence_tx,
+            forwarder,
+        }
+    }
+
+    pub async fn leave(self) {
+        // Stop forwarding first so leaving isn't reported as a lost channel.
+        self.forwarder.abort();
+        self.presence_tx
+            .send_modify(|presence| presence.leave(self.feature));
+        if let Err(e) = self.channel.leave().await {
+            info!("error leaving channel: {e}");
+        }
+    }
+}
+
+/// Whether a topic is held by an open screen, or when it was released.
+#[derive(Debug, Clone, Copy)]
+pub enum TopicUse {
+    Held,
+    Released(Instant),
+}
+
+impl TopicUse {
+    pub fn is_idle(&self) -> bool {
+        match self {
+            TopicUse::Held => false,
+            TopicUse::Released(at) => at.elapsed() >= IDLE_TIMEOUT,
+        }
+    }
+}
+
+async fn forward(
+    feature: Feature,
+    channel: Arc<Channel>,
+    socket_tx: Sender<SocketEvent>,
+    presence_tx: watch::Sender<Presence>,
+    stats_tx: watch::Sender<ConnectionStats>,
+    lost_tx: Sender<Feature>,
+) {
+    let events = channel.events();
+    let statuses = channel.statuses();
+
+    loop {
+        tokio::select! {
+            event = events.event() => match event {
+                Ok(event) => {
+                    stats_tx.send_modify(ConnectionStats::record_heartbeat);
+                    let event = match event.event {
+                        Event::User { user } if user == feature.name() => {
+                            match Response::decode(feature, event.payload) {
+                                Ok(response) => SocketEvent::Response { request_id: None, response },
+                                Err(e) => SocketEvent::Diagnostic(e),
+                            }
+                        }
+                        Event::User { user } if user == "presence_state" || user == "presence_diff" => {
+                            match sync_presence(feature, &user, event.payload, &presence_tx) {
+                                Ok(()) => continue,
+                                Err(e) => SocketEvent::Diagnostic(e),
+                            }
+                        }
+                        event => SocketEvent::Diagnostic(ProtocolError::UnknownEvent {
+                            name: format!("{event:?}"),
+                        }),
+                    };
+                    let _ = socket_tx.send(event).await;
+                }
+                Err(e) => {
+                    info!("error receiving {} event: {e}", feature.name());
+                    break;
+                }
+            },
+
+            status = statuses.status() => match status {
+                Ok(ChannelStatus::Joined) => {}
+                Ok(status) => {
+                    info!("{} channel status: {status:?}", feature.name());
+                    break;
+                }
+                Err(e) => {
+                    info!("error receiving {} channel status: {e}", feature.name());
+                    break;
+                }
+            }
+        }
+    }
+
+    let _ = lost_tx.send(feature).await;
+}
+
+fn sync_presence(
+    feature: Feature,
+    event: &str,
+    payload: Payload,
+    presence_tx: &watch::Sender<Presence>,
+) -> Result<(), ProtocolError> {
+    let payload: Value = match payload {
+        Payload::JSONPayload { json } => JSON::into(json),
+        Payload::Binary { bytes } => return Err(ProtocolError::Binary { len: bytes.len() }),
+    };
+
+    if event == "presence_state" {
+        let state: PresenceState = serde_json::from_value(payload)?;
+        presence_tx.send_modify(|presence| presence.sync_state(feature, state));
+    } else {
+        let diff: PresenceDiff = serde_json::from_value(payload)?;
+        presence_tx.send_modify(|presence| presence.sync_diff(feature, diff));
+    }
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use super::encode;
+use super::event::SocketEvent;
+use super::protocol::bin::{
+    upload_chunk_event, BinRequest, BinResponse, UploadId, UploadStart, UploadStatus,
+};
+use super::protocol::{Feature, Request, Response};
+use super::request::{next_request_id, RequestError, RequestId, DEFAULT_TIMEOUT};
+use phoenix_channels_client::{Channel, Event, Payload};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc::Sender;
+
+/// Size of the binary payloads a file is split into.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// File to upload to the `bin` topic, sent from a screen to `Phoenix`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct Upload {
+    pub request_id: RequestId,
+    pub path: PathBuf,
+    /// Timeout of each step, not of the whole upload.
+    pub timeout: Duration,
+}
+
+impl Upload {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            request_id: next_request_id(),
+            path,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn failed(&self, error: RequestError) -> SocketEvent {
+        SocketEvent::Failed {
+            request_id: self.request_id,
+            feature: Feature::Bin,
+            error,
+        }
+    }
+}
+
+/// A file read `CHUNK_SIZE` bytes at a time, so large files are never loaded whole.
+pub struct FileChunks {
+    file: File,
+    path: PathBuf,
+    buffer: Vec<u8>,
+    /// Size of the file when it was opened, announced in `upload-start`.
+    pub size: u64,
+}
+
+impl FileChunks {
+    pub async fn open(path: &Path) -> Result<Self, RequestError> {
+        let file = File::open(path).await.map_err(|e| read_error(path, e))?;
+        let metadata = file.metadata().await.map_err(|e| read_error(path, e))?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            buffer: vec![0; CHUNK_SIZE],
+            size: metadata.len(),
+        })
+    }
+
+    /// The next chunk, only shorter than `CHUNK_SIZE` at the end of the file. Empty once the
+    /// whole file was read.
+    pub async fn next(&mut self) -> Result<&[u8], RequestError> {
+        let mut filled = 0;
+        while filled < CHUNK_SIZE {
+            let read = self
+                .file
+                .read(&mut self.buffer[filled..])
+                .await
+                .map_err(|e| read_error(&self.path, e))?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+        Ok(&self.buffer[..filled])
+    }
+}
+
+fn read_error(path: &Path, e: std::io::Error) -> RequestError {
+    RequestError::InvalidPayload(format!("cannot read {}: {e}", path.display()))
+}
+
+/// Upload the file and report progress, then the upload ID or the error, on `socket_tx`.
+pub async fn run(upload: Upload, channel: Arc<Channel>, socket_tx: Sender<SocketEvent>) {
+    let event = match send(&upload, &channel, &socket_tx).await {
+        Ok(upload_id) => SocketEvent::Uploaded {
+            request_id: upload.request_id,
+            upload_id,
+        },
+        Err(error) => upload.failed(error),
+    };
+    let _ = socket_tx.send(event).await;
+}
+
+async fn send(
+    upload: &Upload,
+    channel: &Channel,
+    socket_tx: &Sender<SocketEvent>,
+) -> Result<String, RequestError> {
+    let mut chunks = FileChunks::open(&upload.path).await?;
+    let total = chunks.size;
+
+    let start = UploadStart {
+        name: file_name(&upload.path),
+        type_name: content_type(&upload.path).to_string(),
+        size: total,
+    };
+    let request = Request::Bin(BinRequest::UploadStart { data: start });
+    let payload = encode(&request).map_err(|e| RequestError::InvalidPayload(e.to_string()))?;
+    let status = call(
+        channel,
+        Event::from_string(Feature::Bin.name().to_string()),
+        payload,
+        upload,
+    )
+    .await?;
+    let upload_id = status
+        .upload_id
+        .ok_or_else(|| RequestError::InvalidReply("missing upload_id".to_string()))?;
+
+    let mut sent = 0;
+    loop {
+        let chunk = chunks.next().await?;
+        if chunk.is_empty() {
+            break;
+        }
+        let len = chunk.len() as u64;
+        let event = Event::from_string(upload_chunk_event(&upload_id));
+        let payload = Payload::binary_from_bytes(chunk.to_vec());
+        let status = call(channel, event, payload, upload).await?;
+        sent = status.received.max(sent + len).min(total);
+        let _ = socket_tx
+            .send(SocketEvent::UploadProgress {
+                request_id: upload.request_id,
+                sent,
+                total,
+            })
+            .await;
+    }
+
+    let data = UploadId {
+        upload_id: upload_id.clone(),
+    };
+    let request = Request::Bin(BinRequest::UploadFinish { data });
+    let payload = encode(&request).map_err(|e| RequestError::InvalidPayload(e.to_string()))?;
+    call(
+        channel,
+        Event::from_string(Feature::Bin.name().to_string()),
+        payload,
+        upload,
+    )
+    .await?;
+
+    Ok(upload_id)
+}
+
+/// Send one step of the upload and check the server accepted it.
+async fn call(
+    channel: &Channel,
+    event: Event,
+    payload: Payload,
+    upload: &Upload,
+) -> Result<UploadStatus, RequestError> {
+    let reply = channel
+        .call(event, payload, upload.timeout)
+        .await
+        .map_err(|e| RequestError::from_call(e, upload.timeout))?;
+
+    let response = Response::decode(Feature::Bin, reply)
+        .map_err(|e| RequestError::InvalidReply(e.to_string()))?;
+    upload_status(response)
+}
+
+/// Status of an upload step, or why the server rejected it.
+pub fn upload_status(response: Response) -> Result<UploadStatus, RequestError> {
+    let status = match response {
+        Response::Bin(
+            BinResponse::UploadStart { data }
+            | BinResponse::UploadChunk { data }
+            | BinResponse::UploadFinish { data },
+        ) => data,
+        response => {
+            return Err(RequestError::InvalidReply(format!(
+                "expected an upload reply, got {response:?}"
+            )))
+        }
+    };
+
+    if status.status.is_ok() {
+        Ok(status)
+    } else {
+        Err(RequestError::Rejected(status.status.error_message()))
+    }
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Content type from the file extension. The server decides which types it accepts.
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("pdf") => "application/pdf",
+        Some("json") => "application/json",
+        Some("zip") => "application/zip",
+        Some("txt" | "log") => "text/plain",
+        Some("md") => "text/markdown",
+        Some("html" | "htm") => "text/html",
+        Some("csv") => "text/csv",
+        Some("mp3") => "audio/mpeg",
+        Some("mp4") => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
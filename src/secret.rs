@@ -0,0 +1,448 @@
+pub mod vault;
+
+use std::env;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The socket secret. Kept out of `Debug` output so it doesn't end up in logs.
+#[derive(Clone, PartialEq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: String) -> Self {
+        Self(secret)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Secret(..)")
+    }
+}
+
+/// Where the socket secret is read from, set per profile:
+///
+/// ```toml
+/// secret = { source = "env", var = "MEOWUI_SECRET" }
+/// secret = { source = "file", path = "~/.config/meowui/secret" }
+/// secret = { source = "vault", path = "~/.config/meowui/secret.vault" }
+/// ```
+///
+/// `path` defaults to the files above.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "source", rename_all = "lowercase", deny_unknown_fields)]
+pub enum SecretSource {
+    /// Environment variable. A secret entered in the app is only kept for the session.
+    Env {
+        #[serde(default = "default_secret_var")]
+        var: String,
+    },
+    /// Plain text file that only its owner may read.
+    File {
+        #[serde(default = "default_secret_file", deserialize_with = "expand_home")]
+        path: PathBuf,
+    },
+    /// File encrypted with a passphrase asked for on every start.
+    Vault {
+        #[serde(default = "default_vault_file", deserialize_with = "expand_home")]
+        path: PathBuf,
+    },
+}
+
+impl Default for SecretSource {
+    fn default() -> Self {
+        SecretSource::Env {
+            var: default_secret_var(),
+        }
+    }
+}
+
+fn default_secret_var() -> String {
+    "MEOWUI_SECRET".to_string()
+}
+
+fn default_secret_file() -> PathBuf {
+    config_file("secret")
+}
+
+fn default_vault_file() -> PathBuf {
+    config_file("secret.vault")
+}
+
+fn config_file(name: &str) -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_default()
+        .join("meowui")
+        .join(name)
+}
+
+/// Path from the config, with a leading `~/` replaced by the home directory.
+fn expand_home<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    let path: String = serde::Deserialize::deserialize(deserializer)?;
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => Ok(home.join(rest)),
+        _ => Ok(PathBuf::from(path)),
+    }
+}
+
+/// Why no secret could be read.
+#[derive(Debug)]
+pub enum SecretError {
+    /// Nothing stored yet.
+    Missing {
+        source: String,
+    },
+    /// The vault exists and needs its passphrase.
+    Locked {
+        path: PathBuf,
+    },
+    /// The secret file can be read by other users.
+    Insecure {
+        path: PathBuf,
+        mode: u32,
+    },
+    WrongPassphrase,
+    /// The server refused the secret.
+    Rejected,
+    /// The source can't hold a secret entered in the app.
+    ReadOnly {
+        source: String,
+    },
+    Invalid {
+        path: PathBuf,
+        reason: String,
+    },
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+impl Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretError::Missing { source } => write!(f, "No secret found in {source}."),
+            SecretError::Locked { path } => {
+                write!(f, "Enter the passphrase to unlock {}.", path.display())
+            }
+            SecretError::Insecure { path, mode } => write!(
+                f,
+                "{} can be read by other users (mode {mode:o}). Run `chmod 600 {}` or enter the secret again.",
+                path.display(),
+                path.display()
+            ),
+            SecretError::WrongPassphrase => {
+                write!(f, "Wrong passphrase, or the vault is damaged.")
+            }
+            SecretError::Rejected => write!(f, "The server rejected the secret."),
+            SecretError::ReadOnly { source } => write!(f, "Cannot store a secret in {source}."),
+            SecretError::Invalid { path, reason } => {
+                write!(f, "{} is not a valid vault: {reason}.", path.display())
+            }
+            SecretError::Io { path, error } => write!(f, "{}: {error}.", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+impl SecretSource {
+    /// Read the secret without asking for anything.
+    pub fn read(&self) -> Result<Secret, SecretError> {
+        match self {
+            SecretSource::Env { var } => env::var(var)
+                .ok()
+                .filter(|secret| !secret.is_empty())
+                .map(Secret::new)
+                .ok_or_else(|| self.missing()),
+            SecretSource::File { path } => {
+                check_permissions(path).map_err(|e| self.not_found_as_missing(e))?;
+                let contents = read_file(path)?;
+                let secret = String::from_utf8_lossy(&contents).trim().to_string();
+                if secret.is_empty() {
+                    return Err(self.missing());
+                }
+                Ok(Secret::new(secret))
+            }
+            SecretSource::Vault { path } => match std::fs::metadata(path) {
+                Ok(_) => Err(SecretError::Locked { path: path.clone() }),
+                Err(error) => Err(self.not_found_as_missing(SecretError::Io {
+                    path: path.clone(),
+                    error,
+                })),
+            },
+        }
+    }
+
+    /// Open the vault with its passphrase.
+    pub fn unlock(&self, passphrase: &str) -> Result<Secret, SecretError> {
+        match self {
+            SecretSource::Vault { path } => {
+                let contents = read_file(path)?;
+                vault::open(path, &contents, passphrase)
+            }
+            SecretSource::Env { .. } | SecretSource::File { .. } => self.read(),
+        }
+    }
+
+    /// Store a secret entered in the app. The passphrase is only used by vaults.
+    pub fn store(&self, secret: &Secret, passphrase: &str) -> Result<(), SecretError> {
+        match self {
+            SecretSource::Env { .. } => Err(SecretError::ReadOnly {
+                source: self.to_string(),
+            }),
+            SecretSource::File { path } => write_private(path, secret.expose().as_bytes()),
+            SecretSource::Vault { path } => {
+                let sealed = vault::seal(secret, passphrase)?;
+                write_private(path, sealed.as_bytes())
+            }
+        }
+    }
+
+    /// Whether storing a secret needs a passphrase.
+    pub fn needs_passphrase(&self) -> bool {
+        matches!(self, SecretSource::Vault { .. })
+    }
+
+    fn missing(&self) -> SecretError {
+        SecretError::Missing {
+            source: self.to_string(),
+        }
+    }
+
+    fn not_found_as_missing(&self, error: SecretError) -> SecretError {
+        match error {
+            SecretError::Io { error, .. } if error.kind() == std::io::ErrorKind::NotFound => {
+                self.missing()
+            }
+            error => error,
+        }
+    }
+}
+
+impl Display for SecretSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretSource::Env { var } => write!(f, "${var}"),
+            SecretSource::File { path } => write!(f, "{}", path.display()),
+            SecretSource::Vault { path } => write!(f, "the vault at {}", path.display()),
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, SecretError> {
+    std::fs::read(path).map_err(|error| SecretError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+#[cfg(unix)]
+fn check_permissions(path: &Path) -> Result<(), SecretError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = std::fs::metadata(path).map_err(|error| SecretError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        return Err(SecretError::Insecure {
+            path: path.to_path_buf(),
+            mode,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path) -> Result<(), SecretError> {
+    Ok(())
+}
+
+/// Write a file only the owner can read, creating its directory.
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), SecretError> {
+    let io_error = |error| SecretError::Io {
+        path: path.to_path_buf(),
+        error,
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies to new files.
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+                .map_err(io_error)?;
+        }
+    }
+    let mut file = options.open(path).map_err(io_error)?;
+    file.write_all(contents).map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path in a directory of its own, removed again when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!("meowui-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+
+        fn file(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn secret() -> Secret {
+        Secret::new("s3cret".to_string())
+    }
+
+    #[cfg(unix)]
+    fn mode(path: &Path) -> u32 {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[cfg(unix)]
+    fn set_mode(path: &Path, mode: u32) {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn the_secret_is_kept_out_of_debug_output() {
+        assert_eq!(format!("{:?}", secret()), "Secret(..)");
+    }
+
+    #[test]
+    fn sources_are_read_from_the_config() {
+        #[derive(serde::Deserialize)]
+        struct Profile {
+            secret: SecretSource,
+        }
+        let source = |toml: &str| toml::from_str::<Profile>(toml).map(|profile| profile.secret);
+
+        let env = source(r#"secret = { source = "env" }"#).unwrap();
+        assert_eq!(env, SecretSource::default());
+        let file = source(r#"secret = { source = "file", path = "/etc/meowui/secret" }"#);
+        assert_eq!(
+            file.unwrap(),
+            SecretSource::File {
+                path: PathBuf::from("/etc/meowui/secret")
+            }
+        );
+        if let Some(home) = dirs::home_dir() {
+            let vault = source(r#"secret = { source = "vault", path = "~/secret.vault" }"#);
+            assert_eq!(
+                vault.unwrap(),
+                SecretSource::Vault {
+                    path: home.join("secret.vault")
+                }
+            );
+        }
+        assert!(source(r#"secret = { source = "keyring" }"#).is_err());
+        assert!(source(r#"secret = { source = "env", path = "secret" }"#).is_err());
+    }
+
+    #[test]
+    fn a_missing_file_is_reported_as_missing() {
+        let dir = TempDir::new("missing-secret");
+        let source = SecretSource::File {
+            path: dir.file("secret"),
+        };
+        assert!(matches!(source.read(), Err(SecretError::Missing { .. })));
+        let source = SecretSource::Vault {
+            path: dir.file("secret.vault"),
+        };
+        assert!(matches!(source.read(), Err(SecretError::Missing { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stored_files_are_private() {
+        let dir = TempDir::new("file-secret");
+        let path = dir.file("config").join("secret");
+        let source = SecretSource::File { path: path.clone() };
+
+        source.store(&secret(), "").unwrap();
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(source.read().unwrap(), secret());
+
+        // Storing again also fixes the mode of an existing file.
+        set_mode(&path, 0o644);
+        source.store(&secret(), "").unwrap();
+        assert_eq!(mode(&path), 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_readable_by_others_are_refused() {
+        let dir = TempDir::new("insecure-secret");
+        let path = dir.file("secret");
+        let source = SecretSource::File { path: path.clone() };
+        source.store(&secret(), "").unwrap();
+
+        for insecure in [0o640, 0o604, 0o660, 0o601, 0o610] {
+            set_mode(&path, insecure);
+            match source.read() {
+                Err(SecretError::Insecure { mode, .. }) => assert_eq!(mode, insecure),
+                other => panic!("expected {insecure:o} to be refused, got {other:?}"),
+            }
+        }
+        set_mode(&path, 0o400);
+        assert_eq!(source.read().unwrap(), secret());
+    }
+
+    #[test]
+    fn vaults_are_locked_until_unlocked() {
+        let dir = TempDir::new("vault-secret");
+        let source = SecretSource::Vault {
+            path: dir.file("secret.vault"),
+        };
+        assert!(source.needs_passphrase());
+        source.store(&secret(), "correct horse").unwrap();
+
+        assert!(matches!(source.read(), Err(SecretError::Locked { .. })));
+        assert!(matches!(
+            source.unlock("battery staple"),
+            Err(SecretError::WrongPassphrase)
+        ));
+        assert_eq!(source.unlock("correct horse").unwrap(), secret());
+    }
+
+    #[test]
+    fn environment_sources_cannot_store() {
+        let source = SecretSource::Env {
+            var: "MEOWUI_TEST_UNSET_SECRET".to_string(),
+        };
+        assert!(!source.needs_passphrase());
+        assert!(matches!(source.read(), Err(SecretError::Missing { .. })));
+        assert!(matches!(
+            source.store(&secret(), ""),
+            Err(SecretError::ReadOnly { .. })
+        ));
+    }
+}
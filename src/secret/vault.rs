@@ -0,0 +1,176 @@
+use super::{Secret, SecretError};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::path::Path;
+
+const VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+/// Vault file contents. The key is derived from the passphrase with Argon2id and the secret is
+/// sealed with ChaCha20-Poly1305, so a wrong passphrase fails to decrypt instead of yielding
+/// garbage.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Vault {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Encrypt the secret, returning the vault file contents.
+pub fn seal(secret: &Secret, passphrase: &str) -> Result<String, SecretError> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = cipher(passphrase, &salt)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, secret.expose().as_bytes())
+        .map_err(|_| SecretError::WrongPassphrase)?;
+
+    let vault = Vault {
+        version: VERSION,
+        salt: to_hex(&salt),
+        nonce: to_hex(&nonce),
+        ciphertext: to_hex(&ciphertext),
+    };
+    Ok(serde_json::to_string_pretty(&vault).expect("vault serializes"))
+}
+
+/// Decrypt the vault file contents read from `path`.
+pub fn open(path: &Path, contents: &[u8], passphrase: &str) -> Result<Secret, SecretError> {
+    let invalid = |reason: &str| SecretError::Invalid {
+        path: path.to_path_buf(),
+        reason: reason.to_string(),
+    };
+
+    let vault: Vault = serde_json::from_slice(contents).map_err(|e| invalid(&e.to_string()))?;
+    if vault.version != VERSION {
+        return Err(invalid(&format!("unsupported version {}", vault.version)));
+    }
+    let salt = from_hex(&vault.salt).ok_or_else(|| invalid("bad salt"))?;
+    let nonce = from_hex(&vault.nonce)
+        .filter(|nonce| nonce.len() == 12)
+        .ok_or_else(|| invalid("bad nonce"))?;
+    let ciphertext = from_hex(&vault.ciphertext).ok_or_else(|| invalid("bad ciphertext"))?;
+
+    let plaintext = cipher(passphrase, &salt)?
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| SecretError::WrongPassphrase)?;
+    let secret = String::from_utf8(plaintext).map_err(|_| invalid("secret is not UTF-8"))?;
+    Ok(Secret::new(secret))
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, SecretError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|_| SecretError::WrongPassphrase)?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    // `from_str_radix` alone would also take a sign, like `+f`.
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path() -> &'static Path {
+        Path::new("secret.vault")
+    }
+
+    fn sealed() -> String {
+        seal(&Secret::new("s3cret".to_string()), "correct horse").unwrap()
+    }
+
+    fn reason(result: Result<Secret, SecretError>) -> String {
+        match result {
+            Err(SecretError::Invalid { reason, .. }) => reason,
+            other => panic!("expected an invalid vault, got {other:?}"),
+        }
+    }
+
+    /// The vault with one of its fields replaced.
+    fn with_field(contents: &str, field: &str, value: serde_json::Value) -> Vec<u8> {
+        let mut vault: serde_json::Value = serde_json::from_str(contents).unwrap();
+        vault[field] = value;
+        serde_json::to_vec(&vault).unwrap()
+    }
+
+    #[test]
+    fn sealed_secrets_can_be_opened() {
+        let contents = sealed();
+        let secret = open(path(), contents.as_bytes(), "correct horse").unwrap();
+        assert_eq!(secret.expose(), "s3cret");
+        // Every vault gets its own salt and nonce.
+        assert_ne!(contents, sealed());
+    }
+
+    #[test]
+    fn a_wrong_passphrase_is_refused() {
+        let result = open(path(), sealed().as_bytes(), "battery staple");
+        assert!(matches!(result, Err(SecretError::WrongPassphrase)));
+    }
+
+    #[test]
+    fn a_truncated_vault_is_invalid() {
+        let contents = sealed();
+        let truncated = &contents.as_bytes()[..contents.len() / 2];
+        assert!(reason(open(path(), truncated, "correct horse")).contains("EOF"));
+        assert!(!reason(open(path(), b"", "correct horse")).is_empty());
+    }
+
+    #[test]
+    fn a_corrupted_ciphertext_is_refused() {
+        let contents = sealed();
+        let vault: Vault = serde_json::from_str(&contents).unwrap();
+        let mut ciphertext = from_hex(&vault.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        let corrupted = with_field(&contents, "ciphertext", to_hex(&ciphertext).into());
+        let result = open(path(), &corrupted, "correct horse");
+        assert!(matches!(result, Err(SecretError::WrongPassphrase)));
+    }
+
+    #[test]
+    fn malformed_fields_are_invalid() {
+        let contents = sealed();
+        let open_with = |field, value: serde_json::Value| {
+            let contents = with_field(&contents, field, value);
+            reason(open(path(), &contents, "correct horse"))
+        };
+        assert_eq!(open_with("salt", "xyz0".into()), "bad salt");
+        assert_eq!(open_with("nonce", "00ff".into()), "bad nonce");
+        assert_eq!(open_with("ciphertext", "abc".into()), "bad ciphertext");
+        assert_eq!(open_with("version", 2.into()), "unsupported version 2");
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let bytes = [0x00, 0x7f, 0x80, 0xff];
+        assert_eq!(to_hex(&bytes), "007f80ff");
+        assert_eq!(from_hex("007f80ff").unwrap(), bytes);
+        assert_eq!(from_hex("007F80FF").unwrap(), bytes);
+        assert_eq!(from_hex("").unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn odd_or_non_hex_input_is_refused() {
+        for invalid in ["0", "abc", "zz", "0g", "+f", "-1", " 1", "éé"] {
+            assert_eq!(from_hex(invalid), None, "{invalid:?}");
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use cli_log::info;
+pub mod outbox;
+
 use rusqlite::{Connection, Error, Params, Result};
 
 #[derive(Debug)]
@@ -10,7 +11,17 @@ const DB: &str = "./meowui.db";
 
 impl Sqlite {
     pub fn new() -> Self {
-        let conn = Connection::open(DB.to_string()).unwrap();
+        Self::open().unwrap()
+    }
+
+    pub fn open() -> Result<Self> {
+        let conn = Connection::open(DB.to_string())?;
+        Ok(Self { connection: conn })
+    }
+
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        let conn = Connection::open_in_memory().unwrap();
         Self { connection: conn }
     }
 
@@ -36,4 +47,13 @@ impl Sqlite {
             },
         }
     }
+
+    pub fn check_column_exists(&self, table_name: &str, column_name: &str) -> bool {
+        let result: Result<i64> = self.connection.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name=?2;",
+            [table_name, column_name],
+            |row| row.get(0),
+        );
+        matches!(result, Ok(count) if count > 0)
+    }
 }
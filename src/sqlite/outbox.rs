@@ -0,0 +1,358 @@
+use super::Sqlite;
+use crate::phoenix::protocol::{ProtocolError, Request};
+use cli_log::info;
+use rusqlite::Result;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Screen events that could not be sent because the socket was down. They are replayed in order
+/// by `Phoenix` once it reconnects.
+pub struct Outbox {
+    db: Sqlite,
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxItem {
+    pub id: i64,
+    /// `None` when the stored request can't be read back, e.g. one queued by another version.
+    /// Such items are failed, with the reason as their error, and can only be discarded.
+    pub request: Option<Request>,
+    pub status: OutboxStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutboxStatus {
+    Queued,
+    Sending,
+    Failed,
+}
+
+impl OutboxStatus {
+    fn as_str(&self) -> &str {
+        match self {
+            OutboxStatus::Queued => "queued",
+            OutboxStatus::Sending => "sending",
+            OutboxStatus::Failed => "failed",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "sending" => OutboxStatus::Sending,
+            "failed" => OutboxStatus::Failed,
+            _ => OutboxStatus::Queued,
+        }
+    }
+}
+
+impl Display for OutboxStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutboxStatus::Queued => write!(f, "Queued"),
+            OutboxStatus::Sending => write!(f, "Sending"),
+            OutboxStatus::Failed => write!(f, "Failed"),
+        }
+    }
+}
+
+impl OutboxItem {
+    pub fn title(&self) -> &str {
+        match &self.request {
+            Some(Request::Bin(request)) => request.title().unwrap_or_default(),
+            Some(Request::Notes(_) | Request::Todos(_)) | None => "",
+        }
+    }
+
+    /// Action and title of the request, as listed in the outbox.
+    pub fn summary(&self) -> String {
+        match &self.request {
+            Some(request) => format!("{} {}", request.action(), self.title()),
+            None => "unreadable request".to_string(),
+        }
+    }
+}
+
+impl Outbox {
+    /// The outbox of `meowui.db`, shared by the app and `Phoenix`.
+    pub fn new() -> Result<Self> {
+        Self::open(Sqlite::open()?)
+    }
+
+    pub fn open(sqlite: Sqlite) -> Result<Self> {
+        // Both the UI and the phoenix task write to the outbox.
+        sqlite.connection.busy_timeout(Duration::from_secs(5))?;
+
+        if !sqlite.check_table_exists("outbox") {
+            Self::create_outbox_table(&sqlite)?;
+        }
+
+        Ok(Self { db: sqlite })
+    }
+
+    /// Whether the request should be kept in the outbox when it can't be sent.
+    pub fn accepts(request: &Request) -> bool {
+        request.is_mutating()
+    }
+
+    pub fn enqueue(&self, request: &Request) -> Result<i64> {
+        let name = request.feature().name();
+        let payload = request
+            .payload()
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.db.execute_query_with_params(
+            "INSERT INTO outbox (name, payload, status) VALUES (?1, ?2, ?3)",
+            (name, payload.to_string(), OutboxStatus::Queued.as_str()),
+        )?;
+        Ok(self.db.connection.last_insert_rowid())
+    }
+
+    /// All items, oldest first.
+    pub fn items(&self) -> Result<Vec<OutboxItem>> {
+        let mut stmt = self
+            .db
+            .connection
+            .prepare("SELECT id, name, payload, status, error FROM outbox ORDER BY id")?;
+
+        let rows = stmt.query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            let name: String = row.get(1)?;
+            let payload: String = row.get(2)?;
+            let status: String = row.get(3)?;
+            let error: Option<String> = row.get(4)?;
+            Ok((id, name, payload, status, error))
+        })?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            let (id, name, payload, status, error) = row?;
+            let decoded = serde_json::from_str(&payload)
+                .map_err(ProtocolError::from)
+                .and_then(|payload| Request::decode(&name, payload));
+            items.push(match decoded {
+                Ok(request) => OutboxItem {
+                    id,
+                    request: Some(request),
+                    status: OutboxStatus::from_str(&status),
+                    error,
+                },
+                Err(e) => {
+                    info!("cannot read outbox item {id}: {e}");
+                    OutboxItem {
+                        id,
+                        request: None,
+                        status: OutboxStatus::Failed,
+                        error: Some(format!("cannot read the request: {e}")),
+                    }
+                }
+            });
+        }
+        Ok(items)
+    }
+
+    /// Requests waiting to be replayed with their item ID, oldest first.
+    pub fn queued(&self) -> Result<Vec<(i64, Request)>> {
+        let items = self.items()?;
+        Ok(items
+            .into_iter()
+            .filter(|item| item.status == OutboxStatus::Queued)
+            .filter_map(|item| Some((item.id, item.request?)))
+            .collect())
+    }
+
+    /// Queue again the items a replay was sending when it was interrupted, e.g. by a crash, so
+    /// they are sent on the next replay. Returns how many there were.
+    pub fn requeue_interrupted(&self) -> Result<usize> {
+        self.db.execute_query_with_params(
+            "UPDATE outbox SET status = ?1 WHERE status = ?2",
+            (
+                OutboxStatus::Queued.as_str(),
+                OutboxStatus::Sending.as_str(),
+            ),
+        )
+    }
+
+    pub fn set_status(&self, id: i64, status: OutboxStatus, error: Option<String>) -> Result<()> {
+        self.db.execute_query_with_params(
+            "UPDATE outbox SET status = ?1, error = ?2 WHERE id = ?3",
+            (status.as_str(), error, id),
+        )?;
+        Ok(())
+    }
+
+    pub fn remove(&self, id: i64) -> Result<()> {
+        self.db
+            .execute_query_with_params("DELETE FROM outbox WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    fn create_outbox_table(db: &Sqlite) -> Result<()> {
+        db.execute_query(
+            "CREATE TABLE outbox (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                status TEXT NOT NULL,
+                error TEXT
+            )",
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phoenix::protocol::bin::{BinId, BinRequest};
+
+    fn outbox() -> Outbox {
+        Outbox::open(Sqlite::in_memory()).unwrap()
+    }
+
+    fn delete(id: u64) -> Request {
+        Request::Bin(BinRequest::Delete { data: BinId { id } })
+    }
+
+    fn statuses(outbox: &Outbox) -> Vec<(Request, OutboxStatus)> {
+        outbox
+            .items()
+            .unwrap()
+            .into_iter()
+            .map(|item| (item.request.unwrap(), item.status))
+            .collect()
+    }
+
+    #[test]
+    fn items_are_kept_in_the_order_they_were_queued() {
+        let outbox = outbox();
+        let first = outbox.enqueue(&delete(1)).unwrap();
+        let second = outbox.enqueue(&delete(2)).unwrap();
+        outbox.enqueue(&delete(3)).unwrap();
+        assert!(first < second);
+
+        assert_eq!(
+            statuses(&outbox),
+            [
+                (delete(1), OutboxStatus::Queued),
+                (delete(2), OutboxStatus::Queued),
+                (delete(3), OutboxStatus::Queued),
+            ]
+        );
+        let queued: Vec<i64> = outbox.queued().unwrap().iter().map(|(id, _)| *id).collect();
+        assert_eq!(queued[..2], [first, second]);
+    }
+
+    #[test]
+    fn only_queued_items_are_replayed() {
+        let outbox = outbox();
+        let failed = outbox.enqueue(&delete(1)).unwrap();
+        let sending = outbox.enqueue(&delete(2)).unwrap();
+        let queued = outbox.enqueue(&delete(3)).unwrap();
+        outbox
+            .set_status(
+                failed,
+                OutboxStatus::Failed,
+                Some("no such bin".to_string()),
+            )
+            .unwrap();
+        outbox
+            .set_status(sending, OutboxStatus::Sending, None)
+            .unwrap();
+
+        let replayed: Vec<i64> = outbox.queued().unwrap().iter().map(|(id, _)| *id).collect();
+        assert_eq!(replayed, [queued]);
+        let items = outbox.items().unwrap();
+        assert_eq!(items[0].error.as_deref(), Some("no such bin"));
+
+        // Retried from the list.
+        outbox
+            .set_status(failed, OutboxStatus::Queued, None)
+            .unwrap();
+        let replayed: Vec<i64> = outbox.queued().unwrap().iter().map(|(id, _)| *id).collect();
+        assert_eq!(replayed, [failed, queued]);
+        assert_eq!(outbox.items().unwrap()[0].error, None);
+    }
+
+    #[test]
+    fn interrupted_items_are_queued_again() {
+        let outbox = outbox();
+        let sending = outbox.enqueue(&delete(1)).unwrap();
+        let failed = outbox.enqueue(&delete(2)).unwrap();
+        outbox
+            .set_status(sending, OutboxStatus::Sending, None)
+            .unwrap();
+        outbox
+            .set_status(failed, OutboxStatus::Failed, None)
+            .unwrap();
+
+        assert_eq!(outbox.requeue_interrupted().unwrap(), 1);
+        assert_eq!(
+            statuses(&outbox),
+            [
+                (delete(1), OutboxStatus::Queued),
+                (delete(2), OutboxStatus::Failed),
+            ]
+        );
+    }
+
+    #[test]
+    fn discarded_items_are_removed() {
+        let outbox = outbox();
+        let first = outbox.enqueue(&delete(1)).unwrap();
+        outbox.enqueue(&delete(2)).unwrap();
+
+        outbox.remove(first).unwrap();
+        assert_eq!(statuses(&outbox), [(delete(2), OutboxStatus::Queued)]);
+    }
+
+    #[test]
+    fn requests_that_are_not_mutating_are_not_accepted() {
+        assert!(Outbox::accepts(&delete(1)));
+        assert!(!Outbox::accepts(&Request::Bin(BinRequest::GetAll)));
+    }
+
+    #[test]
+    fn reopening_keeps_the_table() {
+        let outbox = outbox();
+        outbox.enqueue(&delete(1)).unwrap();
+        let outbox = Outbox::open(outbox.db).unwrap();
+        assert_eq!(outbox.items().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn unreadable_items_can_be_discarded() {
+        let outbox = outbox();
+        outbox.enqueue(&delete(1)).unwrap();
+        outbox
+            .db
+            .execute_query(
+                "INSERT INTO outbox (name, payload, status) VALUES ('bin', 'not json', 'queued')",
+            )
+            .unwrap();
+        let unknown = outbox
+            .db
+            .execute_query_with_params(
+                "INSERT INTO outbox (name, payload, status) VALUES (?1, ?2, ?3)",
+                ("chat", r#"{"action": "get-all"}"#, "queued"),
+            )
+            .map(|_| outbox.db.connection.last_insert_rowid())
+            .unwrap();
+
+        let items = outbox.items().unwrap();
+        assert_eq!(items.len(), 3);
+        for item in &items[1..] {
+            assert_eq!(item.request, None);
+            assert_eq!(item.status, OutboxStatus::Failed);
+            assert!(item
+                .error
+                .as_ref()
+                .unwrap()
+                .starts_with("cannot read the request"));
+            assert_eq!(item.summary(), "unreadable request");
+        }
+        assert_eq!(outbox.queued().unwrap(), [(items[0].id, delete(1))]);
+
+        outbox.remove(unknown).unwrap();
+        assert_eq!(outbox.items().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::anyhow;
+use ratatui::style::{Color, Style, Stylize};
+use std::env;
+use std::str::FromStr;
+
+/// Colors every screen and widget draws with, picked in the `[theme]` table of the config file,
+/// e.g.
+///
+/// ```toml
+/// [theme]
+/// preset = "light"
+/// focused_border = "magenta"
+/// error = "#c00000"
+/// ```
+///
+/// Colors are names like `red` or `lightblue`, `#rrggbb` or a 256-color index. `NO_COLOR` turns
+/// every color off, whatever the config says.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    /// Borders of blocks, and of fields that aren't focused.
+    pub border: Style,
+    pub focused_border: Style,
+    pub title: Style,
+    /// Content of lists, tables and fields.
+    pub text: Style,
+    /// Selected row of a list.
+    pub highlight: Style,
+    /// Help lines, hints and details.
+    pub muted: Style,
+    /// Secondary information, like expiry dates and file types.
+    pub accent: Style,
+    pub warning: Style,
+    pub error: Style,
+    pub success: Style,
+    /// Cursor of text fields.
+    pub cursor: Style,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+/// The `[theme]` table: a preset and the colors replacing its own.
+#[derive(serde::Deserialize, Clone, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub preset: Preset,
+    pub border: Option<ThemeColor>,
+    pub focused_border: Option<ThemeColor>,
+    pub title: Option<ThemeColor>,
+    pub text: Option<ThemeColor>,
+    pub highlight: Option<ThemeColor>,
+    pub muted: Option<ThemeColor>,
+    pub accent: Option<ThemeColor>,
+    pub warning: Option<ThemeColor>,
+    pub error: Option<ThemeColor>,
+    pub success: Option<ThemeColor>,
+    pub cursor: Option<ThemeColor>,
+}
+
+/// A color as written in the config file.
+#[derive(serde::Deserialize, Clone, Copy, Debug)]
+#[serde(try_from = "String")]
+pub struct ThemeColor(Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = anyhow::Error;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Color::from_str(&name)
+            .map(ThemeColor)
+            .map_err(|_| anyhow!("unknown color '{name}'"))
+    }
+}
+
+impl Theme {
+    /// The configured theme, or no colors at all when `NO_COLOR` is set.
+    pub fn new(config: &ThemeConfig) -> Self {
+        if env::var("NO_COLOR").is_ok_and(|value| !value.is_empty()) {
+            return Self::no_color();
+        }
+
+        let mut theme = Self::preset(config.preset);
+        // Highlights and the cursor are backgrounds, everything else is text.
+        let overrides = [
+            (&mut theme.border, config.border, false),
+            (&mut theme.focused_border, config.focused_border, false),
+            (&mut theme.title, config.title, false),
+            (&mut theme.text, config.text, false),
+            (&mut theme.highlight, config.highlight, true),
+            (&mut theme.muted, config.muted, false),
+            (&mut theme.accent, config.accent, false),
+            (&mut theme.warning, config.warning, false),
+            (&mut theme.error, config.error, false),
+            (&mut theme.success, config.success, false),
+            (&mut theme.cursor, config.cursor, true),
+        ];
+        for (style, color, background) in overrides {
+            match color {
+                Some(ThemeColor(color)) if background => *style = style.bg(color),
+                Some(ThemeColor(color)) => *style = style.fg(color),
+                None => {}
+            }
+        }
+        theme
+    }
+
+    pub fn preset(preset: Preset) -> Self {
+        match preset {
+            Preset::Dark => Self::dark(),
+            Preset::Light => Self::light(),
+            Preset::HighContrast => Self::high_contrast(),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            border: Style::new().fg(Color::Green),
+            focused_border: Style::new().fg(Color::Blue),
+            title: Style::new().fg(Color::Green),
+            text: Style::new().fg(Color::Green),
+            highlight: Style::new().reversed(),
+            muted: Style::new().fg(Color::Gray),
+            accent: Style::new().fg(Color::Blue),
+            warning: Style::new().fg(Color::Yellow),
+            error: Style::new().fg(Color::Red),
+            success: Style::new().fg(Color::Green),
+            cursor: Style::new().fg(Color::Black).bg(Color::White),
+        }
+    }
+
+    /// Dark colors on the terminal's own light background.
+    pub fn light() -> Self {
+        Self {
+            border: Style::new().fg(Color::DarkGray),
+            focused_border: Style::new().fg(Color::Blue),
+            title: Style::new().fg(Color::Blue).bold(),
+            text: Style::new().fg(Color::Reset),
+            highlight: Style::new().fg(Color::Black).bg(Color::Indexed(153)),
+            muted: Style::new().fg(Color::DarkGray),
+            accent: Style::new().fg(Color::Magenta),
+            warning: Style::new().fg(Color::Indexed(130)),
+            error: Style::new().fg(Color::Red),
+            success: Style::new().fg(Color::Indexed(28)),
+            cursor: Style::new().fg(Color::White).bg(Color::Black),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            border: Style::new().fg(Color::White),
+            focused_border: Style::new().fg(Color::Yellow).bold(),
+            title: Style::new().fg(Color::White).bold(),
+            text: Style::new().fg(Color::White),
+            highlight: Style::new().fg(Color::Black).bg(Color::Yellow).bold(),
+            muted: Style::new().fg(Color::White),
+            accent: Style::new().fg(Color::LightCyan),
+            warning: Style::new().fg(Color::LightYellow).bold(),
+            error: Style::new().fg(Color::LightRed).bold(),
+            success: Style::new().fg(Color::LightGreen),
+            cursor: Style::new().fg(Color::Black).bg(Color::Yellow),
+        }
+    }
+
+    /// Only bold, italic and reversed text, for `NO_COLOR`.
+    pub fn no_color() -> Self {
+        Self {
+            border: Style::new(),
+            focused_border: Style::new().bold(),
+            title: Style::new().bold(),
+            text: Style::new(),
+            highlight: Style::new().reversed(),
+            muted: Style::new(),
+            accent: Style::new().italic(),
+            warning: Style::new().bold(),
+            error: Style::new().bold(),
+            success: Style::new(),
+            cursor: Style::new().reversed(),
+        }
+    }
+
+    /// Border of a field, or of a pane that can take the focus.
+    pub fn border_style(&self, focused: bool) -> Style {
+        if focused {
+            self.focused_border
+        } else {
+            self.border
+        }
+    }
+}
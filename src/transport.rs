@@ -0,0 +1,37 @@
+pub mod memory;
+pub mod record;
+pub mod replay;
+pub mod stub_server;
+
+use crate::phoenix::connection::{ConnectionState, ConnectionStats};
+use crate::phoenix::event::{PhoenixEvent, SocketEvent};
+use crate::phoenix::presence::Presence;
+use crate::phoenix::topic::TopicCommand;
+use crate::phoenix::upload::Upload;
+use std::future::Future;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::watch;
+
+/// Transport side of the channels connecting it to `App`.
+pub struct TransportChannels {
+    pub socket_tx: Sender<SocketEvent>,
+    pub screen_rx: Receiver<PhoenixEvent>,
+    pub topic_rx: Receiver<TopicCommand>,
+    pub upload_rx: Receiver<Upload>,
+    pub state_tx: watch::Sender<ConnectionState>,
+    pub stats_tx: watch::Sender<ConnectionStats>,
+    pub presence_tx: watch::Sender<Presence>,
+}
+
+/// Carries requests from screens to a backend, and its replies and pushes back to `App`.
+///
+/// `Phoenix` talks to the real server. `MemoryTransport` answers from memory, and `StubServer`
+/// is a local server `Phoenix` can connect to, so the app runs without network.
+/// `ReplayTransport` plays back a session captured with `record::record`.
+pub trait Transport: Send {
+    /// Serve requests until the transport gives up.
+    fn run(&mut self) -> impl Future<Output = ()> + Send;
+
+    /// Close the connection before the app exits.
+    fn disassemble(&mut self) -> impl Future<Output = ()> + Send;
+}
@@ -0,0 +1,650 @@
+use super::{Transport, TransportChannels};
+use crate::phoenix::connection::{ConnectionState, ConnectionStats};
+use crate::phoenix::event::{PhoenixEvent, SocketEvent};
+use crate::phoenix::protocol::bin::{
+    Bin, BinId, BinRequest, BinResponse, EditBin, Expire, ExpireUnit, File, NewBin, UploadId,
+    UploadStart, UploadStatus,
+};
+use crate::phoenix::protocol::notes::{NotesRequest, NotesResponse};
+use crate::phoenix::protocol::todos::{TodosRequest, TodosResponse};
+use crate::phoenix::protocol::{Feature, Request, Response, StatusEvent};
+use crate::phoenix::request::RequestError;
+use crate::phoenix::upload::{self, FileChunks, Upload};
+use chrono::{Local, TimeDelta};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Largest file `MemoryBackend` accepts, to exercise rejected uploads.
+pub const MAX_UPLOAD_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Reply to a request, and the messages it causes to be pushed to everyone on the topic.
+pub struct Reply {
+    pub response: Response,
+    pub broadcasts: Vec<Response>,
+}
+
+/// Server side of the protocol kept in memory. Shared by `MemoryTransport` and `StubServer`.
+#[derive(Default)]
+pub struct MemoryBackend {
+    bins: Vec<Bin>,
+    next_id: u64,
+    uploads: HashMap<String, PendingUpload>,
+    finished: HashMap<String, File>,
+}
+
+struct PendingUpload {
+    file: File,
+    size: u64,
+    received: u64,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer a request. Errors are requests the server would fail without a reply payload.
+    pub fn handle(&mut self, request: Request) -> Result<Reply, String> {
+        match request {
+            Request::Bin(request) => self.handle_bin(request),
+            Request::Notes(NotesRequest::GetWorkspaces) => {
+                Ok(Reply::new(Response::Notes(NotesResponse::GetWorkspaces {
+                    data: Vec::new(),
+                })))
+            }
+            Request::Todos(TodosRequest::GetAll) => {
+                Ok(Reply::new(Response::Todos(TodosResponse::GetAll {
+                    data: Vec::new(),
+                })))
+            }
+        }
+    }
+
+    fn handle_bin(&mut self, request: BinRequest) -> Result<Reply, String> {
+        let response = match request {
+            BinRequest::GetAll => {
+                return Ok(Reply::new(Response::Bin(self.get_all())));
+            }
+            BinRequest::New { data } => BinResponse::New {
+                data: status(self.new_bin(data)),
+            },
+            BinRequest::Edit { data } => BinResponse::Edit {
+                data: self.edit_bin(data)?,
+            },
+            BinRequest::Delete { data } => BinResponse::Delete {
+                data: status(self.delete_bin(data)),
+            },
+            BinRequest::UploadStart { data } => {
+                return Ok(Reply::new(Response::Bin(BinResponse::UploadStart {
+                    data: self.upload_start(data),
+                })));
+            }
+            BinRequest::UploadFinish { data } => {
+                return Ok(Reply::new(Response::Bin(BinResponse::UploadFinish {
+                    data: self.upload_finish(data),
+                })));
+            }
+            // Presence is tracked by the stub server, there are no other sessions in memory.
+            BinRequest::Viewing { .. } => {
+                return Ok(Reply::new(Response::Bin(BinResponse::Viewing {
+                    data: status(Ok(())),
+                })));
+            }
+        };
+
+        Ok(Reply {
+            response: Response::Bin(response),
+            broadcasts: vec![Response::Bin(self.get_all())],
+        })
+    }
+
+    fn get_all(&self) -> BinResponse {
+        BinResponse::GetAll {
+            data: self.bins.clone(),
+        }
+    }
+
+    fn new_bin(&mut self, data: NewBin) -> Result<(), String> {
+        let files = self.take_uploads(&data.uploads)?;
+        self.next_id += 1;
+        self.bins.push(Bin {
+            id: self.next_id,
+            title: data.title,
+            content: data.content,
+            expire_at: Local::now() + duration(&data.expire),
+            files,
+        });
+        Ok(())
+    }
+
+    fn edit_bin(&mut self, data: EditBin) -> Result<Bin, String> {
+        let uploaded = self.take_uploads(&data.uploads)?;
+        let bin = self
+            .bins
+            .iter_mut()
+            .find(|bin| bin.id == data.id)
+            .ok_or_else(|| format!("no bin with id {}", data.id))?;
+
+        bin.title = data.title;
+        bin.content = data.content;
+        bin.expire_at = Local::now() + duration(&data.expire);
+        bin.files = data
+            .files
+            .into_iter()
+            .filter(|file| !file.removed)
+            .map(|file| file.file)
+            .chain(uploaded)
+            .collect();
+        Ok(bin.clone())
+    }
+
+    fn delete_bin(&mut self, data: BinId) -> Result<(), String> {
+        let len = self.bins.len();
+        self.bins.retain(|bin| bin.id != data.id);
+        if self.bins.len() == len {
+            return Err(format!("no bin with id {}", data.id));
+        }
+        Ok(())
+    }
+
+    fn take_uploads(&mut self, upload_ids: &[String]) -> Result<Vec<File>, String> {
+        if let Some(unknown) = upload_ids
+            .iter()
+            .find(|upload_id| !self.finished.contains_key(*upload_id))
+        {
+            return Err(format!("unknown upload {unknown}"));
+        }
+        Ok(upload_ids
+            .iter()
+            .filter_map(|upload_id| self.finished.remove(upload_id))
+            .collect())
+    }
+
+    fn upload_start(&mut self, data: UploadStart) -> UploadStatus {
+        if data.size > MAX_UPLOAD_SIZE {
+            return upload_status(
+                None,
+                0,
+                Err(format!(
+                    "{} is too large, the limit is {} MiB",
+                    data.name,
+                    MAX_UPLOAD_SIZE / 1024 / 1024
+                )),
+            );
+        }
+
+        self.next_id += 1;
+        let upload_id = format!("upload-{}", self.next_id);
+        let file = File {
+            id: upload_id.clone(),
+            name: data.name,
+            access_path: format!("/files/{upload_id}"),
+            type_name: data.type_name,
+        };
+        let upload = PendingUpload {
+            file,
+            size: data.size,
+            received: 0,
+        };
+        self.uploads.insert(upload_id.clone(), upload);
+        upload_status(Some(upload_id), 0, Ok(()))
+    }
+
+    /// Accept a binary chunk sent on the upload's event.
+    pub fn upload_chunk(&mut self, upload_id: &str, bytes: &[u8]) -> Response {
+        let data = match self.uploads.get_mut(upload_id) {
+            Some(upload) if upload.received + bytes.len() as u64 > upload.size => upload_status(
+                Some(upload_id.to_string()),
+                upload.received,
+                Err("more data than announced".to_string()),
+            ),
+            Some(upload) => {
+                upload.received += bytes.len() as u64;
+                upload_status(Some(upload_id.to_string()), upload.received, Ok(()))
+            }
+            None => upload_status(None, 0, Err(format!("unknown upload {upload_id}"))),
+        };
+        Response::Bin(BinResponse::UploadChunk { data })
+    }
+
+    fn upload_finish(&mut self, data: UploadId) -> UploadStatus {
+        let upload_id = data.upload_id;
+        match self.uploads.remove(&upload_id) {
+            Some(upload) if upload.received == upload.size => {
+                self.finished.insert(upload_id.clone(), upload.file);
+                upload_status(Some(upload_id), upload.received, Ok(()))
+            }
+            Some(upload) => upload_status(
+                Some(upload_id),
+                upload.received,
+                Err(format!(
+                    "received {} of {} bytes",
+                    upload.received, upload.size
+                )),
+            ),
+            None => upload_status(None, 0, Err(format!("unknown upload {upload_id}"))),
+        }
+    }
+}
+
+impl Reply {
+    fn new(response: Response) -> Self {
+        Self {
+            response,
+            broadcasts: Vec::new(),
+        }
+    }
+}
+
+fn status(result: Result<(), String>) -> StatusEvent {
+    match result {
+        Ok(()) => StatusEvent {
+            status: "OK".to_string(),
+            message: None,
+        },
+        Err(message) => StatusEvent {
+            status: "ERROR".to_string(),
+            message: Some(message),
+        },
+    }
+}
+
+fn upload_status(
+    upload_id: Option<String>,
+    received: u64,
+    result: Result<(), String>,
+) -> UploadStatus {
+    UploadStatus {
+        status: status(result),
+        upload_id,
+        received,
+    }
+}
+
+fn duration(expire: &Expire) -> TimeDelta {
+    let time = expire.time as i64;
+    match expire.unit {
+        ExpireUnit::Minute => TimeDelta::minutes(time),
+        ExpireUnit::Hour => TimeDelta::hours(time),
+        ExpireUnit::Day => TimeDelta::days(time),
+    }
+}
+
+/// Transport answering from a `MemoryBackend`, without any socket. Always connected.
+pub struct MemoryTransport {
+    channels: TransportChannels,
+    backend: MemoryBackend,
+}
+
+impl MemoryTransport {
+    pub fn new(channels: TransportChannels) -> Self {
+        Self {
+            channels,
+            backend: MemoryBackend::new(),
+        }
+    }
+
+    async fn handle_screen_event(&mut self, event: PhoenixEvent) {
+        let socket_tx = &self.channels.socket_tx;
+        let started = Instant::now();
+        let reply = self.backend.handle(event.request.clone());
+        self.channels
+            .stats_tx
+            .send_modify(|stats| stats.record_reply(started.elapsed()));
+        match reply {
+            Ok(reply) => {
+                let response = SocketEvent::Response {
+                    request_id: Some(event.request_id),
+                    response: reply.response,
+                };
+                let _ = socket_tx.send(response).await;
+                for response in reply.broadcasts {
+                    let broadcast = SocketEvent::Response {
+                        request_id: None,
+                        response,
+                    };
+                    let _ = socket_tx.send(broadcast).await;
+                }
+            }
+            Err(message) => {
+                let failed = SocketEvent::failed(&event, RequestError::Rejected(message));
+                let _ = socket_tx.send(failed).await;
+            }
+        }
+    }
+
+    async fn handle_upload(&mut self, upload: Upload) {
+        let event = match self.upload(&upload).await {
+            Ok(upload_id) => SocketEvent::Uploaded {
+                request_id: upload.request_id,
+                upload_id,
+            },
+            Err(error) => SocketEvent::Failed {
+                request_id: upload.request_id,
+                feature: Feature::Bin,
+                error,
+            },
+        };
+        let _ = self.channels.socket_tx.send(event).await;
+    }
+
+    /// Same steps as `upload::run`, answered by the backend.
+    async fn upload(&mut self, upload: &Upload) -> Result<String, RequestError> {
+        let mut chunks = FileChunks::open(&upload.path).await?;
+        let total = chunks.size;
+
+        let data = UploadStart {
+            name: upload::file_name(&upload.path),
+            type_name: upload::content_type(&upload.path).to_string(),
+            size: total,
+        };
+        let start = self.request(BinRequest::UploadStart { data })?;
+        let upload_id = start
+            .upload_id
+            .ok_or_else(|| RequestError::InvalidReply("missing upload_id".to_string()))?;
+
+        loop {
+            let chunk = chunks.next().await?;
+            if chunk.is_empty() {
+                break;
+            }
+            let status = upload::upload_status(self.backend.upload_chunk(&upload_id, chunk))?;
+            let progress = SocketEvent::UploadProgress {
+                request_id: upload.request_id,
+                sent: status.received,
+                total,
+            };
+            let _ = self.channels.socket_tx.send(progress).await;
+        }
+
+        let data = UploadId {
+            upload_id: upload_id.clone(),
+        };
+        self.request(BinRequest::UploadFinish { data })?;
+        Ok(upload_id)
+    }
+
+    fn request(&mut self, request: BinRequest) -> Result<UploadStatus, RequestError> {
+        let reply = self
+            .backend
+            .handle(Request::Bin(request))
+            .map_err(RequestError::Rejected)?;
+        upload::upload_status(reply.response)
+    }
+}
+
+impl Transport for MemoryTransport {
+    async fn run(&mut self) {
+        let _ = self.channels.state_tx.send(ConnectionState::Connected);
+        self.channels
+            .stats_tx
+            .send_modify(ConnectionStats::record_heartbeat);
+
+        loop {
+            tokio::select! {
+                Some(event) = self.channels.screen_rx.recv() => self.handle_screen_event(event).await,
+
+                Some(upload) = self.channels.upload_rx.recv() => self.handle_upload(upload).await,
+
+                // Every topic is always joined.
+                Some(_) = self.channels.topic_rx.recv() => {}
+
+                else => return,
+            }
+        }
+    }
+
+    async fn disassemble(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::store::Store;
+    use crate::phoenix::presence::{LocalSession, Presence};
+    use crate::phoenix::protocol::bin::EditFile;
+    use crate::phoenix::topic::TopicCommand;
+    use tokio::sync::mpsc::{self, Receiver, Sender};
+    use tokio::sync::watch;
+
+    /// App side of a running `MemoryTransport`, ingesting what it receives like `App` does.
+    struct Client {
+        screen_tx: Sender<PhoenixEvent>,
+        upload_tx: Sender<Upload>,
+        socket_rx: Receiver<SocketEvent>,
+        // Kept so the transport doesn't stop.
+        _topic_tx: Sender<TopicCommand>,
+        store: Store,
+    }
+
+    impl Client {
+        fn start() -> Self {
+            let (socket_tx, socket_rx) = mpsc::channel(100);
+            let (screen_tx, screen_rx) = mpsc::channel(100);
+            let (topic_tx, topic_rx) = mpsc::channel(10);
+            let (upload_tx, upload_rx) = mpsc::channel(10);
+            let channels = TransportChannels {
+                socket_tx,
+                screen_rx,
+                topic_rx,
+                upload_rx,
+                state_tx: watch::Sender::new(ConnectionState::Connecting),
+                stats_tx: watch::Sender::new(ConnectionStats::default()),
+                presence_tx: watch::Sender::new(Presence::new(LocalSession::new())),
+            };
+            let mut transport = MemoryTransport::new(channels);
+            tokio::spawn(async move { transport.run().await });
+
+            Self {
+                screen_tx,
+                upload_tx,
+                socket_rx,
+                _topic_tx: topic_tx,
+                store: Store::default(),
+            }
+        }
+
+        /// Send a bin request and wait for the socket event answering it.
+        async fn send(&mut self, request: BinRequest) -> SocketEvent {
+            let event = PhoenixEvent::new(Request::Bin(request));
+            let request_id = event.request_id;
+            self.screen_tx.send(event).await.unwrap();
+
+            let event = self.socket_rx.recv().await.unwrap();
+            match &event {
+                SocketEvent::Response {
+                    request_id: Some(id),
+                    response,
+                } => {
+                    assert_eq!(*id, request_id);
+                    self.store.ingest(response);
+                }
+                SocketEvent::Failed { request_id: id, .. } => assert_eq!(*id, request_id),
+                event => panic!("unexpected {event:?}"),
+            }
+            event
+        }
+
+        async fn request(&mut self, request: BinRequest) -> BinResponse {
+            match self.send(request).await {
+                SocketEvent::Response {
+                    response: Response::Bin(response),
+                    ..
+                } => response,
+                event => panic!("expected a bin reply, got {event:?}"),
+            }
+        }
+
+        /// Wait for the `get-all` pushed after a change.
+        async fn broadcast(&mut self) -> Vec<Bin> {
+            match self.socket_rx.recv().await.unwrap() {
+                SocketEvent::Response {
+                    request_id: None,
+                    response,
+                } => {
+                    self.store.ingest(&response);
+                    match response {
+                        Response::Bin(BinResponse::GetAll { data }) => data,
+                        response => panic!("expected get-all, got {response:?}"),
+                    }
+                }
+                event => panic!("expected a broadcast, got {event:?}"),
+            }
+        }
+    }
+
+    fn new_bin(title: &str) -> BinRequest {
+        BinRequest::New {
+            data: NewBin {
+                title: title.to_string(),
+                content: "content".to_string(),
+                expire: Expire {
+                    time: 2,
+                    unit: ExpireUnit::Hour,
+                },
+                uploads: Vec::new(),
+            },
+        }
+    }
+
+    fn edit_bin(bin: &Bin, title: &str) -> BinRequest {
+        BinRequest::Edit {
+            data: EditBin {
+                id: bin.id,
+                title: title.to_string(),
+                content: bin.content.clone(),
+                files: bin
+                    .files
+                    .iter()
+                    .map(|file| EditFile {
+                        file: file.clone(),
+                        removed: false,
+                    })
+                    .collect(),
+                expire: Expire {
+                    time: 30,
+                    unit: ExpireUnit::Minute,
+                },
+                uploads: Vec::new(),
+            },
+        }
+    }
+
+    fn assert_ok(response: &BinResponse) {
+        let status = response.status().expect("a status");
+        assert!(status.is_ok(), "{status:?}");
+    }
+
+    #[tokio::test]
+    async fn create_edit_and_delete_a_bin() {
+        let mut client = Client::start();
+        assert_eq!(
+            client.request(BinRequest::GetAll).await,
+            BinResponse::GetAll { data: vec![] }
+        );
+
+        let reply = client.request(new_bin("Draft")).await;
+        assert!(matches!(reply, BinResponse::New { .. }));
+        assert_ok(&reply);
+        let bins = client.broadcast().await;
+        assert_eq!(bins.len(), 1);
+        assert_eq!(client.store.bins, bins);
+        let bin = bins[0].clone();
+        assert_eq!(bin.title, "Draft");
+        assert!(bin.expire_at > Local::now() + TimeDelta::minutes(119));
+
+        match client.request(edit_bin(&bin, "Final")).await {
+            BinResponse::Edit { data } => {
+                assert_eq!(data.id, bin.id);
+                assert_eq!(data.title, "Final");
+                // From now, like the server, not from the old expiry.
+                assert!(data.expire_at > Local::now() + TimeDelta::minutes(29));
+                assert!(data.expire_at <= Local::now() + TimeDelta::minutes(30));
+            }
+            response => panic!("expected edit, got {response:?}"),
+        }
+        assert_eq!(client.store.bin(bin.id).unwrap().title, "Final");
+        assert_eq!(client.broadcast().await, client.store.bins);
+
+        let reply = client
+            .request(BinRequest::Delete {
+                data: BinId { id: bin.id },
+            })
+            .await;
+        assert_ok(&reply);
+        assert!(client.broadcast().await.is_empty());
+        assert!(client.store.bins.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_bins_are_refused() {
+        let mut client = Client::start();
+        client.request(new_bin("Draft")).await;
+        let bin = client.broadcast().await.remove(0);
+
+        let reply = client
+            .request(BinRequest::Delete {
+                data: BinId { id: 42 },
+            })
+            .await;
+        let status = reply.status().unwrap();
+        assert!(!status.is_ok());
+        assert_eq!(status.error_message(), "no bin with id 42");
+        // The refused delete still pushes the list, unchanged.
+        assert_eq!(client.broadcast().await, vec![bin.clone()]);
+
+        let edit = edit_bin(&Bin { id: 42, ..bin }, "Lost");
+        match client.send(edit).await {
+            SocketEvent::Failed { error, .. } => {
+                assert_eq!(
+                    error,
+                    RequestError::Rejected("no bin with id 42".to_string())
+                );
+            }
+            event => panic!("expected a failure, got {event:?}"),
+        }
+        assert_eq!(client.store.bins.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn upload_a_file_in_chunks() {
+        let path = std::env::temp_dir().join(format!("meowui-upload-{}.txt", std::process::id()));
+        let chunk = upload::CHUNK_SIZE as u64;
+        let size = 2 * chunk + 10;
+        std::fs::write(&path, vec![b'x'; size as usize]).unwrap();
+
+        let mut client = Client::start();
+        let upload = Upload::new(path.clone());
+        let request_id = upload.request_id;
+        client.upload_tx.send(upload).await.unwrap();
+
+        let mut progress = Vec::new();
+        let upload_id = loop {
+            match client.socket_rx.recv().await.unwrap() {
+                SocketEvent::UploadProgress { sent, total, .. } => {
+                    assert_eq!(total, size);
+                    progress.push(sent);
+                }
+                SocketEvent::Uploaded {
+                    request_id: id,
+                    upload_id,
+                } => {
+                    assert_eq!(id, request_id);
+                    break upload_id;
+                }
+                event => panic!("unexpected {event:?}"),
+            }
+        };
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(progress, vec![chunk, 2 * chunk, size]);
+
+        let mut request = new_bin("With a file");
+        if let BinRequest::New { data } = &mut request {
+            data.uploads.push(upload_id);
+        }
+        assert_ok(&client.request(request).await);
+        let bins = client.broadcast().await;
+        assert_eq!(bins[0].files.len(), 1);
+        assert_eq!(bins[0].files[0].name, upload::file_name(&path));
+    }
+}
@@ -0,0 +1,133 @@
+use super::TransportChannels;
+use crate::phoenix::event::{PhoenixEvent, SocketEvent};
+use crate::phoenix::upload::Upload;
+use chrono::{DateTime, Local};
+use cli_log::info;
+use std::fs::File;
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+/// One line of a recorded session.
+#[derive(serde::Deserialize, Debug)]
+pub struct Entry {
+    /// Milliseconds since the recording started.
+    pub at_ms: u64,
+    pub timestamp: DateTime<Local>,
+    #[serde(flatten)]
+    pub traffic: Traffic,
+}
+
+/// Traffic between `App` and the transport, as seen from the app.
+#[derive(serde::Deserialize, Debug)]
+#[serde(tag = "direction", content = "event", rename_all = "lowercase")]
+pub enum Traffic {
+    Sent(PhoenixEvent),
+    Upload(Upload),
+    Received(SocketEvent),
+}
+
+/// Put a recorder between `App` and the transport: every event sent by screens and received
+/// from the transport is passed on and appended to `path` as a JSON line. Returns the channels
+/// the transport should use.
+pub fn record(channels: TransportChannels, path: &Path) -> anyhow::Result<TransportChannels> {
+    let mut recorder = Recorder {
+        file: LineWriter::new(File::create(path)?),
+        started: Instant::now(),
+    };
+
+    let TransportChannels {
+        socket_tx: app_socket_tx,
+        screen_rx: mut app_screen_rx,
+        topic_rx,
+        upload_rx: mut app_upload_rx,
+        state_tx,
+        stats_tx,
+        presence_tx,
+    } = channels;
+    let (socket_tx, mut socket_rx) = mpsc::channel::<SocketEvent>(100);
+    let (screen_tx, screen_rx) = mpsc::channel::<PhoenixEvent>(100);
+    let (upload_tx, upload_rx) = mpsc::channel::<Upload>(10);
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                Some(event) = app_screen_rx.recv() => {
+                    recorder.write("sent", &event);
+                    let _ = screen_tx.send(event).await;
+                }
+
+                Some(upload) = app_upload_rx.recv() => {
+                    recorder.write("upload", &upload);
+                    let _ = upload_tx.send(upload).await;
+                }
+
+                Some(event) = socket_rx.recv() => {
+                    recorder.write("received", &event);
+                    let _ = app_socket_tx.send(event).await;
+                }
+
+                else => return,
+            }
+        }
+    });
+
+    Ok(TransportChannels {
+        socket_tx,
+        screen_rx,
+        topic_rx,
+        upload_rx,
+        state_tx,
+        stats_tx,
+        presence_tx,
+    })
+}
+
+/// Read a recorded session.
+pub fn load(path: &Path) -> anyhow::Result<Vec<Entry>> {
+    let file = File::open(path)?;
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| anyhow::anyhow!("{}:{}: {e}", path.display(), index + 1))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+struct Recorder {
+    file: LineWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    /// Append an entry. Lines are flushed as they are written so a crash keeps the session.
+    fn write<T: serde::Serialize>(&mut self, direction: &'static str, event: &T) {
+        let entry = EntryRef {
+            at_ms: self.started.elapsed().as_millis() as u64,
+            timestamp: Local::now(),
+            direction,
+            event,
+        };
+        let result = serde_json::to_string(&entry)
+            .map_err(std::io::Error::from)
+            .and_then(|line| writeln!(self.file, "{line}"));
+        if let Err(e) = result {
+            info!("recorder: {e}");
+        }
+    }
+}
+
+/// Serialized like `Entry`, borrowing the event so it doesn't need to be cloned.
+#[derive(serde::Serialize)]
+struct EntryRef<'a, T> {
+    at_ms: u64,
+    timestamp: DateTime<Local>,
+    direction: &'static str,
+    event: &'a T,
+}
@@ -0,0 +1,172 @@
+use super::record::{self, Entry, Traffic};
+use super::{Transport, TransportChannels};
+use crate::phoenix::connection::{ConnectionState, ConnectionStats};
+use crate::phoenix::event::{PhoenixEvent, SocketEvent};
+use crate::phoenix::request::{RequestError, RequestId};
+use crate::phoenix::upload::Upload;
+use cli_log::info;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::time::Duration;
+
+/// Transport playing back a session recorded with `record::record`, so the app behaves as it
+/// did without the server.
+///
+/// The recording is followed in order. Each recorded request waits for the app to send its
+/// next request or upload, and received events are sent with the delays they were recorded
+/// with. Request IDs in replies are rewritten to the IDs of the requests actually sent. Once the
+/// recording is over, further requests fail.
+pub struct ReplayTransport {
+    channels: TransportChannels,
+    entries: VecDeque<Entry>,
+    /// Recorded request ID to the ID of the request the app sent in its place.
+    request_ids: HashMap<RequestId, RequestId>,
+}
+
+/// Request sent by the app while replaying.
+enum Outgoing {
+    Request(PhoenixEvent),
+    Upload(Upload),
+}
+
+impl ReplayTransport {
+    pub fn load(path: &Path, channels: TransportChannels) -> anyhow::Result<Self> {
+        Ok(Self {
+            channels,
+            entries: record::load(path)?.into(),
+            request_ids: HashMap::new(),
+        })
+    }
+
+    /// Next request or upload from the app. None once the app is gone.
+    async fn next_outgoing(&mut self) -> Option<Outgoing> {
+        loop {
+            tokio::select! {
+                Some(event) = self.channels.screen_rx.recv() => return Some(Outgoing::Request(event)),
+
+                Some(upload) = self.channels.upload_rx.recv() => return Some(Outgoing::Upload(upload)),
+
+                // Every topic is always joined.
+                Some(_) = self.channels.topic_rx.recv() => {}
+
+                else => return None,
+            }
+        }
+    }
+
+    /// Match a request from the app with the recorded one.
+    fn replace_request(&mut self, recorded: &Traffic, outgoing: Outgoing) {
+        let (recorded_id, recorded_name) = match recorded {
+            Traffic::Sent(event) => (event.request_id, describe(event)),
+            Traffic::Upload(upload) => (upload.request_id, upload.path.display().to_string()),
+            Traffic::Received(_) => return,
+        };
+        let (request_id, name) = match outgoing {
+            Outgoing::Request(event) => (event.request_id, describe(&event)),
+            Outgoing::Upload(upload) => (upload.request_id, upload.path.display().to_string()),
+        };
+        if recorded_name != name {
+            info!("replay: expected {recorded_name}, got {name}");
+        }
+        self.request_ids.insert(recorded_id, request_id);
+    }
+
+    fn request_id(&self, recorded: RequestId) -> RequestId {
+        self.request_ids.get(&recorded).copied().unwrap_or(recorded)
+    }
+
+    /// Recorded event with the request IDs of this session.
+    fn rewrite(&self, event: SocketEvent) -> SocketEvent {
+        match event {
+            SocketEvent::Response {
+                request_id,
+                response,
+            } => SocketEvent::Response {
+                request_id: request_id.map(|request_id| self.request_id(request_id)),
+                response,
+            },
+            SocketEvent::Failed {
+                request_id,
+                feature,
+                error,
+            } => SocketEvent::Failed {
+                request_id: self.request_id(request_id),
+                feature,
+                error,
+            },
+            SocketEvent::UploadProgress {
+                request_id,
+                sent,
+                total,
+            } => SocketEvent::UploadProgress {
+                request_id: self.request_id(request_id),
+                sent,
+                total,
+            },
+            SocketEvent::Uploaded {
+                request_id,
+                upload_id,
+            } => SocketEvent::Uploaded {
+                request_id: self.request_id(request_id),
+                upload_id,
+            },
+            SocketEvent::Diagnostic(e) => SocketEvent::Diagnostic(e),
+        }
+    }
+
+    async fn send(&self, event: SocketEvent) {
+        self.channels
+            .stats_tx
+            .send_modify(ConnectionStats::record_heartbeat);
+        let _ = self.channels.socket_tx.send(event).await;
+    }
+}
+
+/// Request without its ID, to compare it with the recorded one.
+fn describe(event: &PhoenixEvent) -> String {
+    format!("{} {}", event.name(), event.request.action())
+}
+
+impl Transport for ReplayTransport {
+    async fn run(&mut self) {
+        let _ = self.channels.state_tx.send(ConnectionState::Connected);
+        if let Some(first) = self.entries.front() {
+            info!(
+                "replay: {} entries recorded at {}",
+                self.entries.len(),
+                first.timestamp
+            );
+        }
+
+        let mut last_at = 0;
+        while let Some(entry) = self.entries.pop_front() {
+            match entry.traffic {
+                Traffic::Received(event) => {
+                    let delay = entry.at_ms.saturating_sub(last_at);
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    let event = self.rewrite(event);
+                    self.send(event).await;
+                }
+                recorded => {
+                    let Some(outgoing) = self.next_outgoing().await else {
+                        return;
+                    };
+                    self.replace_request(&recorded, outgoing);
+                }
+            }
+            last_at = entry.at_ms;
+        }
+
+        info!("replay: end of recording");
+        while let Some(outgoing) = self.next_outgoing().await {
+            let error = RequestError::Rejected("not in the recording".to_string());
+            let event = match outgoing {
+                Outgoing::Request(event) => SocketEvent::failed(&event, error),
+                Outgoing::Upload(upload) => upload.failed(error),
+            };
+            self.send(event).await;
+        }
+    }
+
+    async fn disassemble(&mut self) {}
+}
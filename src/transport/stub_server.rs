@@ -0,0 +1,518 @@
+use super::memory::MemoryBackend;
+use crate::phoenix::protocol::bin::{BinRequest, UPLOAD_CHUNK_EVENT_PREFIX};
+use crate::phoenix::protocol::{Request, Response};
+use cli_log::info;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Local websocket server speaking the Phoenix channels protocol (V2 serializer), answering from a
+/// `MemoryBackend`. Point `Phoenix` at `url()` to run the app without the real server.
+///
+/// Handles heartbeats, joining and leaving topics, feature events and binary upload chunks.
+/// Messages pushed after a change are sent to every connection that joined the topic. Sessions
+/// that join with a `session_id` are tracked in the topic's presence.
+pub struct StubServer {
+    listener: TcpListener,
+    backend: Arc<Mutex<MemoryBackend>>,
+    presences: Arc<Mutex<Presences>>,
+    pushes: broadcast::Sender<Push>,
+}
+
+/// Message pushed by the server on a topic.
+#[derive(Debug, Clone)]
+struct Push {
+    topic: String,
+    event: String,
+    payload: Value,
+}
+
+/// A frame received from a client.
+struct Frame {
+    join_ref: Option<String>,
+    msg_ref: Option<String>,
+    topic: String,
+    event: String,
+    payload: FramePayload,
+}
+
+enum FramePayload {
+    Json(Value),
+    Binary(Vec<u8>),
+}
+
+impl StubServer {
+    /// Listen on `addr`, e.g. `127.0.0.1:0` for any free port.
+    pub async fn bind(addr: &str) -> std::io::Result<Self> {
+        let (pushes, _) = broadcast::channel(100);
+        Ok(Self {
+            listener: TcpListener::bind(addr).await?,
+            backend: Arc::new(Mutex::new(MemoryBackend::new())),
+            presences: Arc::new(Mutex::new(HashMap::new())),
+            pushes,
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Websocket URL to connect `Phoenix` to.
+    pub fn url(&self) -> std::io::Result<String> {
+        Ok(format!("ws://{}/tui/websocket", self.local_addr()?))
+    }
+
+    /// Accept connections until the task is dropped.
+    pub async fn serve(self) {
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, addr)) => {
+                    info!("stub server: connection from {addr}");
+                    let connection = Connection {
+                        backend: self.backend.clone(),
+                        presences: self.presences.clone(),
+                        pushes: self.pushes.clone(),
+                        joined: Vec::new(),
+                    };
+                    tokio::spawn(connection.run(stream));
+                }
+                Err(e) => info!("stub server: accept failed: {e}"),
+            }
+        }
+    }
+}
+
+/// Presence metas of every session, by topic and session ID.
+type Presences = HashMap<String, HashMap<String, Value>>;
+
+struct Connection {
+    backend: Arc<Mutex<MemoryBackend>>,
+    presences: Arc<Mutex<Presences>>,
+    pushes: broadcast::Sender<Push>,
+    joined: Vec<Joined>,
+}
+
+struct Joined {
+    topic: String,
+    join_ref: Option<String>,
+    /// Session tracked in the topic's presence, from the join params.
+    session_id: Option<String>,
+}
+
+impl Connection {
+    async fn run(mut self, stream: TcpStream) {
+        let websocket = match tokio_tungstenite::accept_async(stream).await {
+            Ok(websocket) => websocket,
+            Err(e) => {
+                info!("stub server: handshake failed: {e}");
+                return;
+            }
+        };
+        let (mut sink, mut stream) = websocket.split();
+        let mut pushes = self.pushes.subscribe();
+
+        'conn: loop {
+            let outgoing = tokio::select! {
+                message = stream.next() => match message {
+                    Some(Ok(message)) => match decode(message) {
+                        Some(frame) => self.handle(frame),
+                        None => continue,
+                    },
+                    Some(Err(e)) => {
+                        info!("stub server: {e}");
+                        break;
+                    }
+                    None => break,
+                },
+
+                Ok(push) = pushes.recv() => {
+                    let Some(joined) = self.joined(&push.topic) else {
+                        continue;
+                    };
+                    vec![json!([joined.join_ref, null, push.topic, push.event, push.payload])]
+                }
+            };
+
+            for frame in outgoing {
+                if sink.send(Message::Text(frame.to_string())).await.is_err() {
+                    break 'conn;
+                }
+            }
+        }
+
+        let topics: Vec<String> = self
+            .joined
+            .iter()
+            .map(|joined| joined.topic.clone())
+            .collect();
+        for topic in topics {
+            self.leave(&topic);
+        }
+    }
+
+    fn joined(&self, topic: &str) -> Option<&Joined> {
+        self.joined.iter().find(|joined| joined.topic == topic)
+    }
+
+    /// Frames to send back for a received frame.
+    fn handle(&mut self, frame: Frame) -> Vec<Value> {
+        let mut outgoing = Vec::new();
+        let result = match (frame.event.as_str(), &frame.payload) {
+            ("heartbeat", _) => Ok(json!({})),
+            ("phx_join", FramePayload::Json(params)) => {
+                self.leave(&frame.topic);
+                if let Some(state) = self.join(&frame, params) {
+                    outgoing.push(json!([
+                        frame.join_ref,
+                        null,
+                        frame.topic,
+                        "presence_state",
+                        state
+                    ]));
+                }
+                Ok(json!({}))
+            }
+            ("phx_leave", _) => {
+                self.leave(&frame.topic);
+                Ok(json!({}))
+            }
+            _ if self.joined(&frame.topic).is_none() => Err("unmatched topic".to_string()),
+            (event, FramePayload::Binary(bytes)) => {
+                match event.strip_prefix(UPLOAD_CHUNK_EVENT_PREFIX) {
+                    Some(upload_id) => {
+                        let response = lock(&self.backend).upload_chunk(upload_id, bytes);
+                        response.payload().map_err(|e| e.to_string())
+                    }
+                    None => Err(format!("unexpected binary payload on {event}")),
+                }
+            }
+            (event, FramePayload::Json(payload)) => self.handle_request(&frame, event, payload),
+        };
+
+        let reply = match result {
+            Ok(response) => json!({"status": "ok", "response": response}),
+            Err(reason) => json!({"status": "error", "response": {"reason": reason}}),
+        };
+        outgoing.insert(
+            0,
+            json!([
+                frame.join_ref,
+                frame.msg_ref,
+                frame.topic,
+                "phx_reply",
+                reply
+            ]),
+        );
+        outgoing
+    }
+
+    /// Join the topic, tracking the session given in the params. Returns the topic's presence
+    /// state to send to the new member.
+    fn join(&mut self, frame: &Frame, params: &Value) -> Option<Value> {
+        let session_id = params["session_id"].as_str().map(str::to_string);
+        self.joined.push(Joined {
+            topic: frame.topic.clone(),
+            join_ref: frame.join_ref.clone(),
+            session_id: session_id.clone(),
+        });
+
+        let session_id = session_id?;
+        let meta = json!({
+            "phx_ref": frame.msg_ref,
+            "name": params["name"].as_str().unwrap_or("unknown"),
+            "bin_id": null,
+        });
+        let state = {
+            let mut presences = lock(&self.presences);
+            let topic = presences.entry(frame.topic.clone()).or_default();
+            topic.insert(session_id.clone(), meta.clone());
+            presence_entries(topic.iter())
+        };
+        self.push_presence_diff(&frame.topic, Some((&session_id, meta)), None);
+        Some(state)
+    }
+
+    fn leave(&mut self, topic: &str) {
+        let Some(index) = self.joined.iter().position(|joined| joined.topic == topic) else {
+            return;
+        };
+        let joined = self.joined.remove(index);
+        let Some(session_id) = joined.session_id else {
+            return;
+        };
+
+        let meta = lock(&self.presences)
+            .get_mut(topic)
+            .and_then(|sessions| sessions.remove(&session_id));
+        if let Some(meta) = meta {
+            self.push_presence_diff(topic, None, Some((&session_id, meta)));
+        }
+    }
+
+    fn handle_request(&self, frame: &Frame, event: &str, payload: &Value) -> Result<Value, String> {
+        let request = Request::decode(event, payload.clone()).map_err(|e| e.to_string())?;
+        if let Request::Bin(BinRequest::Viewing { data }) = &request {
+            self.update_viewing(frame, data.id);
+        }
+        let reply = lock(&self.backend).handle(request)?;
+
+        for response in reply.broadcasts {
+            self.push(&frame.topic, &response);
+        }
+        reply.response.payload().map_err(|e| e.to_string())
+    }
+
+    /// Replace the session's meta with one carrying the open bin.
+    fn update_viewing(&self, frame: &Frame, bin_id: Option<u64>) {
+        let Some(session_id) = self
+            .joined(&frame.topic)
+            .and_then(|joined| joined.session_id.clone())
+        else {
+            return;
+        };
+
+        let (old, new) = {
+            let mut presences = lock(&self.presences);
+            let Some(meta) = presences
+                .get_mut(&frame.topic)
+                .and_then(|sessions| sessions.get_mut(&session_id))
+            else {
+                return;
+            };
+            let old = meta.clone();
+            meta["phx_ref"] = json!(frame.msg_ref);
+            meta["bin_id"] = json!(bin_id);
+            (old, meta.clone())
+        };
+        self.push_presence_diff(
+            &frame.topic,
+            Some((&session_id, new)),
+            Some((&session_id, old)),
+        );
+    }
+
+    fn push_presence_diff(
+        &self,
+        topic: &str,
+        joins: Option<(&String, Value)>,
+        leaves: Option<(&String, Value)>,
+    ) {
+        let entries = |change: Option<(&String, Value)>| {
+            let entries: serde_json::Map<String, Value> = change
+                .map(|(session_id, meta)| (session_id.clone(), json!({"metas": [meta]})))
+                .into_iter()
+                .collect();
+            Value::Object(entries)
+        };
+        let push = Push {
+            topic: topic.to_string(),
+            event: "presence_diff".to_string(),
+            payload: json!({"joins": entries(joins), "leaves": entries(leaves)}),
+        };
+        let _ = self.pushes.send(push);
+    }
+
+    fn push(&self, topic: &str, response: &Response) {
+        let Ok(payload) = response.payload() else {
+            return;
+        };
+        let push = Push {
+            topic: topic.to_string(),
+            event: response.feature().name().to_string(),
+            payload,
+        };
+        // No receivers only means no other connection is open.
+        let _ = self.pushes.send(push);
+    }
+}
+
+/// State shared by the connections. A connection that panicked while holding it doesn't stop the
+/// others.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Presence entries as sent by Phoenix: `{<session_id>: {"metas": [...]}}`.
+fn presence_entries<'a>(sessions: impl Iterator<Item = (&'a String, &'a Value)>) -> Value {
+    let entries: serde_json::Map<String, Value> = sessions
+        .map(|(session_id, meta)| (session_id.clone(), json!({"metas": [meta]})))
+        .collect();
+    Value::Object(entries)
+}
+
+/// Decode a text frame (`[join_ref, ref, topic, event, payload]`) or a binary push
+/// (kind, four sizes, then join_ref, ref, topic, event and the payload bytes).
+fn decode(message: Message) -> Option<Frame> {
+    match message {
+        Message::Text(text) => {
+            let value: Value = serde_json::from_str(&text).ok()?;
+            let [join_ref, msg_ref, topic, event, payload] =
+                <[Value; 5]>::try_from(value.as_array()?.clone()).ok()?;
+            Some(Frame {
+                join_ref: join_ref.as_str().map(str::to_string),
+                msg_ref: msg_ref.as_str().map(str::to_string),
+                topic: topic.as_str()?.to_string(),
+                event: event.as_str()?.to_string(),
+                payload: FramePayload::Json(payload),
+            })
+        }
+        Message::Binary(bytes) => {
+            let (&kind, rest) = bytes.split_first()?;
+            // Only pushes (kind 0) are sent by clients.
+            if kind != 0 || rest.len() < 4 {
+                return None;
+            }
+            let sizes: Vec<usize> = rest[..4].iter().map(|&size| size as usize).collect();
+            let mut rest = &rest[4..];
+            let mut fields = Vec::with_capacity(4);
+            for size in sizes {
+                if rest.len() < size {
+                    return None;
+                }
+                let (field, tail) = rest.split_at(size);
+                fields.push(String::from_utf8(field.to_vec()).ok()?);
+                rest = tail;
+            }
+            let [join_ref, msg_ref, topic, event] = <[String; 4]>::try_from(fields).ok()?;
+            Some(Frame {
+                join_ref: Some(join_ref).filter(|join_ref| !join_ref.is_empty()),
+                msg_ref: Some(msg_ref).filter(|msg_ref| !msg_ref.is_empty()),
+                topic,
+                event,
+                payload: FramePayload::Binary(rest.to_vec()),
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phoenix::protocol::bin::{Bin, BinResponse, Expire, ExpireUnit, NewBin};
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+    type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    async fn send(client: &mut Client, frame: Value) {
+        client.send(Message::Text(frame.to_string())).await.unwrap();
+    }
+
+    async fn receive(client: &mut Client) -> Value {
+        match client.next().await.unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            message => panic!("expected a text frame, got {message:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip_over_a_socket() {
+        let server = StubServer::bind("127.0.0.1:0").await.unwrap();
+        let url = server.url().unwrap();
+        tokio::spawn(server.serve());
+        let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        send(&mut client, json!(["1", "1", "bin", "phx_join", {}])).await;
+        assert_eq!(
+            receive(&mut client).await,
+            json!(["1", "1", "bin", "phx_reply", {"status": "ok", "response": {}}])
+        );
+
+        let request = Request::Bin(BinRequest::New {
+            data: NewBin {
+                title: "Draft".to_string(),
+                content: "content".to_string(),
+                expire: Expire {
+                    time: 1,
+                    unit: ExpireUnit::Day,
+                },
+                uploads: Vec::new(),
+            },
+        });
+        send(
+            &mut client,
+            json!(["1", "2", "bin", "bin", request.payload().unwrap()]),
+        )
+        .await;
+
+        let reply = receive(&mut client).await;
+        assert_eq!(reply[1], "2");
+        assert_eq!(reply[3], "phx_reply");
+        assert_eq!(reply[4]["status"], "ok");
+        let response: BinResponse = serde_json::from_value(reply[4]["response"].clone()).unwrap();
+        assert!(response.status().unwrap().is_ok());
+
+        // The change is pushed to everyone on the topic.
+        let push = receive(&mut client).await;
+        assert_eq!(push[1], Value::Null);
+        assert_eq!(push[3], "bin");
+        let bins: Vec<Bin> = match serde_json::from_value(push[4].clone()).unwrap() {
+            BinResponse::GetAll { data } => data,
+            response => panic!("expected get-all, got {response:?}"),
+        };
+        assert_eq!(bins.len(), 1);
+        assert_eq!(bins[0].title, "Draft");
+
+        send(
+            &mut client,
+            json!(["1", "3", "notes", "notes", {"action": "get-workspaces"}]),
+        )
+        .await;
+        let reply = receive(&mut client).await;
+        assert_eq!(
+            reply[4],
+            json!({"status": "error", "response": {"reason": "unmatched topic"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn a_panicked_connection_does_not_stop_the_others() {
+        let server = StubServer::bind("127.0.0.1:0").await.unwrap();
+        let url = server.url().unwrap();
+        let backend = server.backend.clone();
+        let presences = server.presences.clone();
+        std::thread::spawn(move || {
+            let _backend = backend.lock().unwrap();
+            let _presences = presences.lock().unwrap();
+            panic!("connection handler panicked");
+        })
+        .join()
+        .unwrap_err();
+        tokio::spawn(server.serve());
+        let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        send(
+            &mut client,
+            json!(["1", "1", "bin", "phx_join", {"session_id": "s1", "name": "laptop"}]),
+        )
+        .await;
+        let reply = receive(&mut client).await;
+        assert_eq!(reply[4]["status"], "ok");
+        let state = receive(&mut client).await;
+        assert_eq!(state[3], "presence_state");
+
+        send(
+            &mut client,
+            json!(["1", "2", "bin", "bin", {"action": "get-all"}]),
+        )
+        .await;
+        // After the session's own presence diff.
+        let reply = loop {
+            let frame = receive(&mut client).await;
+            if frame[3] == "phx_reply" {
+                break frame;
+            }
+        };
+        assert_eq!(reply[1], "2");
+        assert_eq!(
+            reply[4],
+            json!({"status": "ok", "response": {"action": "get-all", "data": []}})
+        );
+    }
+}
@@ -1,5 +1,9 @@
 use crate::app::App;
-use crossterm::event::{self, poll, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::cursor::Show;
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, Event, EventStream, MouseEventKind,
+};
+use futures_util::StreamExt;
 use ratatui::crossterm::execute;
 use ratatui::crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
@@ -7,20 +11,49 @@ use ratatui::crossterm::terminal::{
 use ratatui::prelude::CrosstermBackend;
 use ratatui::Terminal;
 use std::io::stdout;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
+use tokio::time::{self, MissedTickBehavior};
 
 pub mod expire_at_input_widget;
 pub mod input_widget;
+pub mod mouse;
 pub mod multiline_input_widget;
 
 type Term = Terminal<CrosstermBackend<std::io::Stdout>>;
 
+/// Redraw interval while something on screen changes with time, like toasts or heartbeat ages.
+const TICK: Duration = Duration::from_secs(1);
+
 pub struct TUI {
     terminal: Term,
 }
 
 pub enum TUIAction {
     Quit,
+    /// Give the terminal back to the shell until it resumes the app.
+    Suspend,
+}
+
+/// Leaves the alternate screen and raw mode when dropped, however the event loop ends.
+struct RestoreGuard;
+
+impl Drop for RestoreGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Whether `restore` already gave the terminal back.
+static RESTORED: AtomicBool = AtomicBool::new(false);
+
+/// Give the terminal back in the state the app found it, ignoring errors so it can be called
+/// while panicking. Only the first call does anything, so a panic of several threads or the
+/// guard dropping after the panic hook don't write the escape codes twice.
+pub fn restore() {
+    if !RESTORED.swap(true, Ordering::SeqCst) {
+        let _ = TUI::done();
+    }
 }
 
 impl TUI {
@@ -32,10 +65,10 @@ impl TUI {
         }
     }
 
-    pub fn run(&mut self, app: App) -> Result<(), std::io::Error> {
+    pub async fn run(&mut self, app: App) -> Result<(), std::io::Error> {
         Self::init()?;
-        self.event_loop(app)?; // TODO: call done() on error as well
-        Self::done()
+        let _restore = RestoreGuard;
+        self.event_loop(app).await
     }
 
     fn setup_terminal() -> Result<Term, std::io::Error> {
@@ -45,34 +78,115 @@ impl TUI {
 
     fn init() -> Result<(), std::io::Error> {
         enable_raw_mode()?;
-        execute!(stdout(), EnterAlternateScreen)
+        execute!(stdout(), EnterAlternateScreen, EnableMouseCapture)
     }
 
     fn done() -> Result<(), std::io::Error> {
-        execute!(stdout(), LeaveAlternateScreen)?;
+        execute!(stdout(), DisableMouseCapture, LeaveAlternateScreen, Show)?;
         disable_raw_mode()
     }
 
-    fn event_loop(&mut self, mut app: App) -> Result<(), std::io::Error> {
+    /// Stop the process like Ctrl-Z does outside raw mode, and take the terminal back when the
+    /// shell resumes it.
+    #[cfg(unix)]
+    fn suspend(&mut self) -> Result<(), std::io::Error> {
+        Self::done()?;
+        signal_hook::low_level::raise(signal_hook::consts::SIGTSTP)?;
+        Self::init()?;
+        // The shell drew over the last frame.
+        self.terminal.clear()
+    }
+
+    #[cfg(not(unix))]
+    fn suspend(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    /// Wait for terminal events, socket events and ticks, and redraw once whatever woke the
+    /// loop up has been handled.
+    async fn event_loop(&mut self, mut app: App) -> Result<(), std::io::Error> {
+        let mut events = EventStream::new();
+        let mut tick = time::interval(TICK);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut shutdown = ShutdownSignals::new()?;
+        let mut dirty = true;
+
         loop {
-            app.receive_socket_events();
-            self.terminal.draw(|f| app.render(f))?;
-            if poll(Duration::from_secs(0))? {
-                if let Event::Key(e) = event::read()? {
-                    let response = match e {
-                        KeyEvent {
-                            code: KeyCode::Char('c'),
-                            modifiers: KeyModifiers::CONTROL,
-                            ..
-                        } => Some(TUIAction::Quit),
-                        e => app.handle_key(e),
-                    };
-                    match response {
-                        Some(TUIAction::Quit) => break Ok(()),
-                        None => {}
+            if dirty {
+                self.terminal.draw(|f| app.render(f))?;
+                dirty = false;
+            }
+
+            tokio::select! {
+                event = events.next() => match event {
+                    Some(Ok(Event::Key(e))) => {
+                        match app.handle_key(e) {
+                            Some(TUIAction::Quit) => break Ok(()),
+                            Some(TUIAction::Suspend) => {
+                                self.suspend()?;
+                                dirty = true;
+                            }
+                            None => dirty = true,
+                        }
+                    }
+                    // Moving the mouse changes nothing on screen.
+                    Some(Ok(Event::Mouse(e))) if e.kind != MouseEventKind::Moved => {
+                        match app.handle_mouse(e) {
+                            Some(TUIAction::Quit) => break Ok(()),
+                            Some(TUIAction::Suspend) => self.suspend()?,
+                            None => {}
+                        }
+                        dirty = true;
                     }
-                }
+                    Some(Ok(Event::Resize(..))) => dirty = true,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => break Err(e),
+                    // The terminal is gone.
+                    None => break Ok(()),
+                },
+                () = app.receive() => dirty = true,
+                _ = tick.tick() => dirty = app.changes_with_time(),
+                () = shutdown.recv() => break Ok(()),
             }
         }
     }
 }
+
+/// SIGTERM and SIGHUP, which end the app like quitting does.
+#[cfg(unix)]
+struct ShutdownSignals {
+    terminate: tokio::signal::unix::Signal,
+    hangup: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl ShutdownSignals {
+    fn new() -> Result<Self, std::io::Error> {
+        use tokio::signal::unix::{signal, SignalKind};
+        Ok(Self {
+            terminate: signal(SignalKind::terminate())?,
+            hangup: signal(SignalKind::hangup())?,
+        })
+    }
+
+    async fn recv(&mut self) {
+        tokio::select! {
+            _ = self.terminate.recv() => {}
+            _ = self.hangup.recv() => {}
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct ShutdownSignals;
+
+#[cfg(not(unix))]
+impl ShutdownSignals {
+    fn new() -> Result<Self, std::io::Error> {
+        Ok(Self)
+    }
+
+    async fn recv(&mut self) {
+        std::future::pending().await
+    }
+}
@@ -1,3 +1,5 @@
+use crate::keymap::{Action, Context, Keymap};
+use crate::theme::Theme;
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     prelude::*,
@@ -61,16 +63,16 @@ impl TimeUnit {
 }
 
 impl<'a> ExpireAtWidget<'a> {
-    pub fn new(input_style: Style, cursor_style: Style) -> Self {
+    pub fn new(theme: &Theme) -> Self {
         Self {
             time: String::from("0"),
             unit: TimeUnit::Minute,
             cursor_position: 1,
             block: None,
-            input_style,
-            cursor_style,
-            selected_style: Style::default().reversed(),
-            unselected_style: Style::default(),
+            input_style: theme.text,
+            cursor_style: theme.cursor,
+            selected_style: theme.highlight,
+            unselected_style: theme.text,
         }
     }
 
@@ -80,7 +82,7 @@ impl<'a> ExpireAtWidget<'a> {
     }
 
     pub fn time(&self) -> ExpireAt {
-        let int_time: usize = self.time.parse().unwrap();
+        let int_time: usize = self.time.parse().unwrap_or(0);
         ExpireAt {
             time: int_time,
             unit: self.unit.clone(),
@@ -111,19 +113,17 @@ impl<'a> ExpireAtWidget<'a> {
         }
     }
 
-    pub fn handle_key(&mut self, e: KeyEvent) {
-        match e.code {
-            KeyCode::Left => self.move_cursor_left(),
-            KeyCode::Right => self.move_cursor_right(),
-            KeyCode::Char('l') => self.unit = self.unit.next(),
-            KeyCode::Char('h') => self.unit = self.unit.prev(),
-            KeyCode::Char(c) => {
-                if c.is_ascii_digit() {
-                    self.insert_char(c);
-                }
-            }
-            KeyCode::Backspace => self.delete_char(),
-            _ => {}
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) {
+        match keymap.action(&[Context::TextInput], &e) {
+            Some(Action::Left) => self.move_cursor_left(),
+            Some(Action::Right) => self.move_cursor_right(),
+            Some(Action::NextUnit) => self.unit = self.unit.next(),
+            Some(Action::PreviousUnit) => self.unit = self.unit.prev(),
+            Some(Action::DeleteChar) => self.delete_char(),
+            _ => match e.code {
+                KeyCode::Char(c) if c.is_ascii_digit() => self.insert_char(c),
+                _ => {}
+            },
         }
     }
 }
@@ -1,3 +1,5 @@
+use crate::keymap::{Action, Context, Key, Keymap};
+use crate::theme::Theme;
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     prelude::*,
@@ -11,17 +13,20 @@ pub struct InputWidget<'a> {
     input_style: Style,
     cursor_style: Style,
     block: Option<Block<'a>>,
+    /// Shown instead of every character, for secrets.
+    mask: Option<char>,
 }
 
 impl<'a> InputWidget<'a> {
-    pub fn new(content: String, input_style: Style, cursor_style: Style) -> Self {
+    pub fn new(content: String, theme: &Theme) -> Self {
         let content_len = content.len();
         Self {
             content,
             cursor_position: content_len,
             block: None,
-            input_style,
-            cursor_style,
+            mask: None,
+            input_style: theme.text,
+            cursor_style: theme.cursor,
         }
     }
 
@@ -30,6 +35,11 @@ impl<'a> InputWidget<'a> {
         self
     }
 
+    pub fn masked(mut self, mask: char) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
     pub fn content(&self) -> &str {
         &self.content
     }
@@ -58,13 +68,15 @@ impl<'a> InputWidget<'a> {
         }
     }
 
-    pub fn handle_key(&mut self, e: KeyEvent) {
-        match e.code {
-            KeyCode::Left => self.move_cursor_left(),
-            KeyCode::Right => self.move_cursor_right(),
-            KeyCode::Char(c) => self.insert_char(c),
-            KeyCode::Backspace => self.delete_char(),
-            _ => {}
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) {
+        match keymap.action(&[Context::TextInput], &e) {
+            Some(Action::Left) => self.move_cursor_left(),
+            Some(Action::Right) => self.move_cursor_right(),
+            Some(Action::DeleteChar) => self.delete_char(),
+            _ => match e.code {
+                KeyCode::Char(c) if Key::from(e).is_typed() => self.insert_char(c),
+                _ => {}
+            },
         }
     }
 }
@@ -84,7 +96,13 @@ impl Widget for &InputWidget<'_> {
         let cursor_x = self.cursor_position as u16;
 
         // Render the text content
-        buf.set_string(area.x, area.y, &self.content, self.input_style);
+        match self.mask {
+            Some(mask) => {
+                let masked: String = self.content.chars().map(|_| mask).collect();
+                buf.set_string(area.x, area.y, masked, self.input_style);
+            }
+            None => buf.set_string(area.x, area.y, &self.content, self.input_style),
+        }
 
         // Render the cursor
         if cursor_x < area.width {
@@ -0,0 +1,74 @@
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+use std::time::{Duration, Instant};
+
+/// Longest time between the two clicks of a double click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+
+/// Lines scrolled by a notch of the wheel in content panes.
+pub const SCROLL_LINES: u16 = 3;
+
+/// What the screens do with a mouse event.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mouse {
+    Click(Position),
+    /// Second click on the same cell.
+    DoubleClick(Position),
+    ScrollUp(Position),
+    ScrollDown(Position),
+}
+
+impl Mouse {
+    pub fn position(self) -> Position {
+        match self {
+            Mouse::Click(position)
+            | Mouse::DoubleClick(position)
+            | Mouse::ScrollUp(position)
+            | Mouse::ScrollDown(position) => position,
+        }
+    }
+
+    /// Whether the event happened over `area`.
+    pub fn is_in(self, area: Rect) -> bool {
+        area.contains(self.position())
+    }
+}
+
+/// Turns the terminal's mouse events into `Mouse` events, telling double clicks from single
+/// ones. Moves, drags, releases and other buttons are dropped.
+#[derive(Default)]
+pub struct MouseTracker {
+    last_click: Option<(Instant, Position)>,
+}
+
+impl MouseTracker {
+    pub fn mouse(&mut self, e: MouseEvent) -> Option<Mouse> {
+        let position = Position::new(e.column, e.row);
+        match e.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let double = self
+                    .last_click
+                    .is_some_and(|(at, last)| last == position && at.elapsed() < DOUBLE_CLICK);
+                if double {
+                    self.last_click = None;
+                    Some(Mouse::DoubleClick(position))
+                } else {
+                    self.last_click = Some((Instant::now(), position));
+                    Some(Mouse::Click(position))
+                }
+            }
+            MouseEventKind::ScrollUp => Some(Mouse::ScrollUp(position)),
+            MouseEventKind::ScrollDown => Some(Mouse::ScrollDown(position)),
+            _ => None,
+        }
+    }
+}
+
+/// Index of the item under `position` in a list drawn in `area`, without its borders, scrolled
+/// to `offset` and whose items are `height` lines high.
+pub fn item_at(area: Rect, offset: usize, height: u16, position: Position) -> Option<usize> {
+    if !area.contains(position) || height == 0 {
+        return None;
+    }
+    Some(offset + usize::from((position.y - area.y) / height))
+}
@@ -1,3 +1,5 @@
+use crate::keymap::{Action, Context, Key, Keymap};
+use crate::theme::Theme;
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     prelude::*,
@@ -9,12 +11,14 @@ pub struct MultilineInput<'a> {
     lines: Vec<String>,
     cursor_x: usize,
     cursor_y: usize,
+    input_style: Style,
+    cursor_style: Style,
     block: Option<Block<'a>>,
 }
 
 // TODO: add scroll for large input
 impl<'a> MultilineInput<'a> {
-    pub fn new(content: String) -> Self {
+    pub fn new(content: String, theme: &Theme) -> Self {
         let lines = if content.is_empty() {
             vec![content]
         } else {
@@ -30,6 +34,8 @@ impl<'a> MultilineInput<'a> {
             lines,
             cursor_x,
             cursor_y,
+            input_style: theme.text,
+            cursor_style: theme.cursor,
             block: None,
         }
     }
@@ -105,16 +111,18 @@ impl<'a> MultilineInput<'a> {
         }
     }
 
-    pub fn handle_key(&mut self, e: KeyEvent) {
-        match e.code {
-            KeyCode::Up => self.move_cursor_up(),
-            KeyCode::Down => self.move_cursor_down(),
-            KeyCode::Left => self.move_cursor_left(),
-            KeyCode::Right => self.move_cursor_right(),
-            KeyCode::Enter => self.insert_char('\n'),
-            KeyCode::Char(c) => self.insert_char(c),
-            KeyCode::Backspace => self.delete_char(),
-            _ => {}
+    pub fn handle_key(&mut self, keymap: &Keymap, e: KeyEvent) {
+        match keymap.action(&[Context::TextInput], &e) {
+            Some(Action::Up) => self.move_cursor_up(),
+            Some(Action::Down) => self.move_cursor_down(),
+            Some(Action::Left) => self.move_cursor_left(),
+            Some(Action::Right) => self.move_cursor_right(),
+            Some(Action::Newline) => self.insert_char('\n'),
+            Some(Action::DeleteChar) => self.delete_char(),
+            _ => match e.code {
+                KeyCode::Char(c) if Key::from(e).is_typed() => self.insert_char(c),
+                _ => {}
+            },
         }
     }
 }
@@ -135,7 +143,7 @@ impl Widget for &MultilineInput<'_> {
         for (i, line) in self.lines.iter().take(visible_lines).enumerate() {
             let y = inner_area.y + i as u16;
             if y < inner_area.y + inner_area.height {
-                buf.set_string(inner_area.x, y, line, Style::default().fg(Color::White));
+                buf.set_string(inner_area.x, y, line, self.input_style);
             }
         }
 
@@ -143,7 +151,7 @@ impl Widget for &MultilineInput<'_> {
         if self.cursor_x as u16 <= inner_area.width {
             buf.set_style(
                 Rect::new(inner_area.x + self.cursor_x as u16, cursor_y, 1, 1),
-                Style::default().fg(Color::Black).bg(Color::White),
+                self.cursor_style,
             );
         }
     }